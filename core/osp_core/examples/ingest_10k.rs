@@ -36,39 +36,44 @@ fn main() {
         "Database indexing strategies",
     ];
 
-    for i in 0..10_000 {
-        let kind = match i % 4 {
-            0 => ItemKind::Text,
-            1 => ItemKind::Rtf,
-            2 => ItemKind::Image,
-            _ => ItemKind::File,
-        };
-
-        let content_ref = if matches!(kind, ItemKind::Text | ItemKind::Rtf) {
-            let base = sample_texts[i % sample_texts.len()];
-            format!("{} - item {}", base, i)
-        } else {
-            format!("/path/to/resource_{}.dat", i)
-        };
-
-        let item = NewItem {
-            kind,
-            content_ref,
-            source_app: Some("perf_test".to_string()),
-            created_at: 1_000_000 + i as i64,
-            tags: if i % 5 == 0 {
-                vec!["important".to_string()]
+    let items: Vec<NewItem> = (0..10_000)
+        .map(|i| {
+            let kind = match i % 4 {
+                0 => ItemKind::Text,
+                1 => ItemKind::Rtf,
+                2 => ItemKind::Image,
+                _ => ItemKind::File,
+            };
+
+            let content_ref = if matches!(kind, ItemKind::Text | ItemKind::Rtf) {
+                let base = sample_texts[i % sample_texts.len()];
+                format!("{} - item {}", base, i)
             } else {
-                vec![]
-            },
-        };
-
-        core.add_item(item).expect("Failed to add item");
+                format!("/path/to/resource_{}.dat", i)
+            };
+
+            NewItem {
+                kind,
+                content_ref,
+                source_app: Some("perf_test".to_string()),
+                created_at: 1_000_000 + i as i64,
+                tags: if i % 5 == 0 {
+                    vec!["important".to_string()]
+                } else {
+                    vec![]
+                },
+            }
+        })
+        .collect();
 
-        if (i + 1) % 1000 == 0 {
-            print!(".");
-            std::io::Write::flush(&mut std::io::stdout()).unwrap();
+    // Insert in batches of 1000, one transaction each
+    for chunk in items.chunks(1000) {
+        for result in core.add_items(chunk.to_vec()) {
+            result.expect("Failed to add item");
         }
+
+        print!(".");
+        std::io::Write::flush(&mut std::io::stdout()).unwrap();
     }
 
     let ingest_duration = start.elapsed();
@@ -0,0 +1,5 @@
+-- Soft delete: trashed items keep their row (and FTS entry) until purged
+
+ALTER TABLE items ADD COLUMN deleted_at INTEGER;
+
+CREATE INDEX idx_items_deleted_at ON items(deleted_at) WHERE deleted_at IS NOT NULL;
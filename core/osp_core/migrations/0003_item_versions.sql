@@ -0,0 +1,17 @@
+-- Editable content: stored dedupe keys and a history of previous content
+
+ALTER TABLE items ADD COLUMN dedupe_key TEXT;
+
+-- Rows written before this migration were deduplicated on their raw content
+UPDATE items SET dedupe_key = content_ref;
+
+CREATE INDEX idx_items_dedupe_key ON items(kind, dedupe_key);
+
+CREATE TABLE item_versions(
+  id INTEGER PRIMARY KEY,
+  item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+  version INTEGER NOT NULL,
+  content_ref TEXT NOT NULL,
+  replaced_at INTEGER NOT NULL,
+  UNIQUE(item_id, version)
+);
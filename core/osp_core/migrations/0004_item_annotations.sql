@@ -0,0 +1,18 @@
+-- User-defined titles and notes, indexed as separate FTS columns
+
+ALTER TABLE items ADD COLUMN title TEXT;
+ALTER TABLE items ADD COLUMN note TEXT;
+
+-- FTS5 tables can't gain columns, so rebuild items_fts
+CREATE VIRTUAL TABLE items_fts_new USING fts5(
+  item_id UNINDEXED,
+  text,
+  title,
+  note,
+  tokenize='unicode61 remove_diacritics 2'
+);
+
+INSERT INTO items_fts_new (item_id, text) SELECT item_id, text FROM items_fts;
+
+DROP TABLE items_fts;
+ALTER TABLE items_fts_new RENAME TO items_fts;
@@ -0,0 +1,6 @@
+-- Snippets: text items reachable through a trigger abbreviation
+
+CREATE TABLE snippets(
+  item_id INTEGER PRIMARY KEY REFERENCES items(id) ON DELETE CASCADE,
+  abbreviation TEXT NOT NULL UNIQUE
+);
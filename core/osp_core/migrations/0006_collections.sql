@@ -0,0 +1,17 @@
+-- Collections: named, manually ordered groups of items
+
+CREATE TABLE collections(
+  id INTEGER PRIMARY KEY,
+  name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+  created_at INTEGER NOT NULL
+);
+
+-- An item can belong to several collections, with its own position in each
+CREATE TABLE collection_items(
+  collection_id INTEGER NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
+  item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+  position INTEGER NOT NULL,
+  PRIMARY KEY (collection_id, item_id)
+);
+
+CREATE INDEX idx_collection_items_item ON collection_items(item_id);
@@ -0,0 +1,6 @@
+-- Quick-paste slots 1-9, each bound to at most one item
+
+CREATE TABLE slots(
+  slot INTEGER PRIMARY KEY CHECK (slot BETWEEN 1 AND 9),
+  item_id INTEGER NOT NULL UNIQUE REFERENCES items(id) ON DELETE CASCADE
+);
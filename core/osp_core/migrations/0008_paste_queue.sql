@@ -0,0 +1,12 @@
+-- Paste queue: items waiting to be pasted in order
+
+CREATE TABLE paste_queue(
+  seq INTEGER PRIMARY KEY AUTOINCREMENT,
+  item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE
+);
+
+-- Persistent key/value state, JSON-encoded values
+CREATE TABLE settings(
+  key TEXT PRIMARY KEY,
+  value TEXT NOT NULL
+);
@@ -0,0 +1,11 @@
+-- Paste events: one row each time an item is pasted from Haste
+
+CREATE TABLE uses(
+  id INTEGER PRIMARY KEY,
+  item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+  target_app TEXT,
+  used_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_uses_item ON uses(item_id, used_at);
+CREATE INDEX idx_uses_target_app ON uses(target_app, item_id);
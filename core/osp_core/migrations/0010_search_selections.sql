@@ -0,0 +1,12 @@
+-- Learned ranking: which item the user picked for a (normalized) query.
+-- weight decays over time; see ranking.rs.
+
+CREATE TABLE search_selections(
+  query TEXT NOT NULL,
+  item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+  weight REAL NOT NULL,
+  updated_at INTEGER NOT NULL,
+  PRIMARY KEY (query, item_id)
+);
+
+CREATE INDEX idx_search_selections_item ON search_selections(item_id);
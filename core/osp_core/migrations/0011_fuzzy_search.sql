@@ -0,0 +1,4 @@
+-- Fuzzy search support
+
+-- Terms of items_fts, used to find near-misses of misspelled query words
+CREATE VIRTUAL TABLE items_fts_vocab USING fts5vocab(items_fts, row);
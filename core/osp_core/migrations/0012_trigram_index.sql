@@ -0,0 +1,29 @@
+-- Trigram index for CJK text and substring search
+
+-- Rows use the item ID as rowid. Unlike items_fts, the table is
+-- maintained by triggers on items.
+CREATE VIRTUAL TABLE items_trigram USING fts5(
+  text,
+  title,
+  note,
+  tokenize='trigram case_sensitive 0 remove_diacritics 1'
+);
+
+INSERT INTO items_trigram (rowid, text, title, note)
+SELECT id, CASE WHEN kind IN ('text', 'rtf') THEN content_ref END, title, note
+FROM items;
+
+CREATE TRIGGER items_trigram_insert AFTER INSERT ON items BEGIN
+  INSERT INTO items_trigram (rowid, text, title, note)
+  VALUES (new.id, CASE WHEN new.kind IN ('text', 'rtf') THEN new.content_ref END, new.title, new.note);
+END;
+
+CREATE TRIGGER items_trigram_update AFTER UPDATE OF kind, content_ref, title, note ON items BEGIN
+  DELETE FROM items_trigram WHERE rowid = old.id;
+  INSERT INTO items_trigram (rowid, text, title, note)
+  VALUES (new.id, CASE WHEN new.kind IN ('text', 'rtf') THEN new.content_ref END, new.title, new.note);
+END;
+
+CREATE TRIGGER items_trigram_delete AFTER DELETE ON items BEGIN
+  DELETE FROM items_trigram WHERE rowid = old.id;
+END;
@@ -0,0 +1,8 @@
+-- Saved searches: named queries rerun on demand, like smart collections
+
+CREATE TABLE saved_searches(
+  id INTEGER PRIMARY KEY,
+  name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+  query TEXT NOT NULL,  -- SearchQuery as JSON, operators unparsed in "text"
+  created_at INTEGER NOT NULL
+);
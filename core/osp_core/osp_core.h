@@ -16,6 +16,9 @@ typedef struct {
     int64_t created_at;
     int32_t pinned;         /* 0=false, 1=true */
     char *tags_json;        /* JSON array as string */
+    int64_t deleted_at;     /* 0 if not in the trash */
+    char *title;            /* NULL if None */
+    char *note;             /* NULL if None */
 } CItem;
 
 /* C-compatible array of items */
@@ -26,6 +29,10 @@ typedef struct {
 
 /* Core management */
 CoreHandle *core_new(const char *db_path, const char *blobs_dir);
+CoreHandle *core_new_with_config(const char *db_path, const char *blobs_dir,
+                                 const char *config_json);
+CoreHandle *core_new_with_recovery(const char *db_path, const char *blobs_dir,
+                                   const char *config_json, char **report_out);
 void core_free(CoreHandle *handle);
 
 /* Item operations */
@@ -33,10 +40,113 @@ int64_t core_add_item(CoreHandle *handle, int32_t kind, const char *content_ref,
                       const char *source_app, int64_t created_at);
 int64_t core_dedupe_insert(CoreHandle *handle, int32_t kind, const char *content_ref,
                            const char *source_app, int64_t created_at);
+char *core_add_items_json(CoreHandle *handle, const char *items_json, int32_t dedupe);
+int32_t core_enqueue(CoreHandle *handle, int32_t kind, const char *content_ref,
+                     const char *source_app, int64_t created_at);
+int32_t core_flush(CoreHandle *handle);
 CItemArray *core_search(CoreHandle *handle, const char *query, uint32_t limit);
+CItemArray *core_search_query(CoreHandle *handle, const char *query_json);
+char *core_parse_query(const char *query);
+char *core_facets_json(CoreHandle *handle, const char *query_json);
+CItemArray *core_search_mode(CoreHandle *handle, const char *query,
+                             int32_t mode, uint32_t limit); /* 0=standard, 1=fuzzy, 2=regex */
+CItemArray *core_search_in_collection(CoreHandle *handle, const char *query,
+                                      int64_t collection_id, uint32_t limit);
 CItem *core_get_item(CoreHandle *handle, int64_t id);
 int32_t core_delete_item(CoreHandle *handle, int64_t id);
 int32_t core_pin_item(CoreHandle *handle, int64_t id, int32_t pinned);
+int32_t core_annotate_item(CoreHandle *handle, int64_t id, const char *title, const char *note);
+
+/* Editing */
+int32_t core_update_content(CoreHandle *handle, int64_t id, const char *new_text);
+char *core_versions_json(CoreHandle *handle, int64_t id);
+int32_t core_revert_item(CoreHandle *handle, int64_t id, int64_t version);
+
+/* Trash */
+int32_t core_delete_item_permanently(CoreHandle *handle, int64_t id);
+int64_t core_clear_history(CoreHandle *handle);
+CItemArray *core_trash_list(CoreHandle *handle, uint32_t limit);
+int32_t core_restore_item(CoreHandle *handle, int64_t id);
+int64_t core_empty_trash(CoreHandle *handle);
+int64_t core_purge_expired_trash(CoreHandle *handle);
+int64_t core_trash_grace_period_ms(CoreHandle *handle);
+
+/* Snippets */
+int64_t core_create_snippet(CoreHandle *handle, const char *name, const char *abbreviation,
+                            const char *template_text);
+int32_t core_set_snippet_abbreviation(CoreHandle *handle, int64_t id, const char *abbreviation);
+int32_t core_remove_snippet(CoreHandle *handle, int64_t id);
+char *core_snippets_json(CoreHandle *handle);
+char *core_expand_snippet(CoreHandle *handle, const char *abbreviation, int64_t *cursor_out);
+char *core_render_template(CoreHandle *handle, int64_t id, int64_t *cursor_out);
+
+/* Collections */
+int64_t core_create_collection(CoreHandle *handle, const char *name);
+int32_t core_rename_collection(CoreHandle *handle, int64_t id, const char *name);
+int32_t core_delete_collection(CoreHandle *handle, int64_t id);
+char *core_collections_json(CoreHandle *handle);
+char *core_item_collections_json(CoreHandle *handle, int64_t item_id);
+CItemArray *core_collection_items(CoreHandle *handle, int64_t id);
+int32_t core_collection_add_item(CoreHandle *handle, int64_t id, int64_t item_id, int64_t position);
+int32_t core_collection_remove_item(CoreHandle *handle, int64_t id, int64_t item_id);
+int32_t core_collection_move_item(CoreHandle *handle, int64_t id, int64_t item_id, int64_t position);
+
+/* Saved searches */
+int64_t core_save_search(CoreHandle *handle, const char *name, const char *query_json);
+int32_t core_update_saved_search(CoreHandle *handle, int64_t id, const char *name,
+                                 const char *query_json);
+int32_t core_delete_saved_search(CoreHandle *handle, int64_t id);
+char *core_saved_searches_json(CoreHandle *handle);
+CItemArray *core_run_saved_search(CoreHandle *handle, int64_t id, uint32_t page);
+
+/* Quick-paste slots (1-9) */
+int32_t core_assign_slot(CoreHandle *handle, int32_t slot, int64_t id);
+int32_t core_clear_slot(CoreHandle *handle, int32_t slot);
+CItem *core_slot_item(CoreHandle *handle, int32_t slot);
+char *core_slots_json(CoreHandle *handle);
+
+/* Usage */
+int32_t core_record_use(CoreHandle *handle, int64_t id, const char *target_app);
+CItemArray *core_suggest(CoreHandle *handle, const char *target_app, uint32_t limit);
+int32_t core_record_selection(CoreHandle *handle, const char *query, int64_t item_id);
+int64_t core_reset_learned_ranking(CoreHandle *handle);
+
+/* Paste queue */
+char *core_paste_queue_json(CoreHandle *handle);
+int32_t core_set_paste_queue_json(CoreHandle *handle, const char *options_json);
+int32_t core_queue_push(CoreHandle *handle, int64_t id);
+CItem *core_queue_pop(CoreHandle *handle);
+CItem *core_queue_peek(CoreHandle *handle);
+CItemArray *core_queue_items(CoreHandle *handle);
+int64_t core_queue_clear(CoreHandle *handle);
+
+/* Statistics */
+char *core_stats_json(CoreHandle *handle);
+
+/* Maintenance */
+/* step: 0=integrity check, 1=FTS integrity check, 2=FTS rebuild, 3=FTS optimize,
+   4=vacuum, 5=analyze, 6=WAL checkpoint */
+typedef void (*MaintenanceProgressFn)(int32_t step, int32_t index, int32_t total,
+                                      void *user_data);
+char *core_maintenance_json(CoreHandle *handle, const char *options_json,
+                            MaintenanceProgressFn progress, void *user_data);
+
+/* Backup and restore */
+/* stage: 0=database pages, 1=blob files */
+typedef void (*BackupProgressFn)(int32_t stage, int64_t done, int64_t total, void *user_data);
+char *core_backup_to(CoreHandle *handle, const char *path, const char *options_json,
+                     BackupProgressFn progress, void *user_data);
+char *core_backup_rotating(CoreHandle *handle, const char *dir, uint32_t keep,
+                           const char *options_json, BackupProgressFn progress, void *user_data);
+char *core_list_backups_json(CoreHandle *handle, const char *dir);
+char *core_restore_from(CoreHandle *handle, const char *path, BackupProgressFn progress,
+                        void *user_data);
+
+/* Export */
+char *core_export(CoreHandle *handle, const char *path, const char *options_json);
+
+/* Import */
+char *core_import(CoreHandle *handle, const char *path, const char *options_json);
 
 /* Memory management */
 void item_free(CItem *item);
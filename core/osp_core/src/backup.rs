@@ -0,0 +1,442 @@
+//! Online backup and restore.
+//!
+//! Copying the database file while it is open in WAL mode can produce a
+//! torn copy, so backups go through SQLite's online backup API, which
+//! copies a consistent snapshot page by page while captures continue.
+//!
+//! A backup is a single SQLite file. With blobs included, it also holds a
+//! `backup_blobs` table with the files of the blobs directory; restoring
+//! writes them back and drops the table.
+
+use anyhow::{Context, Result};
+use rusqlite::backup::{Backup, StepResult};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, Instant};
+use time::macros::format_description;
+
+use crate::db::{Database, SCHEMA_VERSION};
+use crate::recovery::{self, with_suffix};
+use crate::{maintenance, now_local};
+
+/// Table holding the blobs in a backup file.
+pub const BLOBS_TABLE: &str = "backup_blobs";
+
+/// File name prefix of rotating backups.
+pub const ROTATING_PREFIX: &str = "backup-";
+
+/// Pages copied per backup step, between progress reports.
+const PAGES_PER_STEP: i32 = 256;
+
+/// Wait before retrying a step that found the database locked.
+const BUSY_PAUSE: Duration = Duration::from_millis(10);
+
+/// What to put in a backup.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupOptions {
+    /// Store the files of the blobs directory in the backup too.
+    pub include_blobs: bool,
+}
+
+/// What a backup or restore is copying.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupStage {
+    /// Database pages.
+    Database,
+    /// Files of the blobs directory.
+    Blobs,
+}
+
+impl BackupStage {
+    /// The stage's code in backup progress callbacks.
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+}
+
+/// Reported as a backup or restore advances.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupProgress {
+    pub stage: BackupStage,
+    /// Pages or files copied so far.
+    pub done: u64,
+    /// Pages or files to copy in this stage.
+    pub total: u64,
+}
+
+/// A finished backup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupReport {
+    pub path: PathBuf,
+    /// Size of the backup file.
+    pub bytes: u64,
+    /// Blob files included.
+    pub blobs: usize,
+    /// Rotating backups deleted to keep the configured number.
+    pub removed: Vec<PathBuf>,
+    pub duration_ms: i64,
+}
+
+/// A finished restore.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestoreReport {
+    /// Schema version of the backup; older backups are migrated.
+    pub schema_version: i32,
+    /// Items in the restored database, trash included.
+    pub items: i64,
+    /// Blob files written to the blobs directory.
+    pub blobs: usize,
+    pub duration_ms: i64,
+}
+
+/// Back up the database `src` (and optionally `blobs_dir`) to `path`.
+///
+/// The backup is written next to `path` and renamed into place once
+/// complete, so an interrupted backup never leaves a partial file at
+/// `path`.
+pub fn backup(
+    src: &Connection,
+    blobs_dir: &Path,
+    path: &Path,
+    options: &BackupOptions,
+    progress: &mut impl FnMut(BackupProgress),
+) -> Result<BackupReport> {
+    let started = Instant::now();
+    let partial = with_suffix(path, ".partial");
+    if partial.exists() {
+        std::fs::remove_file(&partial)
+            .with_context(|| format!("Failed to remove {:?}", partial))?;
+    }
+
+    let mut blobs = 0;
+    {
+        let mut dst = Connection::open(&partial)
+            .with_context(|| format!("Failed to create backup at {:?}", partial))?;
+        copy(src, &mut dst, progress)?;
+
+        // A single self-contained file, whatever the source's journal mode
+        dst.execute_batch("PRAGMA journal_mode = DELETE")
+            .context("Failed to set backup journal mode")?;
+
+        if options.include_blobs {
+            blobs = store_blobs(&dst, blobs_dir, progress)?;
+        }
+    }
+
+    std::fs::rename(&partial, path)
+        .with_context(|| format!("Failed to move backup to {:?}", path))?;
+
+    Ok(BackupReport {
+        path: path.to_path_buf(),
+        bytes: std::fs::metadata(path)?.len(),
+        blobs,
+        removed: Vec::new(),
+        duration_ms: started.elapsed().as_millis() as i64,
+    })
+}
+
+/// Path for a new rotating backup in `dir`, named after the current time.
+pub fn rotating_path(dir: &Path) -> Result<PathBuf> {
+    loop {
+        let stamp = now_local()
+            .format(format_description!(
+                "[year][month][day]-[hour][minute][second]-[subsecond digits:3]"
+            ))
+            .context("Failed to format timestamp")?;
+        let path = dir.join(format!("{}{}.db", ROTATING_PREFIX, stamp));
+        if !path.exists() {
+            return Ok(path);
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// Rotating backups in `dir`, newest first.
+pub fn list(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {:?}", dir)),
+    };
+
+    let mut backups = Vec::new();
+    for entry in entries {
+        let path = entry.with_context(|| format!("Failed to read {:?}", dir))?.path();
+        let is_backup = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(ROTATING_PREFIX) && name.ends_with(".db"));
+        if is_backup && path.is_file() {
+            backups.push(path);
+        }
+    }
+
+    // Timestamps in the names sort chronologically
+    backups.sort();
+    backups.reverse();
+    Ok(backups)
+}
+
+/// Delete all but the `keep` newest rotating backups in `dir` and return
+/// the deleted paths.
+pub fn prune(dir: &Path, keep: usize) -> Result<Vec<PathBuf>> {
+    let mut backups = list(dir)?;
+    let removed = backups.split_off(keep.min(backups.len()));
+    for path in &removed {
+        std::fs::remove_file(path).with_context(|| format!("Failed to remove {:?}", path))?;
+    }
+    Ok(removed)
+}
+
+/// Replace the database `dst` with the backup at `path`, and write the
+/// blobs it holds to `blobs_dir`.
+///
+/// The backup is checked before anything is touched: it must be a healthy
+/// database of this app, at a schema version no newer than this build's.
+/// Blobs are written next to `blobs_dir` first and only moved into it once
+/// the database has been replaced.
+pub fn restore(
+    dst: &mut Connection,
+    blobs_dir: &Path,
+    path: &Path,
+    progress: &mut impl FnMut(BackupProgress),
+) -> Result<RestoreReport> {
+    let started = Instant::now();
+    let src = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Failed to open backup at {:?}", path))?;
+    let schema_version = validate(&src)?;
+
+    let staging = with_suffix(blobs_dir, ".restoring");
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)
+            .with_context(|| format!("Failed to remove {:?}", staging))?;
+    }
+    let blobs = match restore_staged(&src, dst, &staging, progress) {
+        Ok(blobs) => blobs,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+    };
+    drop(src);
+    move_blobs(&staging, blobs_dir)?;
+
+    Database::apply_migrations(dst)?;
+    if has_table(dst, BLOBS_TABLE)? {
+        dst.execute_batch(&format!("DROP TABLE {}", BLOBS_TABLE))
+            .context("Failed to drop blobs from restored database")?;
+        maintenance::vacuum(dst)?;
+    }
+
+    let items = dst
+        .query_row("SELECT COUNT(*) FROM items", [], |row| row.get(0))
+        .context("Failed to count restored items")?;
+
+    Ok(RestoreReport {
+        schema_version,
+        items,
+        blobs,
+        duration_ms: started.elapsed().as_millis() as i64,
+    })
+}
+
+/// Write the blobs of `src` to `staging`, then copy it over `dst`.
+fn restore_staged(
+    src: &Connection,
+    dst: &mut Connection,
+    staging: &Path,
+    progress: &mut impl FnMut(BackupProgress),
+) -> Result<usize> {
+    let blobs = if has_table(src, BLOBS_TABLE)? {
+        restore_blobs(src, staging, progress)?
+    } else {
+        0
+    };
+
+    copy(src, dst, progress)?;
+    Ok(blobs)
+}
+
+/// Move the files under `staging` to the same place under `blobs_dir`, and
+/// remove `staging`.
+fn move_blobs(staging: &Path, blobs_dir: &Path) -> Result<()> {
+    if !staging.exists() {
+        return Ok(());
+    }
+
+    for (name, path) in blob_files(staging)? {
+        let target = blobs_dir.join(name);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(&path, &target)
+            .with_context(|| format!("Failed to move {:?} to {:?}", path, target))?;
+    }
+
+    std::fs::remove_dir_all(staging).with_context(|| format!("Failed to remove {:?}", staging))
+}
+
+/// Check that `conn` is a restorable backup and return its schema version.
+fn validate(conn: &Connection) -> Result<i32> {
+    let version: i32 = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .context("Backup is not a valid database")?;
+
+    if version == 0 || !has_table(conn, "items")? {
+        anyhow::bail!("Backup is not a clipboard database");
+    }
+    if version > SCHEMA_VERSION {
+        anyhow::bail!(
+            "Backup is from a newer version (schema {}, this version supports up to {})",
+            version,
+            SCHEMA_VERSION
+        );
+    }
+    if let Some(problem) = recovery::check(conn)? {
+        anyhow::bail!("Backup is damaged: {}", problem);
+    }
+
+    Ok(version)
+}
+
+/// Copy the database `src` over `dst` with the backup API.
+fn copy(
+    src: &Connection,
+    dst: &mut Connection,
+    progress: &mut impl FnMut(BackupProgress),
+) -> Result<()> {
+    let backup = Backup::new(src, dst).context("Failed to start backup")?;
+
+    loop {
+        let step = backup.step(PAGES_PER_STEP).context("Failed to copy database")?;
+        let pages = backup.progress();
+        progress(BackupProgress {
+            stage: BackupStage::Database,
+            done: (pages.pagecount - pages.remaining) as u64,
+            total: pages.pagecount as u64,
+        });
+
+        match step {
+            StepResult::Done => return Ok(()),
+            StepResult::Busy | StepResult::Locked => std::thread::sleep(BUSY_PAUSE),
+            _ => {}
+        }
+    }
+}
+
+fn store_blobs(
+    dst: &Connection,
+    blobs_dir: &Path,
+    progress: &mut impl FnMut(BackupProgress),
+) -> Result<usize> {
+    let files = blob_files(blobs_dir)?;
+
+    dst.execute_batch(&format!(
+        "CREATE TABLE {}(name TEXT PRIMARY KEY, data BLOB NOT NULL)",
+        BLOBS_TABLE
+    ))?;
+    let tx = dst
+        .unchecked_transaction()
+        .context("Failed to begin transaction")?;
+    {
+        let mut insert = tx.prepare(&format!(
+            "INSERT INTO {} (name, data) VALUES (?1, ?2)",
+            BLOBS_TABLE
+        ))?;
+        for (done, (name, path)) in files.iter().enumerate() {
+            let data = std::fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+            insert
+                .execute(params![name, data])
+                .context("Failed to store blob")?;
+            progress(BackupProgress {
+                stage: BackupStage::Blobs,
+                done: done as u64 + 1,
+                total: files.len() as u64,
+            });
+        }
+    }
+    tx.commit().context("Failed to commit transaction")?;
+
+    Ok(files.len())
+}
+
+fn restore_blobs(
+    src: &Connection,
+    blobs_dir: &Path,
+    progress: &mut impl FnMut(BackupProgress),
+) -> Result<usize> {
+    let total: u64 = src.query_row(&format!("SELECT COUNT(*) FROM {}", BLOBS_TABLE), [], |row| {
+        row.get(0)
+    })?;
+
+    let mut stmt = src.prepare(&format!("SELECT name, data FROM {} ORDER BY name", BLOBS_TABLE))?;
+    let mut rows = stmt.query([])?;
+    let mut done = 0;
+    while let Some(row) = rows.next().context("Failed to read blobs from backup")? {
+        let name: String = row.get(0)?;
+        let data: Vec<u8> = row.get(1)?;
+
+        // Names are relative paths inside the blobs directory
+        let relative = Path::new(&name);
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            anyhow::bail!("Backup contains an invalid blob name {:?}", name);
+        }
+
+        let path = blobs_dir.join(relative);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, data).with_context(|| format!("Failed to write {:?}", path))?;
+
+        done += 1;
+        progress(BackupProgress {
+            stage: BackupStage::Blobs,
+            done,
+            total,
+        });
+    }
+
+    Ok(done as usize)
+}
+
+/// Files under `dir`, with their path relative to it using `/` separators.
+fn blob_files(dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    fn walk(dir: &Path, prefix: &str, files: &mut Vec<(String, PathBuf)>) -> Result<()> {
+        for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read {:?}", dir))? {
+            let entry = entry.with_context(|| format!("Failed to read {:?}", dir))?;
+            let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                walk(&entry.path(), &format!("{}/", name), files)?;
+            } else if file_type.is_file() {
+                files.push((name, entry.path()));
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    if dir.exists() {
+        walk(dir, "", &mut files)?;
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn has_table(conn: &Connection, name: &str) -> Result<bool> {
+    let found = conn
+        .query_row(
+            "SELECT 1 FROM sqlite_schema WHERE type = 'table' AND name = ?1",
+            params![name],
+            |_| Ok(()),
+        )
+        .optional()
+        .context("Failed to read schema")?;
+    Ok(found.is_some())
+}
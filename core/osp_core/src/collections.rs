@@ -0,0 +1,295 @@
+//! Collections: named groups of items with a manual order.
+//!
+//! An item can belong to any number of collections. Positions are kept
+//! dense (0, 1, 2, ...) by rewriting the order after every change;
+//! collections are small enough for that to be cheap. Trashed items keep
+//! their place, but positions passed in count only the items listed by
+//! [`items`], as a frontend shows them.
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::db::{Database, ITEM_COLUMNS};
+use crate::models::Item;
+
+/// A named collection of items.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collection {
+    pub id: i64,
+    pub name: String,
+    pub created_at: i64,
+    /// Number of items in the collection, not counting trashed ones.
+    pub item_count: i64,
+}
+
+const COLLECTION_COLUMNS: &str = "c.id, c.name, c.created_at,
+    (SELECT COUNT(*) FROM collection_items ci
+     INNER JOIN items i ON i.id = ci.item_id
+     WHERE ci.collection_id = c.id AND i.deleted_at IS NULL)";
+
+fn validate_name(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() {
+        anyhow::bail!("Collection name must not be empty");
+    }
+    Ok(name)
+}
+
+/// Create a collection and return its ID. Names are unique, ignoring case.
+pub fn create(conn: &Connection, name: &str, created_at: i64) -> Result<i64> {
+    let name = validate_name(name)?;
+    ensure_name_free(conn, name, None)?;
+
+    conn.prepare_cached("INSERT INTO collections (name, created_at) VALUES (?1, ?2)")?
+        .execute(params![name, created_at])
+        .context("Failed to create collection")?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+/// Rename a collection.
+pub fn rename(conn: &Connection, id: i64, name: &str) -> Result<()> {
+    let name = validate_name(name)?;
+    ensure_name_free(conn, name, Some(id))?;
+
+    let rows = conn
+        .prepare_cached("UPDATE collections SET name = ?1 WHERE id = ?2")?
+        .execute(params![name, id])
+        .context("Failed to rename collection")?;
+
+    if rows == 0 {
+        anyhow::bail!("Collection not found");
+    }
+
+    Ok(())
+}
+
+/// Delete a collection. Its items are kept.
+pub fn delete(conn: &Connection, id: i64) -> Result<()> {
+    let rows = conn
+        .prepare_cached("DELETE FROM collections WHERE id = ?1")?
+        .execute(params![id])
+        .context("Failed to delete collection")?;
+
+    if rows == 0 {
+        anyhow::bail!("Collection not found");
+    }
+
+    Ok(())
+}
+
+/// List all collections, ordered by name.
+pub fn list(conn: &Connection) -> Result<Vec<Collection>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM collections c ORDER BY c.name",
+        COLLECTION_COLUMNS
+    ))?;
+
+    let collections = stmt
+        .query_map([], row_to_collection)?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to fetch collections")?;
+
+    Ok(collections)
+}
+
+/// List the collections an item belongs to, ordered by name.
+pub fn for_item(conn: &Connection, item_id: i64) -> Result<Vec<Collection>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {}
+         FROM collections c
+         INNER JOIN collection_items m ON m.collection_id = c.id
+         WHERE m.item_id = ?1
+         ORDER BY c.name",
+        COLLECTION_COLUMNS
+    ))?;
+
+    let collections = stmt
+        .query_map(params![item_id], row_to_collection)?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to fetch collections")?;
+
+    Ok(collections)
+}
+
+/// List the items of a collection in their manual order.
+///
+/// Trashed items keep their place but are not listed.
+pub fn items(conn: &Connection, id: i64) -> Result<Vec<Item>> {
+    ensure_exists(conn, id)?;
+
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {}
+         FROM collection_items ci
+         INNER JOIN items i ON i.id = ci.item_id
+         WHERE ci.collection_id = ?1 AND i.deleted_at IS NULL
+         ORDER BY ci.position",
+        ITEM_COLUMNS
+    ))?;
+
+    let items = stmt
+        .query_map(params![id], Database::row_to_item)?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to fetch collection items")?;
+
+    Ok(items)
+}
+
+/// Add an item to a collection at `position` among its listed items, or at
+/// the end if `None`.
+///
+/// An item already in the collection is moved instead.
+pub fn add_item(conn: &Connection, id: i64, item_id: i64, position: Option<usize>) -> Result<()> {
+    ensure_exists(conn, id)?;
+
+    let live: bool = conn
+        .prepare_cached("SELECT EXISTS(SELECT 1 FROM items WHERE id = ?1 AND deleted_at IS NULL)")?
+        .query_row(params![item_id], |row| row.get(0))?;
+    if !live {
+        anyhow::bail!("Item not found");
+    }
+
+    let mut order = item_order(conn, id)?;
+    order.retain(|member| member.item_id != item_id);
+    place(&mut order, item_id, position);
+
+    // Added last, then moved into place by the rewrite below
+    conn.prepare_cached(
+        "INSERT OR IGNORE INTO collection_items (collection_id, item_id, position) VALUES (?1, ?2, ?3)",
+    )?
+    .execute(params![id, item_id, order.len() as i64])
+    .context("Failed to add item to collection")?;
+
+    write_order(conn, id, &order)
+}
+
+/// Remove an item from a collection. The item itself is kept.
+pub fn remove_item(conn: &Connection, id: i64, item_id: i64) -> Result<()> {
+    let rows = conn
+        .prepare_cached("DELETE FROM collection_items WHERE collection_id = ?1 AND item_id = ?2")?
+        .execute(params![id, item_id])
+        .context("Failed to remove item from collection")?;
+
+    if rows == 0 {
+        anyhow::bail!("Item is not in this collection");
+    }
+
+    let order = item_order(conn, id)?;
+    write_order(conn, id, &order)
+}
+
+/// Move an item to `position` among the listed items of a collection.
+/// Positions past the end move it last.
+pub fn move_item(conn: &Connection, id: i64, item_id: i64, position: usize) -> Result<()> {
+    let mut order = item_order(conn, id)?;
+    let current = order
+        .iter()
+        .position(|member| member.item_id == item_id)
+        .context("Item is not in this collection")?;
+
+    order.remove(current);
+    place(&mut order, item_id, Some(position));
+
+    write_order(conn, id, &order)
+}
+
+fn ensure_exists(conn: &Connection, id: i64) -> Result<()> {
+    let exists: bool = conn
+        .prepare_cached("SELECT EXISTS(SELECT 1 FROM collections WHERE id = ?1)")?
+        .query_row(params![id], |row| row.get(0))?;
+
+    if !exists {
+        anyhow::bail!("Collection not found");
+    }
+
+    Ok(())
+}
+
+fn ensure_name_free(conn: &Connection, name: &str, except: Option<i64>) -> Result<()> {
+    let existing: Option<i64> = conn
+        .prepare_cached("SELECT id FROM collections WHERE name = ?1")?
+        .query_row(params![name], |row| row.get(0))
+        .optional()?;
+
+    match existing {
+        Some(other) if Some(other) != except => {
+            anyhow::bail!("A collection named {:?} already exists", name)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// An item of a collection, as stored in its order.
+struct Member {
+    item_id: i64,
+    trashed: bool,
+}
+
+/// Members of a collection in order, including trashed items.
+fn item_order(conn: &Connection, id: i64) -> Result<Vec<Member>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT ci.item_id, i.deleted_at IS NOT NULL
+         FROM collection_items ci
+         INNER JOIN items i ON i.id = ci.item_id
+         WHERE ci.collection_id = ?1
+         ORDER BY ci.position",
+    )?;
+
+    let members = stmt
+        .query_map(params![id], |row| {
+            Ok(Member {
+                item_id: row.get(0)?,
+                trashed: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to fetch collection order")?;
+
+    Ok(members)
+}
+
+/// Insert a live item into `order` so that it lands at `position` among
+/// the items that aren't trashed, or last if `None` or past the end.
+fn place(order: &mut Vec<Member>, item_id: i64, position: Option<usize>) {
+    let index = position
+        .and_then(|position| {
+            order
+                .iter()
+                .enumerate()
+                .filter(|(_, member)| !member.trashed)
+                .nth(position)
+                .map(|(index, _)| index)
+        })
+        .unwrap_or(order.len());
+
+    order.insert(
+        index,
+        Member {
+            item_id,
+            trashed: false,
+        },
+    );
+}
+
+fn write_order(conn: &Connection, id: i64, order: &[Member]) -> Result<()> {
+    let mut stmt = conn.prepare_cached(
+        "UPDATE collection_items SET position = ?3 WHERE collection_id = ?1 AND item_id = ?2",
+    )?;
+
+    for (position, member) in order.iter().enumerate() {
+        stmt.execute(params![id, member.item_id, position as i64])
+            .context("Failed to update collection order")?;
+    }
+
+    Ok(())
+}
+
+fn row_to_collection(row: &rusqlite::Row) -> rusqlite::Result<Collection> {
+    Ok(Collection {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        created_at: row.get(2)?,
+        item_count: row.get(3)?,
+    })
+}
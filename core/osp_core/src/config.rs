@@ -0,0 +1,104 @@
+//! Runtime configuration for [`Core`](crate::Core).
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::models::{ItemKind, NewItem};
+
+/// Options used when opening a [`Core`](crate::Core).
+///
+/// Deserializable from JSON so frontends can pass it over FFI. Missing
+/// fields take their default values.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Background ingestion worker. Disabled when `None`.
+    pub ingest: Option<IngestConfig>,
+    /// Which items [`Core::enqueue`](crate::Core::enqueue) records.
+    pub capture: CaptureRules,
+    /// How long trashed items are kept before they are purged for good.
+    pub trash_grace_period_ms: i64,
+}
+
+/// Default trash grace period: 30 days.
+pub const DEFAULT_TRASH_GRACE_PERIOD_MS: i64 = 30 * 24 * 60 * 60 * 1000;
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            ingest: None,
+            capture: CaptureRules::default(),
+            trash_grace_period_ms: DEFAULT_TRASH_GRACE_PERIOD_MS,
+        }
+    }
+}
+
+/// Batching parameters for the background ingestion worker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IngestConfig {
+    /// Write pending items at most this long after the first one arrived.
+    pub flush_interval_ms: u64,
+    /// Write as soon as this many items are pending.
+    pub max_batch_items: usize,
+}
+
+impl IngestConfig {
+    pub fn flush_interval(&self) -> Duration {
+        Duration::from_millis(self.flush_interval_ms)
+    }
+}
+
+impl Default for IngestConfig {
+    fn default() -> Self {
+        Self {
+            flush_interval_ms: 50,
+            max_batch_items: 256,
+        }
+    }
+}
+
+/// Rules deciding which captured items are recorded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureRules {
+    /// Source apps whose copies are never recorded, e.g. password
+    /// managers. Compared case-insensitively.
+    pub ignored_apps: Vec<String>,
+    /// Drop text that is empty or only whitespace.
+    pub skip_blank: bool,
+    /// Drop text larger than this many bytes. No limit when `None`.
+    pub max_text_bytes: Option<usize>,
+}
+
+impl CaptureRules {
+    /// Whether `item` should be recorded.
+    pub fn allows(&self, item: &NewItem) -> bool {
+        if let Some(app) = &item.source_app {
+            if self.ignored_apps.iter().any(|ignored| ignored.eq_ignore_ascii_case(app)) {
+                return false;
+            }
+        }
+
+        if matches!(item.kind, ItemKind::Text | ItemKind::Rtf) {
+            if self.skip_blank && item.content_ref.trim().is_empty() {
+                return false;
+            }
+            if self.max_text_bytes.is_some_and(|max| item.content_ref.len() > max) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl Default for CaptureRules {
+    fn default() -> Self {
+        Self {
+            ignored_apps: vec![],
+            skip_blank: true,
+            max_text_bytes: None,
+        }
+    }
+}
@@ -1,20 +1,80 @@
 //! Database connection management and migrations.
 
 use anyhow::{Context, Result};
-use parking_lot::Mutex;
+use parking_lot::{Mutex, MutexGuard};
 use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use std::path::Path;
 
-use crate::models::{Item, ItemKind, NewItem};
+use crate::models::{dedupe_key, Item, ItemKind, ItemVersion, NewItem};
+use crate::pool::{ReaderPool, STATEMENT_CACHE_CAPACITY};
+use crate::search::{self, SearchQuery};
+use crate::stats;
+
+/// Default number of reader connections.
+pub const DEFAULT_READERS: usize = 4;
+
+/// Columns selected for an [`Item`], in the order `row_to_item` expects.
+/// Queries must alias `items` as `i`.
+pub const ITEM_COLUMNS: &str = "i.id, i.kind, i.content_ref, i.source_app, i.created_at, \
+     i.pinned, i.tags, i.deleted_at, i.title, i.note";
+
+/// Number of columns in [`ITEM_COLUMNS`]: the index of the first column a
+/// query selects after them.
+pub const ITEM_COLUMN_COUNT: usize = count_columns(ITEM_COLUMNS);
+
+const fn count_columns(columns: &str) -> usize {
+    let bytes = columns.as_bytes();
+    let mut count = 1;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b',' {
+            count += 1;
+        }
+        i += 1;
+    }
+    count
+}
 
-/// Database connection wrapper with migrations support.
+/// Schema migrations, applied in order. The schema version stored in
+/// `PRAGMA user_version` is the number of migrations applied.
+const MIGRATIONS: [&str; 13] = [
+    include_str!("../migrations/0001_init.sql"),
+    include_str!("../migrations/0002_soft_delete.sql"),
+    include_str!("../migrations/0003_item_versions.sql"),
+    include_str!("../migrations/0004_item_annotations.sql"),
+    include_str!("../migrations/0005_snippets.sql"),
+    include_str!("../migrations/0006_collections.sql"),
+    include_str!("../migrations/0007_slots.sql"),
+    include_str!("../migrations/0008_paste_queue.sql"),
+    include_str!("../migrations/0009_uses.sql"),
+    include_str!("../migrations/0010_search_selections.sql"),
+    include_str!("../migrations/0011_fuzzy_search.sql"),
+    include_str!("../migrations/0012_trigram_index.sql"),
+    include_str!("../migrations/0013_saved_searches.sql"),
+];
+
+/// Schema version of a fully migrated database.
+pub const SCHEMA_VERSION: i32 = MIGRATIONS.len() as i32;
+
+/// Database wrapper with one writer connection and a pool of readers.
+///
+/// All writes are serialized through the writer. Reads go through the
+/// pool so they are never blocked by inserts (SQLite runs in WAL mode).
 pub struct Database {
-    conn: Mutex<Connection>,
+    writer: Mutex<Connection>,
+    readers: ReaderPool,
 }
 
 impl Database {
     /// Open or create a database at the specified path and apply migrations.
     pub fn open(db_path: &Path) -> Result<Self> {
+        Self::open_with_readers(db_path, DEFAULT_READERS)
+    }
+
+    /// Open or create a database with `readers` pooled reader connections.
+    pub fn open_with_readers(db_path: &Path, readers: usize) -> Result<Self> {
         let conn = Connection::open(db_path)
             .with_context(|| format!("Failed to open database at {:?}", db_path))?;
 
@@ -28,25 +88,59 @@ impl Database {
              PRAGMA temp_store=MEMORY;",
         )
         .context("Failed to set SQLite pragmas")?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+        Self::register_functions(&conn)?;
+
+        Self::apply_migrations(&conn)?;
+
+        // Readers are opened after migrations so they see the final schema
+        let readers = ReaderPool::open(db_path, readers)?;
+
+        Ok(Self {
+            writer: Mutex::new(conn),
+            readers,
+        })
+    }
 
-        let db = Self {
-            conn: Mutex::new(conn),
-        };
+    /// Lock the writer connection.
+    pub fn writer(&self) -> MutexGuard<'_, Connection> {
+        self.writer.lock()
+    }
+
+    /// Borrow a read-only connection from the pool.
+    pub fn reader(&self) -> MutexGuard<'_, Connection> {
+        self.readers.get()
+    }
+
+    /// Run `f` in a transaction on the writer connection.
+    ///
+    /// Commits if `f` succeeds and rolls back if it fails.
+    pub fn write_tx<T>(&self, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+        let conn = self.writer();
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to begin transaction")?;
+
+        let value = f(&tx)?;
+
+        tx.commit().context("Failed to commit transaction")?;
+        Ok(value)
+    }
 
-        db.apply_migrations()?;
-        Ok(db)
+    /// Register the custom SQL functions used by queries. Must be called on
+    /// every connection, readers included.
+    pub fn register_functions(conn: &Connection) -> Result<()> {
+        crate::fuzzy::register(conn)?;
+        crate::regexp::register(conn)
     }
 
     /// Apply all pending migrations.
-    fn apply_migrations(&self) -> Result<()> {
-        let conn = self.conn.lock();
+    pub(crate) fn apply_migrations(conn: &Connection) -> Result<()> {
         let current_version: i32 = conn
             .query_row("PRAGMA user_version", [], |row| row.get(0))
             .context("Failed to get user_version")?;
 
-        let migrations = [include_str!("../migrations/0001_init.sql")];
-
-        for (idx, sql) in migrations.iter().enumerate() {
+        for (idx, sql) in MIGRATIONS.iter().enumerate() {
             let version = idx as i32 + 1;
             if current_version < version {
                 conn.execute_batch(sql)
@@ -62,86 +156,212 @@ impl Database {
     /// Insert a new item and return its ID.
     /// Also inserts into FTS if applicable.
     pub fn insert_item(&self, item: &NewItem) -> Result<i64> {
-        let conn = self.conn.lock();
+        let conn = self.writer();
         let tx = conn
             .unchecked_transaction()
             .context("Failed to begin transaction")?;
 
+        let item_id = Self::insert_with(&tx, item)?;
+
+        tx.commit().context("Failed to commit transaction")?;
+        Ok(item_id)
+    }
+
+    /// Insert a batch of items in a single transaction.
+    ///
+    /// With `dedupe`, each item is checked against the database and against
+    /// the items before it in the same batch; duplicates bump the existing
+    /// item's timestamp instead of inserting. Every item runs in its own
+    /// savepoint so one failure doesn't affect the rest of the batch.
+    ///
+    /// Returns one result per item, in order. The outer error is only set
+    /// when the transaction itself cannot be started or committed.
+    pub fn insert_batch(&self, items: &[NewItem], dedupe: bool) -> Result<Vec<Result<i64>>> {
+        self.write_tx(|conn| Self::insert_batch_with(conn, items, dedupe))
+    }
+
+    /// [`Database::insert_batch`] using the given connection.
+    ///
+    /// The caller is responsible for wrapping this in a transaction.
+    pub fn insert_batch_with(
+        conn: &Connection,
+        items: &[NewItem],
+        dedupe: bool,
+    ) -> Result<Vec<Result<i64>>> {
+        let mut results = Vec::with_capacity(items.len());
+        for item in items {
+            conn.execute_batch("SAVEPOINT batch_item")?;
+
+            let result = if dedupe {
+                Self::dedupe_insert_with(conn, item)
+            } else {
+                Self::insert_with(conn, item)
+            };
+
+            if result.is_err() {
+                conn.execute_batch("ROLLBACK TO batch_item")?;
+            }
+            conn.execute_batch("RELEASE batch_item")?;
+            results.push(result);
+        }
+
+        Ok(results)
+    }
+
+    /// Insert an item, or bump the timestamp of its existing duplicate.
+    /// Returns the ID of the new or bumped item and counts the attempt for
+    /// [`stats`].
+    ///
+    /// The caller is responsible for wrapping this in a transaction.
+    pub fn dedupe_insert_with(conn: &Connection, item: &NewItem) -> Result<i64> {
+        let duplicate = Self::find_duplicate(conn, item)?;
+        stats::record_dedupe(conn, duplicate.is_some())?;
+
+        match duplicate {
+            Some(id) => {
+                Self::bump_timestamp(conn, id, item.created_at)?;
+                Ok(id)
+            }
+            None => Self::insert_with(conn, item),
+        }
+    }
+
+    /// Insert an item and its FTS entry using the given connection.
+    ///
+    /// The caller is responsible for wrapping this in a transaction.
+    pub fn insert_with(conn: &Connection, item: &NewItem) -> Result<i64> {
         let tags_json = serde_json::to_string(&item.tags).context("Failed to serialize tags")?;
 
-        tx.execute(
-            "INSERT INTO items (kind, content_ref, source_app, created_at, pinned, tags)
-             VALUES (?1, ?2, ?3, ?4, 0, ?5)",
-            params![
-                item.kind.as_str(),
-                &item.content_ref,
-                &item.source_app,
-                item.created_at,
-                tags_json,
-            ],
-        )
+        conn.prepare_cached(
+            "INSERT INTO items (kind, content_ref, source_app, created_at, pinned, tags, dedupe_key)
+             VALUES (?1, ?2, ?3, ?4, 0, ?5, ?6)",
+        )?
+        .execute(params![
+            item.kind.as_str(),
+            &item.content_ref,
+            &item.source_app,
+            item.created_at,
+            tags_json,
+            item.dedupe_key(),
+        ])
         .context("Failed to insert item")?;
 
-        let item_id = tx.last_insert_rowid();
+        let item_id = conn.last_insert_rowid();
 
         // Insert into FTS for text/rtf items
         if matches!(item.kind, ItemKind::Text | ItemKind::Rtf) {
-            tx.execute(
-                "INSERT INTO items_fts (item_id, text) VALUES (?1, ?2)",
-                params![item_id, &item.content_ref],
-            )
-            .context("Failed to insert into FTS")?;
+            conn.prepare_cached("INSERT INTO items_fts (item_id, text) VALUES (?1, ?2)")?
+                .execute(params![item_id, &item.content_ref])
+                .context("Failed to insert into FTS")?;
         }
 
-        tx.commit().context("Failed to commit transaction")?;
         Ok(item_id)
     }
 
-
-    /// Get an item by ID.
+    /// Get an item by ID. Trashed items are not returned.
     pub fn get_item(&self, id: i64) -> Result<Item> {
-        let conn = self.conn.lock();
+        let conn = self.reader();
         let mut stmt = conn
-            .prepare("SELECT id, kind, content_ref, source_app, created_at, pinned, tags FROM items WHERE id = ?1")
+            .prepare_cached(&format!(
+                "SELECT {} FROM items i WHERE i.id = ?1 AND i.deleted_at IS NULL",
+                ITEM_COLUMNS
+            ))
             .context("Failed to prepare statement")?;
 
-            let item = stmt
-            .query_row(params![id], |row| {
-                let kind_str: String = row.get(1)?;
-                let tags_json: String = row.get(6)?;
-                let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
-
-                Ok(Item {
-                    id: row.get(0)?,
-                    kind: kind_str.parse().unwrap(),
-                    content_ref: row.get(2)?,
-                    source_app: row.get(3)?,
-                    created_at: row.get(4)?,
-                    pinned: row.get::<_, i32>(5)? != 0,
-                    tags,
-                })
-            })
+        let item = stmt
+            .query_row(params![id], Self::row_to_item)
             .context("Item not found")?;
 
         Ok(item)
     }
 
-    /// Delete an item by ID.
+    /// Move an item to the trash.
+    ///
+    /// The item loses its quick-paste slot, if it had one.
+    pub fn trash_item(&self, id: i64, deleted_at: i64) -> Result<()> {
+        self.write_tx(|conn| {
+            let rows = conn
+                .prepare_cached("UPDATE items SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL")?
+                .execute(params![deleted_at, id])
+                .context("Failed to trash item")?;
+
+            if rows == 0 {
+                anyhow::bail!("Item not found");
+            }
+
+            conn.prepare_cached("DELETE FROM slots WHERE item_id = ?1")?
+                .execute(params![id])
+                .context("Failed to clear slot")?;
+
+            Ok(())
+        })
+    }
+
+    /// Move every item that is neither pinned nor in a slot to the trash.
+    /// Returns the number of items trashed.
+    pub fn trash_unpinned(&self, deleted_at: i64) -> Result<usize> {
+        let conn = self.writer();
+        let rows = conn
+            .prepare_cached(
+                "UPDATE items SET deleted_at = ?1
+                 WHERE deleted_at IS NULL AND pinned = 0
+                   AND id NOT IN (SELECT item_id FROM slots)",
+            )?
+            .execute(params![deleted_at])
+            .context("Failed to trash items")?;
+
+        Ok(rows)
+    }
+
+    /// Take an item back out of the trash.
+    pub fn restore_item(&self, id: i64) -> Result<()> {
+        let conn = self.writer();
+        let rows = conn
+            .prepare_cached("UPDATE items SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL")?
+            .execute(params![id])
+            .context("Failed to restore item")?;
+
+        if rows == 0 {
+            anyhow::bail!("Item not found in trash");
+        }
+
+        Ok(())
+    }
+
+    /// List trashed items, most recently deleted first.
+    pub fn trashed_items(&self, limit: u32) -> Result<Vec<Item>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {} FROM items i
+             WHERE i.deleted_at IS NOT NULL
+             ORDER BY i.deleted_at DESC, i.id DESC
+             LIMIT ?1",
+            ITEM_COLUMNS
+        ))?;
+
+        let items = stmt
+            .query_map(params![limit], Self::row_to_item)?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to fetch trashed items")?;
+
+        Ok(items)
+    }
+
+    /// Permanently delete an item by ID, whether trashed or not.
     pub fn delete_item(&self, id: i64) -> Result<()> {
-        let conn = self.conn.lock();
+        let conn = self.writer();
         let tx = conn
             .unchecked_transaction()
             .context("Failed to begin transaction")?;
 
         // Delete from FTS first
-        tx.execute(
-            "DELETE FROM items_fts WHERE item_id = ?1",
-            params![id],
-        )
-        .context("Failed to delete from FTS")?;
+        tx.prepare_cached("DELETE FROM items_fts WHERE item_id = ?1")?
+            .execute(params![id])
+            .context("Failed to delete from FTS")?;
 
         let rows = tx
-            .execute("DELETE FROM items WHERE id = ?1", params![id])
+            .prepare_cached("DELETE FROM items WHERE id = ?1")?
+            .execute(params![id])
             .context("Failed to delete item")?;
 
         if rows == 0 {
@@ -152,66 +372,223 @@ impl Database {
         Ok(())
     }
 
-    /// Set the pinned status of an item.
-    pub fn set_pinned(&self, id: i64, pinned: bool) -> Result<()> {
-        let conn = self.conn.lock();
+    /// Permanently delete trashed items deleted at or before `cutoff`.
+    /// Returns the number of items removed.
+    pub fn purge_trash(&self, cutoff: i64) -> Result<usize> {
+        self.write_tx(|conn| Self::purge_trash_with(conn, cutoff))
+    }
+
+    /// [`Database::purge_trash`] using the given connection.
+    ///
+    /// The caller is responsible for wrapping this in a transaction.
+    pub fn purge_trash_with(conn: &Connection, cutoff: i64) -> Result<usize> {
+        conn.prepare_cached(
+            "DELETE FROM items_fts WHERE item_id IN
+               (SELECT id FROM items WHERE deleted_at IS NOT NULL AND deleted_at <= ?1)",
+        )?
+        .execute(params![cutoff])
+        .context("Failed to delete from FTS")?;
+
         let rows = conn
-            .execute(
-                "UPDATE items SET pinned = ?1 WHERE id = ?2",
-                params![if pinned { 1 } else { 0 }, id],
-            )
-            .context("Failed to update pinned status")?;
+            .prepare_cached("DELETE FROM items WHERE deleted_at IS NOT NULL AND deleted_at <= ?1")?
+            .execute(params![cutoff])
+            .context("Failed to purge trash")?;
+
+        Ok(rows)
+    }
+
+    /// Replace the content of a text/rtf item, keeping the old content as a version.
+    ///
+    /// Reindexes FTS and recomputes the dedupe key. Editing to identical
+    /// content is a no-op.
+    pub fn update_content(&self, id: i64, content_ref: &str, replaced_at: i64) -> Result<()> {
+        let conn = self.writer();
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to begin transaction")?;
+
+        Self::update_content_with(&tx, id, content_ref, replaced_at)?;
+
+        tx.commit().context("Failed to commit transaction")?;
+        Ok(())
+    }
+
+    /// Restore the content recorded as `version`, keeping the current content
+    /// as a new version so the revert itself can be undone.
+    pub fn revert_content(&self, id: i64, version: i64, replaced_at: i64) -> Result<()> {
+        let conn = self.writer();
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to begin transaction")?;
+
+        let content_ref: String = tx
+            .prepare_cached("SELECT content_ref FROM item_versions WHERE item_id = ?1 AND version = ?2")?
+            .query_row(params![id, version], |row| row.get(0))
+            .optional()?
+            .ok_or_else(|| anyhow::anyhow!("Version not found"))?;
+
+        Self::update_content_with(&tx, id, &content_ref, replaced_at)?;
+
+        tx.commit().context("Failed to commit transaction")?;
+        Ok(())
+    }
+
+    /// List the previous versions of an item, oldest first.
+    pub fn item_versions(&self, id: i64) -> Result<Vec<ItemVersion>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare_cached(
+            "SELECT item_id, version, content_ref, replaced_at
+             FROM item_versions
+             WHERE item_id = ?1
+             ORDER BY version",
+        )?;
+
+        let versions = stmt
+            .query_map(params![id], |row| {
+                Ok(ItemVersion {
+                    item_id: row.get(0)?,
+                    version: row.get(1)?,
+                    content_ref: row.get(2)?,
+                    replaced_at: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to fetch item versions")?;
+
+        Ok(versions)
+    }
+
+    fn update_content_with(
+        conn: &Connection,
+        id: i64,
+        content_ref: &str,
+        replaced_at: i64,
+    ) -> Result<()> {
+        let (kind, old_content): (String, String) = conn
+            .prepare_cached("SELECT kind, content_ref FROM items WHERE id = ?1 AND deleted_at IS NULL")?
+            .query_row(params![id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .optional()?
+            .ok_or_else(|| anyhow::anyhow!("Item not found"))?;
+
+        let kind: ItemKind = kind.parse().map_err(anyhow::Error::msg)?;
+        if !matches!(kind, ItemKind::Text | ItemKind::Rtf) {
+            anyhow::bail!("Only text and rtf items can be edited");
+        }
+
+        if old_content == content_ref {
+            return Ok(());
+        }
+
+        conn.prepare_cached(
+            "INSERT INTO item_versions (item_id, version, content_ref, replaced_at)
+             VALUES (?1, (SELECT COALESCE(MAX(version), 0) + 1 FROM item_versions WHERE item_id = ?1), ?2, ?3)",
+        )?
+        .execute(params![id, old_content, replaced_at])
+        .context("Failed to record version")?;
+
+        conn.prepare_cached("UPDATE items SET content_ref = ?1, dedupe_key = ?2 WHERE id = ?3")?
+            .execute(params![content_ref, dedupe_key(kind, content_ref), id])
+            .context("Failed to update content")?;
+
+        conn.prepare_cached("UPDATE items_fts SET text = ?1 WHERE item_id = ?2")?
+            .execute(params![content_ref, id])
+            .context("Failed to update FTS")?;
+
+        Ok(())
+    }
+
+    /// Set or clear the title and note of an item.
+    ///
+    /// Both are indexed for search; items without a text FTS entry (images,
+    /// files) get one so they can be found by their annotations.
+    pub fn annotate(&self, id: i64, title: Option<&str>, note: Option<&str>) -> Result<()> {
+        self.write_tx(|conn| Self::annotate_with(conn, id, title, note))
+    }
+
+    /// Set or clear the title and note of an item using the given connection.
+    pub fn annotate_with(
+        conn: &Connection,
+        id: i64,
+        title: Option<&str>,
+        note: Option<&str>,
+    ) -> Result<()> {
+        let rows = conn
+            .prepare_cached("UPDATE items SET title = ?1, note = ?2 WHERE id = ?3 AND deleted_at IS NULL")?
+            .execute(params![title, note, id])
+            .context("Failed to annotate item")?;
 
         if rows == 0 {
             anyhow::bail!("Item not found");
         }
 
+        let indexed = conn
+            .prepare_cached("UPDATE items_fts SET title = ?1, note = ?2 WHERE item_id = ?3")?
+            .execute(params![title, note, id])
+            .context("Failed to update FTS")?;
+
+        if indexed == 0 {
+            conn.prepare_cached("INSERT INTO items_fts (item_id, title, note) VALUES (?1, ?2, ?3)")?
+                .execute(params![id, title, note])
+                .context("Failed to insert into FTS")?;
+        }
+
         Ok(())
     }
 
-    /// Search items using FTS5 or LIKE for short queries.
-    pub fn search(&self, query: &str, limit: u32) -> Result<Vec<Item>> {
-        let conn = self.conn.lock();
-
-        if query.len() < 3 {
-            // Use LIKE for short queries
-            let pattern = format!("%{}%", query);
-            let mut stmt = conn.prepare(
-                "SELECT id, kind, content_ref, source_app, created_at, pinned, tags
-                 FROM items
-                 WHERE content_ref LIKE ?1
-                 ORDER BY created_at DESC
-                 LIMIT ?2",
-            )?;
-
-            let items = stmt
-                .query_map(params![pattern, limit], Self::row_to_item)?
-                .collect::<Result<Vec<_>, _>>()
-                .context("Failed to fetch search results")?;
-
-            return Ok(items);
+    /// Read a JSON-encoded value from the `settings` table.
+    pub fn setting<T: DeserializeOwned>(conn: &Connection, key: &str) -> Result<Option<T>> {
+        let value: Option<String> = conn
+            .prepare_cached("SELECT value FROM settings WHERE key = ?1")?
+            .query_row(params![key], |row| row.get(0))
+            .optional()
+            .with_context(|| format!("Failed to read setting {}", key))?;
+
+        match value {
+            Some(json) => Ok(Some(
+                serde_json::from_str(&json).with_context(|| format!("Invalid setting {}", key))?,
+            )),
+            None => Ok(None),
         }
+    }
 
-        // Use FTS5 for longer queries
-        let mut stmt = conn.prepare(
-            "SELECT i.id, i.kind, i.content_ref, i.source_app, i.created_at, i.pinned, i.tags
-             FROM items i
-             INNER JOIN items_fts fts ON fts.item_id = i.id
-             WHERE items_fts MATCH ?1
-             ORDER BY fts.rank, i.created_at DESC
-             LIMIT ?2",
-        )?;
+    /// Store a value in the `settings` table as JSON.
+    pub fn set_setting<T: Serialize>(conn: &Connection, key: &str, value: &T) -> Result<()> {
+        conn.prepare_cached(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        )?
+        .execute(params![key, serde_json::to_string(value)?])
+        .with_context(|| format!("Failed to save setting {}", key))?;
 
-        let items = stmt
-            .query_map(params![query, limit], Self::row_to_item)?
-            .collect::<Result<Vec<_>, _>>()
-            .context("Failed to fetch FTS search results")?;
+        Ok(())
+    }
 
-        Ok(items)
+    /// Set the pinned status of an item.
+    pub fn set_pinned(&self, id: i64, pinned: bool) -> Result<()> {
+        Self::set_pinned_with(&self.writer(), id, pinned)
+    }
+
+    /// Set the pinned status of an item using the given connection.
+    pub fn set_pinned_with(conn: &Connection, id: i64, pinned: bool) -> Result<()> {
+        let rows = conn
+            .prepare_cached("UPDATE items SET pinned = ?1 WHERE id = ?2 AND deleted_at IS NULL")?
+            .execute(params![if pinned { 1 } else { 0 }, id])
+            .context("Failed to update pinned status")?;
+
+        if rows == 0 {
+            anyhow::bail!("Item not found");
+        }
+
+        Ok(())
+    }
+
+    /// Search items using FTS5 or LIKE for short queries.
+    pub fn search(&self, query: &SearchQuery) -> Result<Vec<Item>> {
+        search::search(&self.reader(), query)
     }
 
     /// Helper to convert a row to an Item.
-    fn row_to_item(row: &rusqlite::Row) -> rusqlite::Result<Item> {
+    pub fn row_to_item(row: &rusqlite::Row) -> rusqlite::Result<Item> {
         let kind_str: String = row.get(1)?;
         let tags_json: String = row.get(6)?;
         let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
@@ -224,76 +601,36 @@ impl Database {
             created_at: row.get(4)?,
             pinned: row.get::<_, i32>(5)? != 0,
             tags,
+            deleted_at: row.get(7)?,
+            title: row.get(8)?,
+            note: row.get(9)?,
         })
     }
 
-    /// Check for duplicate by computing hash and checking existing items.
-    /// This is a simple implementation; returns true if duplicate exists.
-    pub fn has_duplicate(&self, new_item: &NewItem) -> Result<bool> {
-        let conn = self.conn.lock();
-
-        // For text items, check normalized content
-        if matches!(new_item.kind, ItemKind::Text | ItemKind::Rtf) {
-            let normalized = new_item.normalized_text();
-            let mut stmt = conn.prepare(
-                "SELECT id FROM items WHERE kind = ?1 AND content_ref = ?2 LIMIT 1",
-            )?;
-
-            let exists = stmt
-                .query_row(params![new_item.kind.as_str(), normalized], |_| Ok(()))
-                .optional()?
-                .is_some();
-
-            return Ok(exists);
-        }
-
-        // For image/file items, check exact content_ref
-        let mut stmt = conn.prepare(
-            "SELECT id FROM items WHERE kind = ?1 AND content_ref = ?2 LIMIT 1",
-        )?;
+    /// Find the ID of an existing item that duplicates `new_item`.
+    /// Trashed items are ignored.
+    ///
+    /// - For text/rtf: normalized content
+    /// - For image/file: exact content_ref path
+    fn find_duplicate(conn: &Connection, new_item: &NewItem) -> Result<Option<i64>> {
+        let id = conn
+            .prepare_cached(
+                "SELECT id FROM items
+                 WHERE kind = ?1 AND dedupe_key = ?2 AND deleted_at IS NULL
+                 LIMIT 1",
+            )?
+            .query_row(params![new_item.kind.as_str(), new_item.dedupe_key()], |row| row.get(0))
+            .optional()?;
+
+        Ok(id)
+    }
 
-        let exists = stmt
-            .query_row(
-                params![new_item.kind.as_str(), &new_item.content_ref],
-                |_| Ok(()),
-            )
-            .optional()?
-            .is_some();
-
-        Ok(exists)
-    }
-    
-    /// Update the timestamp of an existing duplicate item to "bump" it to the top.
-    /// Returns the ID of the updated item if found, None otherwise.
-    pub fn update_duplicate_timestamp(&self, new_item: &NewItem) -> Result<Option<i64>> {
-        let conn = self.conn.lock();
-        
-        // Find the duplicate item
-        let id: Option<i64> = if matches!(new_item.kind, ItemKind::Text | ItemKind::Rtf) {
-            let normalized = new_item.normalized_text();
-            conn.query_row(
-                "SELECT id FROM items WHERE kind = ?1 AND content_ref = ?2 LIMIT 1",
-                params![new_item.kind.as_str(), normalized],
-                |row| row.get(0)
-            ).optional()?
-        } else {
-            conn.query_row(
-                "SELECT id FROM items WHERE kind = ?1 AND content_ref = ?2 LIMIT 1",
-                params![new_item.kind.as_str(), &new_item.content_ref],
-                |row| row.get(0)
-            ).optional()?
-        };
-        
-        if let Some(item_id) = id {
-            // Update the timestamp
-            conn.execute(
-                "UPDATE items SET created_at = ?1 WHERE id = ?2",
-                params![new_item.created_at, item_id],
-            )?;
-            Ok(Some(item_id))
-        } else {
-            Ok(None)
-        }
+    /// Move an existing item to the top by updating its timestamp. A
+    /// duplicate older than the item, e.g. from an import, leaves it as is.
+    fn bump_timestamp(conn: &Connection, id: i64, created_at: i64) -> Result<()> {
+        conn.prepare_cached("UPDATE items SET created_at = MAX(created_at, ?1) WHERE id = ?2")?
+            .execute(params![created_at, id])
+            .context("Failed to update timestamp")?;
+        Ok(())
     }
 }
-
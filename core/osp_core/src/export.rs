@@ -0,0 +1,326 @@
+//! Export of the history to JSON Lines, CSV or Markdown.
+//!
+//! Items are streamed oldest first straight from a statement over the same
+//! match set as a search, so exports of any size use constant memory.
+//! Paging (`limit`, `offset`) and the sort order of the query do not apply.
+//!
+//! JSON Lines keeps everything an [`Item`] holds and is the format to
+//! re-import: all but the ID and trash state comes back. CSV is meant for
+//! spreadsheets and Markdown for reading, with one section per local day.
+
+use anyhow::{Context, Result};
+use rusqlite::{params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::db::{Database, ITEM_COLUMNS, ITEM_COLUMN_COUNT};
+use crate::models::{Item, ItemKind};
+use crate::regexp;
+use crate::search::{self, Matches, SearchMode, SearchQuery};
+
+/// Header row of CSV exports.
+const CSV_HEADER: [&str; 11] = [
+    "id",
+    "kind",
+    "content",
+    "source_app",
+    "created_at",
+    "pinned",
+    "tags",
+    "title",
+    "note",
+    "deleted_at",
+    "blob",
+];
+
+/// Output format of an export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// One JSON object per line, as serialized by [`Item`].
+    #[default]
+    Jsonl,
+    /// RFC 4180 CSV with a header row. Times are UTC ISO 8601.
+    Csv,
+    /// A Markdown document with a section per local day.
+    Markdown,
+}
+
+/// What to export and how.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    /// Only export the items this query matches. `None` exports the whole
+    /// history, trash excluded.
+    pub query: Option<SearchQuery>,
+    /// Copy the files of image and file items into this directory, named
+    /// `<id>-<file name>`, and refer to them from the export.
+    pub sidecar_dir: Option<PathBuf>,
+}
+
+/// A finished export.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportReport {
+    /// Items written.
+    pub items: i64,
+    /// Files copied to the sidecar directory.
+    pub blobs: usize,
+    /// Image and file items whose file was not found, so it couldn't be
+    /// copied.
+    pub missing_blobs: Vec<i64>,
+    pub duration_ms: i64,
+}
+
+/// An item as written to JSON Lines: the item itself, plus its sidecar
+/// file when one was copied.
+#[derive(Serialize)]
+struct Exported<'a> {
+    #[serde(flatten)]
+    item: &'a Item,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    blob: Option<&'a str>,
+}
+
+/// Write the items matching `options.query` to `out`.
+///
+/// Relative `content_ref` paths of image and file items are resolved
+/// against `blobs_dir`.
+pub fn export(
+    conn: &Connection,
+    blobs_dir: &Path,
+    out: &mut impl Write,
+    options: &ExportOptions,
+) -> Result<ExportReport> {
+    let started = Instant::now();
+    let query = options.query.clone().unwrap_or_default();
+    let matches = search::matching(conn, &query)?;
+
+    if let Some(dir) = &options.sidecar_dir {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create sidecar directory {:?}", dir))?;
+    }
+
+    let mut report = if query.mode == SearchMode::Regex {
+        regexp::guard(conn, || write_items(conn, &matches, blobs_dir, out, options))?
+    } else {
+        write_items(conn, &matches, blobs_dir, out, options)?
+    };
+
+    out.flush().context("Failed to write export")?;
+    report.duration_ms = started.elapsed().as_millis() as i64;
+    Ok(report)
+}
+
+fn write_items(
+    conn: &Connection,
+    matches: &Matches,
+    blobs_dir: &Path,
+    out: &mut impl Write,
+    options: &ExportOptions,
+) -> Result<ExportReport> {
+    let mut report = ExportReport::default();
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {},
+                date(i.created_at / 1000, 'unixepoch', 'localtime'),
+                time(i.created_at / 1000, 'unixepoch', 'localtime'),
+                strftime('%Y-%m-%dT%H:%M:%fZ', i.created_at / 1000.0, 'unixepoch')
+         {}
+         ORDER BY i.created_at, i.id",
+        ITEM_COLUMNS,
+        matches.clauses()
+    ))?;
+    let mut rows = stmt.query(params_from_iter(matches.params.0.iter()))?;
+
+    match options.format {
+        ExportFormat::Jsonl => {}
+        ExportFormat::Csv => csv_row(out, CSV_HEADER.iter().map(|field| Some(*field)))?,
+        ExportFormat::Markdown => writeln!(out, "# Clipboard history")?,
+    }
+
+    let mut current_day = None;
+    while let Some(row) = rows.next().context("Failed to read items")? {
+        let item = Database::row_to_item(row)?;
+        let day: String = row.get(ITEM_COLUMN_COUNT)?;
+        let time: String = row.get(ITEM_COLUMN_COUNT + 1)?;
+        let utc: String = row.get(ITEM_COLUMN_COUNT + 2)?;
+
+        let blob = match &options.sidecar_dir {
+            Some(dir) if matches!(item.kind, ItemKind::Image | ItemKind::File) => {
+                match copy_blob(&item, blobs_dir, dir)? {
+                    Some(name) => {
+                        report.blobs += 1;
+                        Some(name)
+                    }
+                    None => {
+                        report.missing_blobs.push(item.id);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        match options.format {
+            ExportFormat::Jsonl => {
+                serde_json::to_writer(
+                    &mut *out,
+                    &Exported {
+                        item: &item,
+                        blob: blob.as_deref(),
+                    },
+                )?;
+                writeln!(out)?;
+            }
+            ExportFormat::Csv => {
+                let deleted_at = item.deleted_at.map(|t| t.to_string());
+                csv_row(
+                    out,
+                    [
+                        Some(item.id.to_string().as_str()),
+                        Some(item.kind.as_str()),
+                        Some(item.content_ref.as_str()),
+                        item.source_app.as_deref(),
+                        Some(utc.as_str()),
+                        Some(if item.pinned { "true" } else { "false" }),
+                        Some(item.tags.join(", ").as_str()),
+                        item.title.as_deref(),
+                        item.note.as_deref(),
+                        deleted_at.as_deref(),
+                        blob.as_deref(),
+                    ],
+                )?;
+            }
+            ExportFormat::Markdown => {
+                if current_day.as_ref() != Some(&day) {
+                    writeln!(out, "\n## {}", day)?;
+                    current_day = Some(day);
+                }
+                let sidecar = blob.as_deref().zip(options.sidecar_dir.as_deref());
+                markdown_item(out, &item, &time, sidecar)?;
+            }
+        }
+        report.items += 1;
+    }
+
+    Ok(report)
+}
+
+/// Copy the file of an image or file item to `dir`, returning its name
+/// there, or `None` if there is no such file.
+fn copy_blob(item: &Item, blobs_dir: &Path, dir: &Path) -> Result<Option<String>> {
+    let source = blobs_dir.join(&item.content_ref);
+    let file_name = match source.file_name() {
+        Some(name) if source.is_file() => name.to_string_lossy().into_owned(),
+        _ => return Ok(None),
+    };
+
+    let name = format!("{}-{}", item.id, file_name);
+    std::fs::copy(&source, dir.join(&name))
+        .with_context(|| format!("Failed to copy {:?} to {:?}", source, dir))?;
+    Ok(Some(name))
+}
+
+/// Write one CSV record, quoting fields that need it. `None` is an empty
+/// field.
+fn csv_row<'a>(
+    out: &mut impl Write,
+    fields: impl IntoIterator<Item = Option<&'a str>>,
+) -> Result<()> {
+    let fields: Vec<String> = fields
+        .into_iter()
+        .map(|field| csv_field(field.unwrap_or("")))
+        .collect();
+    write!(out, "{}\r\n", fields.join(","))?;
+    Ok(())
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Write one item as a Markdown subsection headed by its local time.
+/// `sidecar` is the item's file name in the sidecar directory, and that
+/// directory.
+fn markdown_item(
+    out: &mut impl Write,
+    item: &Item,
+    time: &str,
+    sidecar: Option<(&str, &Path)>,
+) -> Result<()> {
+    let mut heading = time.to_string();
+    if let Some(app) = &item.source_app {
+        heading.push_str(&format!(" · {}", app));
+    }
+    if item.pinned {
+        heading.push_str(" · pinned");
+    }
+    writeln!(out, "\n### {}\n", heading)?;
+
+    if let Some(title) = &item.title {
+        writeln!(out, "**{}**\n", title)?;
+    }
+
+    match (item.kind, sidecar) {
+        (ItemKind::Image, Some((name, dir))) => {
+            writeln!(out, "![{}](<{}>)", name, dir.join(name).display())?
+        }
+        (ItemKind::File, Some((name, dir))) => {
+            writeln!(out, "[{}](<{}>)", name, dir.join(name).display())?
+        }
+        (ItemKind::Image | ItemKind::File, None) => writeln!(out, "`{}`", item.content_ref)?,
+        (ItemKind::Text | ItemKind::Rtf, _) => {
+            let fence = fence(&item.content_ref);
+            writeln!(out, "{}\n{}\n{}", fence, item.content_ref, fence)?
+        }
+    }
+
+    if !item.tags.is_empty() {
+        writeln!(out, "\nTags: {}", item.tags.join(", "))?;
+    }
+    if let Some(note) = &item.note {
+        writeln!(out)?;
+        for line in note.lines() {
+            writeln!(out, "> {}", line)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A code fence longer than any run of backticks in `content`.
+fn fence(content: &str) -> String {
+    let longest = content
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn test_fence() {
+        assert_eq!(fence("no backticks"), "```");
+        assert_eq!(fence("```rust\nfn main() {}\n```"), "````");
+        assert_eq!(fence("`````"), "``````");
+    }
+}
@@ -0,0 +1,161 @@
+//! Faceted counts for search results.
+//!
+//! Counts the items a [`SearchQuery`] matches by kind, source app, tag,
+//! pinned state and creation day or week, with one `GROUP BY` statement
+//! per facet over the same match set as the search itself. Paging
+//! (`limit`, `offset`) does not apply.
+
+use anyhow::{Context, Result};
+use rusqlite::{params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::models::ItemKind;
+use crate::regexp;
+use crate::search::{self, Matches, SearchMode, SearchQuery};
+
+/// Most values listed for the app and tag facets.
+pub const MAX_VALUES: usize = 50;
+
+/// Number of matching items with one facet value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Count<T> {
+    pub value: T,
+    pub count: i64,
+}
+
+/// Counts of the items matching a query.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Facets {
+    /// Number of matching items.
+    pub total: i64,
+    /// How many of them are pinned.
+    pub pinned: i64,
+    /// By kind, most common first.
+    pub kinds: Vec<Count<ItemKind>>,
+    /// By source app, most common first; `None` for items without one.
+    /// At most [`MAX_VALUES`].
+    pub apps: Vec<Count<Option<String>>>,
+    /// By tag, most common first. At most [`MAX_VALUES`].
+    pub tags: Vec<Count<String>>,
+    /// By local creation date (`YYYY-MM-DD`), newest first.
+    pub days: Vec<Count<String>>,
+    /// By local creation week, named by its Monday (`YYYY-MM-DD`), newest
+    /// first.
+    pub weeks: Vec<Count<String>>,
+}
+
+/// Count the items matching `query`.
+pub fn facets(conn: &Connection, query: &SearchQuery) -> Result<Facets> {
+    let matches = search::matching(conn, query)?;
+
+    if query.mode == SearchMode::Regex {
+        regexp::guard(conn, || count(conn, &matches))
+    } else {
+        count(conn, &matches)
+    }
+}
+
+fn count(conn: &Connection, matches: &Matches) -> Result<Facets> {
+    let clauses = matches.clauses();
+
+    let (total, pinned) = conn
+        .prepare_cached(&format!(
+            "SELECT COUNT(*), COALESCE(SUM(i.pinned), 0) {}",
+            clauses
+        ))?
+        .query_row(params_from_iter(matches.params.0.iter()), |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .context("Failed to count search results")?;
+
+    let kinds = group(
+        conn,
+        matches,
+        &format!(
+            "SELECT i.kind, COUNT(*) {} GROUP BY i.kind ORDER BY 2 DESC, 1",
+            clauses
+        ),
+    )?
+    .into_iter()
+    .map(|Count { value, count }: Count<String>| {
+        ItemKind::from_str(&value)
+            .map(|value| Count { value, count })
+            .map_err(anyhow::Error::msg)
+    })
+    .collect::<Result<Vec<_>>>()?;
+
+    let apps = group(
+        conn,
+        matches,
+        &format!(
+            "SELECT i.source_app, COUNT(*) {} GROUP BY i.source_app ORDER BY 2 DESC, 1 LIMIT {}",
+            clauses, MAX_VALUES
+        ),
+    )?;
+
+    let tags = group(
+        conn,
+        matches,
+        &format!(
+            "SELECT tag.value, COUNT(*)
+             FROM items i {} INNER JOIN json_each(i.tags) tag
+             WHERE {}
+             GROUP BY tag.value ORDER BY 2 DESC, 1 LIMIT {}",
+            matches.join,
+            matches.conditions.join(" AND "),
+            MAX_VALUES
+        ),
+    )?;
+
+    let days = group(
+        conn,
+        matches,
+        &format!(
+            "SELECT date(i.created_at / 1000, 'unixepoch', 'localtime') AS day, COUNT(*) {}
+             GROUP BY day ORDER BY day DESC",
+            clauses
+        ),
+    )?;
+
+    // 'weekday 0' moves to the coming Sunday, six days before is Monday
+    let weeks = group(
+        conn,
+        matches,
+        &format!(
+            "SELECT date(i.created_at / 1000, 'unixepoch', 'localtime', 'weekday 0', '-6 days') AS week,
+                    COUNT(*) {}
+             GROUP BY week ORDER BY week DESC",
+            clauses
+        ),
+    )?;
+
+    Ok(Facets {
+        total,
+        pinned,
+        kinds,
+        apps,
+        tags,
+        days,
+        weeks,
+    })
+}
+
+fn group<T: rusqlite::types::FromSql>(
+    conn: &Connection,
+    matches: &Matches,
+    sql: &str,
+) -> Result<Vec<Count<T>>> {
+    let counts = conn
+        .prepare_cached(sql)?
+        .query_map(params_from_iter(matches.params.0.iter()), |row| {
+            Ok(Count {
+                value: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to count search results")?;
+
+    Ok(counts)
+}
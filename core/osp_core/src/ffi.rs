@@ -4,11 +4,14 @@
 //! All functions are marked `extern "C"` and use C-compatible types.
 
 use std::ffi::{CStr, CString};
-use std::os::raw::{c_char, c_int, c_longlong};
+use std::os::raw::{c_char, c_int, c_longlong, c_void};
 use std::path::Path;
 use std::ptr;
 
-use crate::{Core, Item, ItemKind, NewItem};
+use crate::{
+    BackupOptions, BackupProgress, Config, Core, ExportOptions, ImportOptions, Item, ItemKind, MaintenanceOptions,
+    NewItem, PasteQueue, Rendered, SearchMode, SearchQuery,
+};
 
 /// Opaque pointer to Core instance (passed to Swift as OpaquePointer)
 pub struct CoreHandle {
@@ -25,6 +28,9 @@ pub struct CItem {
     pub created_at: c_longlong,
     pub pinned: c_int, // 0=false, 1=true
     pub tags_json: *mut c_char, // JSON array as string
+    pub deleted_at: c_longlong, // 0 if not in the trash
+    pub title: *mut c_char, // NULL if None
+    pub note: *mut c_char, // NULL if None
 }
 
 /// C-compatible array of items
@@ -64,15 +70,117 @@ pub unsafe extern "C" fn core_new(
     }
 }
 
+/// Create a new Core instance with a JSON configuration
+///
+/// The JSON object mirrors `Config`, e.g.
+/// `{"ingest": {"flush_interval_ms": 50, "max_batch_items": 256},
+///   "capture": {"ignored_apps": ["com.1password.1password"], "max_text_bytes": 1048576}}`.
+///
+/// # Safety
+/// - db_path, blobs_dir and config_json must be valid UTF-8 null-terminated strings
+/// - Caller must call core_free() when done
+#[no_mangle]
+pub unsafe extern "C" fn core_new_with_config(
+    db_path: *const c_char,
+    blobs_dir: *const c_char,
+    config_json: *const c_char,
+) -> *mut CoreHandle {
+    if db_path.is_null() || blobs_dir.is_null() || config_json.is_null() {
+        return ptr::null_mut();
+    }
+
+    let db_path_str = match CStr::from_ptr(db_path).to_str() {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    let blobs_dir_str = match CStr::from_ptr(blobs_dir).to_str() {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    let config: Config = match CStr::from_ptr(config_json)
+        .to_str()
+        .ok()
+        .and_then(|s| serde_json::from_str(s).ok())
+    {
+        Some(config) => config,
+        None => return ptr::null_mut(),
+    };
+
+    match Core::open_with_config(Path::new(db_path_str), Path::new(blobs_dir_str), config) {
+        Ok(core) => Box::into_raw(Box::new(CoreHandle { core })),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Create a new Core instance, recovering the database if it is corrupted
+///
+/// config_json is as for core_new_with_config(), or NULL for the defaults.
+/// If the database was corrupted, the damaged file is moved aside, what can
+/// be read is copied into a new database, and `*report_out` is set to a
+/// JSON report: `{"reason", "moved_to", "items_recovered", "tables": [{"table",
+/// "recovered", "dropped", "errors"}]}`. Otherwise `*report_out` is set to
+/// NULL.
+///
+/// # Safety
+/// - db_path and blobs_dir must be valid UTF-8 null-terminated strings
+/// - config_json must be NULL or a valid UTF-8 null-terminated string
+/// - report_out must be NULL or a valid pointer
+/// - Caller must call core_free() when done, and string_free() on the report
+#[no_mangle]
+pub unsafe extern "C" fn core_new_with_recovery(
+    db_path: *const c_char,
+    blobs_dir: *const c_char,
+    config_json: *const c_char,
+    report_out: *mut *mut c_char,
+) -> *mut CoreHandle {
+    if !report_out.is_null() {
+        *report_out = ptr::null_mut();
+    }
+
+    if db_path.is_null() || blobs_dir.is_null() {
+        return ptr::null_mut();
+    }
+
+    let db_path_str = match CStr::from_ptr(db_path).to_str() {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    let blobs_dir_str = match CStr::from_ptr(blobs_dir).to_str() {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    let config: Config = match optional_json_from_c(config_json) {
+        Some(config) => config,
+        None => return ptr::null_mut(),
+    };
+
+    match Core::open_with_recovery(Path::new(db_path_str), Path::new(blobs_dir_str), config) {
+        Ok((core, report)) => {
+            if let (Some(report), false) = (report, report_out.is_null()) {
+                *report_out = json_to_c_string(&report);
+            }
+            Box::into_raw(Box::new(CoreHandle { core }))
+        }
+        Err(_) => ptr::null_mut(),
+    }
+}
+
 /// Free a Core instance
 ///
+/// Writes any items still queued by core_enqueue() before returning.
+///
 /// # Safety
 /// - handle must be a valid pointer returned by core_new()
 /// - Must not be called twice on the same pointer
 #[no_mangle]
 pub unsafe extern "C" fn core_free(handle: *mut CoreHandle) {
     if !handle.is_null() {
-        let _ = Box::from_raw(handle);
+        let handle = Box::from_raw(handle);
+        handle.core.shutdown();
     }
 }
 
@@ -124,15 +232,16 @@ pub unsafe extern "C" fn core_add_item(
         tags: vec![],
     };
 
-    match handle.core.add_item(new_item) {
-        Ok(id) => id,
-        Err(_) => -1,
-    }
+    handle.core.add_item(new_item).unwrap_or(-1)
 }
 
 /// Add item with deduplication
 ///
-/// Returns item ID if inserted, 0 if duplicate detected, -1 on error
+/// # Safety
+/// - handle must be valid
+/// - content_ref must be valid UTF-8 null-terminated string
+/// - source_app can be NULL
+/// - Returns item ID if inserted, 0 if duplicate detected, -1 on error
 #[no_mangle]
 pub unsafe extern "C" fn core_dedupe_insert(
     handle: *mut CoreHandle,
@@ -181,11 +290,130 @@ pub unsafe extern "C" fn core_dedupe_insert(
     }
 }
 
+/// Add many items in a single transaction
+///
+/// items_json is a JSON array of objects shaped like `NewItem`, e.g.
+/// `[{"kind": "text", "content_ref": "hello", "source_app": null, "created_at": 1, "tags": []}]`.
+/// With dedupe != 0, duplicates (in the database or earlier in the array)
+/// bump the existing item instead of inserting.
+///
+/// Returns a JSON array with one entry per input item: the item ID, or
+/// null if that item failed. Returns NULL if the input can't be parsed.
+///
+/// # Safety
+/// - handle must be valid
+/// - items_json must be valid UTF-8 null-terminated string
+/// - Caller must call string_free() on the returned string
+#[no_mangle]
+pub unsafe extern "C" fn core_add_items_json(
+    handle: *mut CoreHandle,
+    items_json: *const c_char,
+    dedupe: c_int,
+) -> *mut c_char {
+    if handle.is_null() || items_json.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    let items: Vec<NewItem> = match CStr::from_ptr(items_json)
+        .to_str()
+        .ok()
+        .and_then(|s| serde_json::from_str(s).ok())
+    {
+        Some(items) => items,
+        None => return ptr::null_mut(),
+    };
+
+    let results = if dedupe != 0 {
+        handle.core.dedupe_insert_items(items)
+    } else {
+        handle.core.add_items(items)
+    };
+
+    let ids: Vec<Option<i64>> = results.into_iter().map(Result::ok).collect();
+    json_to_c_string(&ids)
+}
+
+/// Queue an item for deduplicated insertion and return immediately
+///
+/// # Safety
+/// - handle must be valid
+/// - content_ref must be valid UTF-8 null-terminated string
+/// - source_app can be NULL
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_enqueue(
+    handle: *mut CoreHandle,
+    kind: c_int,
+    content_ref: *const c_char,
+    source_app: *const c_char,
+    created_at: c_longlong,
+) -> c_int {
+    if handle.is_null() || content_ref.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    let content_ref_str = match CStr::from_ptr(content_ref).to_str() {
+        Ok(s) => s,
+        Err(_) => return -1,
+    };
+
+    let source_app_opt = if source_app.is_null() {
+        None
+    } else {
+        CStr::from_ptr(source_app).to_str().ok().map(|s| s.to_string())
+    };
+
+    let item_kind = match item_kind_from_c(kind) {
+        Some(kind) => kind,
+        None => return -1,
+    };
+
+    let new_item = NewItem {
+        kind: item_kind,
+        content_ref: content_ref_str.to_string(),
+        source_app: source_app_opt,
+        created_at,
+        tags: vec![],
+    };
+
+    match handle.core.enqueue(new_item) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Wait until every item queued with core_enqueue() has been written
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_flush(handle: *mut CoreHandle) -> c_int {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    match handle.core.flush() {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
 /// Search for items
 ///
+/// Inline operators in `query` are applied as filters; use
+/// core_parse_query() to find out why a query is rejected.
+///
 /// # Safety
 /// - handle must be valid
 /// - query must be valid UTF-8 null-terminated string
+/// - Returns NULL on error, including malformed operators
 /// - Caller must call item_array_free() on the returned array
 #[no_mangle]
 pub unsafe extern "C" fn core_search(
@@ -212,142 +440,1862 @@ pub unsafe extern "C" fn core_search(
     items_to_c_array(items)
 }
 
-/// Get a single item by ID
+/// Search with a JSON-encoded query
+///
+/// The query is an object with optional fields: `text`, `limit` (default
+/// 100), `offset`, `collection` (collection ID), `sort` (`"relevance"`,
+/// `"recent"` or `"frecency"`), `mode` (`"standard"`, `"fuzzy"` or
+/// `"regex"`) and the filters `kinds`, `apps`, `tags`, `pinned`, `after`,
+/// `before` and `exclude`, e.g. `{"text": "ssh", "sort": "frecency"}`.
+/// Inline operators in `text` are not parsed; see core_parse_query().
 ///
 /// # Safety
 /// - handle must be valid
-/// - Caller must call item_free() on the returned item
+/// - query_json must be valid UTF-8 null-terminated string
+/// - Returns NULL on error or if the query is malformed
+/// - Caller must call item_array_free() on the returned array
 #[no_mangle]
-pub unsafe extern "C" fn core_get_item(
+pub unsafe extern "C" fn core_search_query(
     handle: *mut CoreHandle,
-    id: c_longlong,
-) -> *mut CItem {
-    if handle.is_null() {
+    query_json: *const c_char,
+) -> *mut CItemArray {
+    if handle.is_null() || query_json.is_null() {
         return ptr::null_mut();
     }
 
     let handle = &*handle;
 
-    match handle.core.get(id) {
-        Ok(item) => item_to_c(item),
+    let query: SearchQuery = match CStr::from_ptr(query_json)
+        .to_str()
+        .ok()
+        .and_then(|s| serde_json::from_str(s).ok())
+    {
+        Some(query) => query,
+        None => return ptr::null_mut(),
+    };
+
+    match handle.core.search_with(&query) {
+        Ok(items) => items_to_c_array(items),
         Err(_) => ptr::null_mut(),
     }
 }
 
-/// Delete an item
+/// Count the items matching a JSON-encoded query as a JSON object
+///
+/// The query has the format taken by core_search_query(). The result has
+/// `total` and `pinned` counts and `kinds`, `apps`, `tags`, `days` and
+/// `weeks` arrays of `{"value", "count"}`, most common (or newest) first.
 ///
-/// Returns 0 on success, -1 on error
+/// # Safety
+/// - handle must be valid
+/// - query_json must be valid UTF-8 null-terminated string
+/// - Returns NULL on error or if the query is malformed
+/// - Caller must call string_free() on the returned string
 #[no_mangle]
-pub unsafe extern "C" fn core_delete_item(
+pub unsafe extern "C" fn core_facets_json(
     handle: *mut CoreHandle,
-    id: c_longlong,
-) -> c_int {
-    if handle.is_null() {
-        return -1;
+    query_json: *const c_char,
+) -> *mut c_char {
+    if handle.is_null() || query_json.is_null() {
+        return ptr::null_mut();
     }
 
     let handle = &*handle;
 
-    match handle.core.delete(id) {
-        Ok(_) => 0,
-        Err(_) => -1,
+    let query: SearchQuery = match CStr::from_ptr(query_json)
+        .to_str()
+        .ok()
+        .and_then(|s| serde_json::from_str(s).ok())
+    {
+        Some(query) => query,
+        None => return ptr::null_mut(),
+    };
+
+    match handle.core.facets(&query) {
+        Ok(facets) => json_to_c_string(&facets),
+        Err(_) => ptr::null_mut(),
     }
 }
 
-/// Pin or unpin an item
+/// Parse search box input with inline operators (`kind:`, `app:`, `tag:`,
+/// `pinned:`, `before:`, `after:`, `-exclude`, `"phrase"`)
+///
+/// Returns `{"query": {...}}` with the query in the format taken by
+/// core_search_query(), or `{"error": {"message": ..., "start": ...,
+/// "end": ...}}` where start and end are character offsets into `query`.
 ///
-/// Returns 0 on success, -1 on error
+/// # Safety
+/// - query must be valid UTF-8 null-terminated string
+/// - Returns NULL if query is not valid UTF-8
+/// - Caller must call string_free() on the returned string
 #[no_mangle]
-pub unsafe extern "C" fn core_pin_item(
-    handle: *mut CoreHandle,
-    id: c_longlong,
-    pinned: c_int,
-) -> c_int {
-    if handle.is_null() {
-        return -1;
+pub unsafe extern "C" fn core_parse_query(query: *const c_char) -> *mut c_char {
+    if query.is_null() {
+        return ptr::null_mut();
     }
 
-    let handle = &*handle;
+    let query_str = match CStr::from_ptr(query).to_str() {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
 
-    match handle.core.pin(id, pinned != 0) {
-        Ok(_) => 0,
-        Err(_) => -1,
+    match SearchQuery::parse(query_str) {
+        Ok(query) => json_to_c_string(&serde_json::json!({ "query": query })),
+        Err(err) => json_to_c_string(&serde_json::json!({ "error": err })),
     }
 }
 
-/// Free a single CItem
+/// Search for items with a match mode
+///
+/// `mode` is 0 for standard, 1 for fuzzy and 2 for regex search. Inline
+/// operators are applied as in core_search(), except in a regex, which is
+/// matched as given. A regex search fails if the pattern is invalid, too
+/// large, or runs too long.
 ///
 /// # Safety
-/// - item must be a valid pointer returned by core_get_item()
+/// - handle must be valid
+/// - query must be valid UTF-8 null-terminated string
+/// - Returns NULL on error or for an unknown mode
+/// - Caller must call item_array_free() on the returned array
 #[no_mangle]
-pub unsafe extern "C" fn item_free(item: *mut CItem) {
-    if item.is_null() {
-        return;
+pub unsafe extern "C" fn core_search_mode(
+    handle: *mut CoreHandle,
+    query: *const c_char,
+    mode: c_int,
+    limit: u32,
+) -> *mut CItemArray {
+    if handle.is_null() || query.is_null() {
+        return ptr::null_mut();
     }
 
-    let item = Box::from_raw(item);
-    if !item.content_ref.is_null() {
-        let _ = CString::from_raw(item.content_ref);
-    }
-    if !item.source_app.is_null() {
-        let _ = CString::from_raw(item.source_app);
-    }
-    if !item.tags_json.is_null() {
-        let _ = CString::from_raw(item.tags_json);
+    let handle = &*handle;
+
+    let query_str = match CStr::from_ptr(query).to_str() {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    let Some(mode) = search_mode_from_c(mode) else {
+        return ptr::null_mut();
+    };
+
+    let query = SearchQuery {
+        mode,
+        ..SearchQuery::new(query_str, limit)
+    };
+
+    match handle.core.search_input(query) {
+        Ok(items) => items_to_c_array(items),
+        Err(_) => ptr::null_mut(),
     }
 }
 
-/// Free a CItemArray
+/// Search for items within a collection
+///
+/// Inline operators are applied as in core_search().
 ///
 /// # Safety
-/// - array must be a valid pointer returned by core_search()
+/// - handle must be valid
+/// - query must be valid UTF-8 null-terminated string
+/// - Caller must call item_array_free() on the returned array
 #[no_mangle]
-pub unsafe extern "C" fn item_array_free(array: *mut CItemArray) {
-    if array.is_null() {
-        return;
+pub unsafe extern "C" fn core_search_in_collection(
+    handle: *mut CoreHandle,
+    query: *const c_char,
+    collection_id: c_longlong,
+    limit: u32,
+) -> *mut CItemArray {
+    if handle.is_null() || query.is_null() {
+        return ptr::null_mut();
     }
 
-    let array = Box::from_raw(array);
-    if !array.items.is_null() {
-        let items = Vec::from_raw_parts(array.items, array.count, array.count);
-        for item in items {
-            if !item.content_ref.is_null() {
-                let _ = CString::from_raw(item.content_ref);
-            }
-            if !item.source_app.is_null() {
-                let _ = CString::from_raw(item.source_app);
-            }
-            if !item.tags_json.is_null() {
-                let _ = CString::from_raw(item.tags_json);
-            }
-        }
-    }
-}
+    let handle = &*handle;
 
-// Helper functions
+    let query_str = match CStr::from_ptr(query).to_str() {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
 
-unsafe fn item_to_c(item: Item) -> *mut CItem {
-    let content_ref = match CString::new(item.content_ref) {
-        Ok(s) => s.into_raw(),
+    let query = SearchQuery {
+        collection: Some(collection_id),
+        ..SearchQuery::new(query_str, limit)
+    };
+
+    match handle.core.search_input(query) {
+        Ok(items) => items_to_c_array(items),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Get a single item by ID
+///
+/// # Safety
+/// - handle must be valid
+/// - Caller must call item_free() on the returned item
+#[no_mangle]
+pub unsafe extern "C" fn core_get_item(
+    handle: *mut CoreHandle,
+    id: c_longlong,
+) -> *mut CItem {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    match handle.core.get(id) {
+        Ok(item) => item_to_c(item),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Move an item to the trash
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_delete_item(
+    handle: *mut CoreHandle,
+    id: c_longlong,
+) -> c_int {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    match handle.core.delete(id) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Delete an item immediately, bypassing the trash
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_delete_item_permanently(
+    handle: *mut CoreHandle,
+    id: c_longlong,
+) -> c_int {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    match handle.core.delete_permanently(id) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Move every unpinned item to the trash
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns the number of items trashed, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_clear_history(handle: *mut CoreHandle) -> c_longlong {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    match handle.core.clear_history() {
+        Ok(count) => count as c_longlong,
+        Err(_) => -1,
+    }
+}
+
+/// List trashed items, most recently deleted first
+///
+/// # Safety
+/// - handle must be valid
+/// - Caller must call item_array_free() on the returned array
+#[no_mangle]
+pub unsafe extern "C" fn core_trash_list(handle: *mut CoreHandle, limit: u32) -> *mut CItemArray {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    match handle.core.trash_list(limit) {
+        Ok(items) => items_to_c_array(items),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Take an item back out of the trash
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_restore_item(handle: *mut CoreHandle, id: c_longlong) -> c_int {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    match handle.core.restore(id) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Permanently delete everything in the trash
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns the number of items removed, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_empty_trash(handle: *mut CoreHandle) -> c_longlong {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    match handle.core.empty_trash() {
+        Ok(count) => count as c_longlong,
+        Err(_) => -1,
+    }
+}
+
+/// Permanently delete trashed items older than the grace period
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns the number of items removed, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_purge_expired_trash(handle: *mut CoreHandle) -> c_longlong {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    match handle.core.purge_expired_trash() {
+        Ok(count) => count as c_longlong,
+        Err(_) => -1,
+    }
+}
+
+/// How long trashed items are kept before they are purged, in milliseconds
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_trash_grace_period_ms(handle: *mut CoreHandle) -> c_longlong {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+    handle.core.trash_grace_period_ms()
+}
+
+/// Set or clear the title and note of an item
+///
+/// # Safety
+/// - handle must be valid
+/// - title and note can be NULL (or empty) to clear them
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_annotate_item(
+    handle: *mut CoreHandle,
+    id: c_longlong,
+    title: *const c_char,
+    note: *const c_char,
+) -> c_int {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    let title_opt = if title.is_null() {
+        None
+    } else {
+        match CStr::from_ptr(title).to_str() {
+            Ok(s) => Some(s),
+            Err(_) => return -1,
+        }
+    };
+
+    let note_opt = if note.is_null() {
+        None
+    } else {
+        match CStr::from_ptr(note).to_str() {
+            Ok(s) => Some(s),
+            Err(_) => return -1,
+        }
+    };
+
+    match handle.core.annotate(id, title_opt, note_opt) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Pin or unpin an item
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_pin_item(
+    handle: *mut CoreHandle,
+    id: c_longlong,
+    pinned: c_int,
+) -> c_int {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    match handle.core.pin(id, pinned != 0) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Replace the content of a text or rtf item, keeping the old content as a version
+///
+/// # Safety
+/// - handle must be valid
+/// - new_text must be valid UTF-8 null-terminated string
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_update_content(
+    handle: *mut CoreHandle,
+    id: c_longlong,
+    new_text: *const c_char,
+) -> c_int {
+    if handle.is_null() || new_text.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    let new_text_str = match CStr::from_ptr(new_text).to_str() {
+        Ok(s) => s,
+        Err(_) => return -1,
+    };
+
+    match handle.core.update_content(id, new_text_str) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// List the previous versions of an item as a JSON array
+///
+/// Each entry is `{"item_id", "version", "content_ref", "replaced_at"}`,
+/// oldest first.
+///
+/// # Safety
+/// - handle must be valid
+/// - Caller must call string_free() on the returned string
+#[no_mangle]
+pub unsafe extern "C" fn core_versions_json(handle: *mut CoreHandle, id: c_longlong) -> *mut c_char {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    match handle.core.versions(id) {
+        Ok(versions) => json_to_c_string(&versions),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Restore an item's content to a previous version
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_revert_item(
+    handle: *mut CoreHandle,
+    id: c_longlong,
+    version: c_longlong,
+) -> c_int {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    match handle.core.revert(id, version) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Create a snippet and return its item ID
+///
+/// # Safety
+/// - handle must be valid
+/// - name, abbreviation and template must be valid UTF-8 null-terminated strings
+/// - Returns the new item ID, or -1 on error (e.g. abbreviation already in use)
+#[no_mangle]
+pub unsafe extern "C" fn core_create_snippet(
+    handle: *mut CoreHandle,
+    name: *const c_char,
+    abbreviation: *const c_char,
+    template: *const c_char,
+) -> c_longlong {
+    if handle.is_null() || name.is_null() || abbreviation.is_null() || template.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    let name_str = match CStr::from_ptr(name).to_str() {
+        Ok(s) => s,
+        Err(_) => return -1,
+    };
+
+    let abbreviation_str = match CStr::from_ptr(abbreviation).to_str() {
+        Ok(s) => s,
+        Err(_) => return -1,
+    };
+
+    let template_str = match CStr::from_ptr(template).to_str() {
+        Ok(s) => s,
+        Err(_) => return -1,
+    };
+
+    handle
+        .core
+        .create_snippet(name_str, abbreviation_str, template_str)
+        .unwrap_or(-1)
+}
+
+/// Turn a text item into a snippet, or change its abbreviation
+///
+/// # Safety
+/// - handle must be valid
+/// - abbreviation must be valid UTF-8 null-terminated string
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_set_snippet_abbreviation(
+    handle: *mut CoreHandle,
+    id: c_longlong,
+    abbreviation: *const c_char,
+) -> c_int {
+    if handle.is_null() || abbreviation.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    let abbreviation_str = match CStr::from_ptr(abbreviation).to_str() {
+        Ok(s) => s,
+        Err(_) => return -1,
+    };
+
+    match handle.core.set_snippet_abbreviation(id, abbreviation_str) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Remove an item's abbreviation, keeping the item
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_remove_snippet(handle: *mut CoreHandle, id: c_longlong) -> c_int {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    match handle.core.remove_snippet(id) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// List all snippets as a JSON array
+///
+/// Each entry is `{"id", "abbreviation", "name", "template"}`, ordered by
+/// abbreviation.
+///
+/// # Safety
+/// - handle must be valid
+/// - Caller must call string_free() on the returned string
+#[no_mangle]
+pub unsafe extern "C" fn core_snippets_json(handle: *mut CoreHandle) -> *mut c_char {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    match handle.core.snippets() {
+        Ok(snippets) => json_to_c_string(&snippets),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Render the snippet with the given abbreviation
+///
+/// # Safety
+/// - handle must be valid
+/// - abbreviation must be valid UTF-8 null-terminated string
+/// - cursor_out can be NULL; otherwise it receives the `{{cursor}}` position
+///   in Unicode scalar values, or -1 if the template has none
+/// - Returns NULL if no snippet matches or on error
+/// - Caller must call string_free() on the returned string
+#[no_mangle]
+pub unsafe extern "C" fn core_expand_snippet(
+    handle: *mut CoreHandle,
+    abbreviation: *const c_char,
+    cursor_out: *mut c_longlong,
+) -> *mut c_char {
+    if handle.is_null() || abbreviation.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    let abbreviation_str = match CStr::from_ptr(abbreviation).to_str() {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    match handle.core.expand_snippet(abbreviation_str) {
+        Ok(Some(rendered)) => rendered_to_c(rendered, cursor_out),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Render a text item as a template
+///
+/// # Safety
+/// - handle must be valid
+/// - cursor_out can be NULL; otherwise it receives the `{{cursor}}` position
+///   in Unicode scalar values, or -1 if the template has none
+/// - Returns NULL on error
+/// - Caller must call string_free() on the returned string
+#[no_mangle]
+pub unsafe extern "C" fn core_render_template(
+    handle: *mut CoreHandle,
+    id: c_longlong,
+    cursor_out: *mut c_longlong,
+) -> *mut c_char {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    match handle.core.render_template(id) {
+        Ok(rendered) => rendered_to_c(rendered, cursor_out),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Create a collection and return its ID
+///
+/// # Safety
+/// - handle must be valid
+/// - name must be valid UTF-8 null-terminated string
+/// - Returns the new collection ID, or -1 on error (e.g. name already in use)
+#[no_mangle]
+pub unsafe extern "C" fn core_create_collection(
+    handle: *mut CoreHandle,
+    name: *const c_char,
+) -> c_longlong {
+    if handle.is_null() || name.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    let name_str = match CStr::from_ptr(name).to_str() {
+        Ok(s) => s,
+        Err(_) => return -1,
+    };
+
+    handle.core.create_collection(name_str).unwrap_or(-1)
+}
+
+/// Rename a collection
+///
+/// # Safety
+/// - handle must be valid
+/// - name must be valid UTF-8 null-terminated string
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_rename_collection(
+    handle: *mut CoreHandle,
+    id: c_longlong,
+    name: *const c_char,
+) -> c_int {
+    if handle.is_null() || name.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    let name_str = match CStr::from_ptr(name).to_str() {
+        Ok(s) => s,
+        Err(_) => return -1,
+    };
+
+    match handle.core.rename_collection(id, name_str) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Delete a collection, keeping its items
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_delete_collection(handle: *mut CoreHandle, id: c_longlong) -> c_int {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    match handle.core.delete_collection(id) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// List all collections as a JSON array
+///
+/// Each entry is `{"id", "name", "created_at", "item_count"}`, ordered by name.
+///
+/// # Safety
+/// - handle must be valid
+/// - Caller must call string_free() on the returned string
+#[no_mangle]
+pub unsafe extern "C" fn core_collections_json(handle: *mut CoreHandle) -> *mut c_char {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    match handle.core.collections() {
+        Ok(collections) => json_to_c_string(&collections),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// List the collections an item belongs to as a JSON array
+///
+/// # Safety
+/// - handle must be valid
+/// - Caller must call string_free() on the returned string
+#[no_mangle]
+pub unsafe extern "C" fn core_item_collections_json(
+    handle: *mut CoreHandle,
+    item_id: c_longlong,
+) -> *mut c_char {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    match handle.core.item_collections(item_id) {
+        Ok(collections) => json_to_c_string(&collections),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// List the items of a collection in their manual order
+///
+/// # Safety
+/// - handle must be valid
+/// - Caller must call item_array_free() on the returned array
+#[no_mangle]
+pub unsafe extern "C" fn core_collection_items(
+    handle: *mut CoreHandle,
+    id: c_longlong,
+) -> *mut CItemArray {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    match handle.core.collection_items(id) {
+        Ok(items) => items_to_c_array(items),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Add an item to a collection
+///
+/// # Safety
+/// - handle must be valid
+/// - position is 0-based; pass -1 to append
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_collection_add_item(
+    handle: *mut CoreHandle,
+    id: c_longlong,
+    item_id: c_longlong,
+    position: c_longlong,
+) -> c_int {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+    let position = usize::try_from(position).ok();
+
+    match handle.core.add_to_collection(id, item_id, position) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Remove an item from a collection, keeping the item
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_collection_remove_item(
+    handle: *mut CoreHandle,
+    id: c_longlong,
+    item_id: c_longlong,
+) -> c_int {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    match handle.core.remove_from_collection(id, item_id) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Move an item to a new 0-based position within a collection
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_collection_move_item(
+    handle: *mut CoreHandle,
+    id: c_longlong,
+    item_id: c_longlong,
+    position: c_longlong,
+) -> c_int {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    let Ok(position) = usize::try_from(position) else {
+        return -1;
+    };
+
+    match handle.core.move_in_collection(id, item_id, position) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Save a JSON-encoded search under a name and return its ID
+///
+/// The query has the format taken by core_search_query(); inline operators
+/// in its `text` are parsed each time the search runs.
+///
+/// # Safety
+/// - handle must be valid
+/// - name and query_json must be valid UTF-8 null-terminated strings
+/// - Returns the new saved search ID, or -1 on error (e.g. name already in
+///   use, malformed query)
+#[no_mangle]
+pub unsafe extern "C" fn core_save_search(
+    handle: *mut CoreHandle,
+    name: *const c_char,
+    query_json: *const c_char,
+) -> c_longlong {
+    if handle.is_null() || name.is_null() || query_json.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    let name_str = match CStr::from_ptr(name).to_str() {
+        Ok(s) => s,
+        Err(_) => return -1,
+    };
+
+    let query: SearchQuery = match CStr::from_ptr(query_json)
+        .to_str()
+        .ok()
+        .and_then(|s| serde_json::from_str(s).ok())
+    {
+        Some(query) => query,
+        None => return -1,
+    };
+
+    handle.core.save_search(name_str, &query).unwrap_or(-1)
+}
+
+/// Replace the name and JSON-encoded query of a saved search
+///
+/// # Safety
+/// - handle must be valid
+/// - name and query_json must be valid UTF-8 null-terminated strings
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_update_saved_search(
+    handle: *mut CoreHandle,
+    id: c_longlong,
+    name: *const c_char,
+    query_json: *const c_char,
+) -> c_int {
+    if handle.is_null() || name.is_null() || query_json.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    let name_str = match CStr::from_ptr(name).to_str() {
+        Ok(s) => s,
+        Err(_) => return -1,
+    };
+
+    let query: SearchQuery = match CStr::from_ptr(query_json)
+        .to_str()
+        .ok()
+        .and_then(|s| serde_json::from_str(s).ok())
+    {
+        Some(query) => query,
+        None => return -1,
+    };
+
+    match handle.core.update_saved_search(id, name_str, &query) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Delete a saved search
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_delete_saved_search(handle: *mut CoreHandle, id: c_longlong) -> c_int {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    match handle.core.delete_saved_search(id) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// List all saved searches as a JSON array
+///
+/// Each entry is `{"id", "name", "query", "created_at"}`, ordered by name.
+///
+/// # Safety
+/// - handle must be valid
+/// - Caller must call string_free() on the returned string
+#[no_mangle]
+pub unsafe extern "C" fn core_saved_searches_json(handle: *mut CoreHandle) -> *mut c_char {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    match handle.core.list_saved_searches() {
+        Ok(searches) => json_to_c_string(&searches),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Run a saved search and return one page (0-based) of results
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns NULL on error
+/// - Caller must call item_array_free() on the returned array
+#[no_mangle]
+pub unsafe extern "C" fn core_run_saved_search(
+    handle: *mut CoreHandle,
+    id: c_longlong,
+    page: u32,
+) -> *mut CItemArray {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    match handle.core.run_saved_search(id, page) {
+        Ok(items) => items_to_c_array(items),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Bind an item to quick-paste slot 1-9
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns 0 on success, -1 on error (e.g. slot out of range)
+#[no_mangle]
+pub unsafe extern "C" fn core_assign_slot(
+    handle: *mut CoreHandle,
+    slot: c_int,
+    id: c_longlong,
+) -> c_int {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    let Ok(slot) = u8::try_from(slot) else {
+        return -1;
+    };
+
+    match handle.core.assign_slot(slot, id) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Empty a quick-paste slot
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns 1 if the slot held an item, 0 if it was empty, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_clear_slot(handle: *mut CoreHandle, slot: c_int) -> c_int {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    let Ok(slot) = u8::try_from(slot) else {
+        return -1;
+    };
+
+    match handle.core.clear_slot(slot) {
+        Ok(cleared) => cleared as c_int,
+        Err(_) => -1,
+    }
+}
+
+/// Get the item in a quick-paste slot
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns NULL if the slot is empty or on error
+/// - Caller must call item_free() on the returned item
+#[no_mangle]
+pub unsafe extern "C" fn core_slot_item(handle: *mut CoreHandle, slot: c_int) -> *mut CItem {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    let Ok(slot) = u8::try_from(slot) else {
+        return ptr::null_mut();
+    };
+
+    match handle.core.slot(slot) {
+        Ok(Some(item)) => item_to_c(item),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// List occupied quick-paste slots as a JSON array
+///
+/// Each entry is `{"slot", "item"}`, in slot order. `item` has the CItem
+/// fields, with `kind` as a string (`"text"`, `"image"`, ...), `tags` as an
+/// array and unset `deleted_at`/`title`/`note` as null.
+///
+/// # Safety
+/// - handle must be valid
+/// - Caller must call string_free() on the returned string
+#[no_mangle]
+pub unsafe extern "C" fn core_slots_json(handle: *mut CoreHandle) -> *mut c_char {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    match handle.core.slots() {
+        Ok(slots) => json_to_c_string(&slots),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Record that an item was pasted, for frecency ranking
+///
+/// # Safety
+/// - handle must be valid
+/// - target_app can be NULL if unknown
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_record_use(
+    handle: *mut CoreHandle,
+    id: c_longlong,
+    target_app: *const c_char,
+) -> c_int {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    let target_app_opt = if target_app.is_null() {
+        None
+    } else {
+        match CStr::from_ptr(target_app).to_str() {
+            Ok(s) => Some(s),
+            Err(_) => return -1,
+        }
+    };
+
+    match handle.core.record_use(id, target_app_opt) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Suggest items for pasting into an app, by per-app paste frequency and recency
+///
+/// # Safety
+/// - handle must be valid
+/// - target_app must be valid UTF-8 null-terminated string
+/// - Caller must call item_array_free() on the returned array
+#[no_mangle]
+pub unsafe extern "C" fn core_suggest(
+    handle: *mut CoreHandle,
+    target_app: *const c_char,
+    limit: u32,
+) -> *mut CItemArray {
+    if handle.is_null() || target_app.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    let target_app_str = match CStr::from_ptr(target_app).to_str() {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    match handle.core.suggest(target_app_str, limit) {
+        Ok(items) => items_to_c_array(items),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Record that the user picked an item from the results for a query
+///
+/// # Safety
+/// - handle must be valid
+/// - query must be valid UTF-8 null-terminated string
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_record_selection(
+    handle: *mut CoreHandle,
+    query: *const c_char,
+    item_id: c_longlong,
+) -> c_int {
+    if handle.is_null() || query.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    let query_str = match CStr::from_ptr(query).to_str() {
+        Ok(s) => s,
+        Err(_) => return -1,
+    };
+
+    match handle.core.record_selection(query_str, item_id) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Forget all learned query/item associations
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns the number of associations removed, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_reset_learned_ranking(handle: *mut CoreHandle) -> c_longlong {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    match handle.core.reset_learned_ranking() {
+        Ok(count) => count as c_longlong,
+        Err(_) => -1,
+    }
+}
+
+/// Get the paste queue options as JSON
+///
+/// Returns `{"mode": "fifo" | "lifo", "active": bool, "auto_enqueue": bool}`.
+///
+/// # Safety
+/// - handle must be valid
+/// - Caller must call string_free() on the returned string
+#[no_mangle]
+pub unsafe extern "C" fn core_paste_queue_json(handle: *mut CoreHandle) -> *mut c_char {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    match handle.core.paste_queue() {
+        Ok(options) => json_to_c_string(&options),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Set the paste queue options from JSON
+///
+/// Takes the same object as core_paste_queue_json(); missing fields take
+/// their defaults (FIFO, inactive, no auto-enqueue).
+///
+/// # Safety
+/// - handle must be valid
+/// - options_json must be valid UTF-8 null-terminated string
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_set_paste_queue_json(
+    handle: *mut CoreHandle,
+    options_json: *const c_char,
+) -> c_int {
+    if handle.is_null() || options_json.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    let options: PasteQueue = match CStr::from_ptr(options_json)
+        .to_str()
+        .ok()
+        .and_then(|s| serde_json::from_str(s).ok())
+    {
+        Some(options) => options,
+        None => return -1,
+    };
+
+    match handle.core.set_paste_queue(&options) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Add an item to the paste queue
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_queue_push(handle: *mut CoreHandle, id: c_longlong) -> c_int {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    match handle.core.queue_push(id) {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Remove and return the next item from the paste queue
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns NULL if the queue is empty or on error
+/// - Caller must call item_free() on the returned item
+#[no_mangle]
+pub unsafe extern "C" fn core_queue_pop(handle: *mut CoreHandle) -> *mut CItem {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    match handle.core.queue_pop() {
+        Ok(Some(item)) => item_to_c(item),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Return the next item from the paste queue without removing it
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns NULL if the queue is empty or on error
+/// - Caller must call item_free() on the returned item
+#[no_mangle]
+pub unsafe extern "C" fn core_queue_peek(handle: *mut CoreHandle) -> *mut CItem {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    match handle.core.queue_peek() {
+        Ok(Some(item)) => item_to_c(item),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// List queued items in the order they will be pasted
+///
+/// # Safety
+/// - handle must be valid
+/// - Caller must call item_array_free() on the returned array
+#[no_mangle]
+pub unsafe extern "C" fn core_queue_items(handle: *mut CoreHandle) -> *mut CItemArray {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    match handle.core.queue_items() {
+        Ok(items) => items_to_c_array(items),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Empty the paste queue
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns the number of entries removed, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn core_queue_clear(handle: *mut CoreHandle) -> c_longlong {
+    if handle.is_null() {
+        return -1;
+    }
+
+    let handle = &*handle;
+
+    match handle.core.queue_clear() {
+        Ok(count) => count as c_longlong,
+        Err(_) => -1,
+    }
+}
+
+/// Get the storage and usage report as JSON
+///
+/// Returns an object with `total`, `trashed`, `text_bytes`, `blob_bytes`,
+/// `database_bytes` and `wal_bytes`, the `oldest` and `newest` items (or
+/// null), `kinds`, `apps` and `days` arrays of `{"value", "count"}`, and
+/// `dedupe` with `attempts`, `hits` and `hit_rate`.
+///
+/// # Safety
+/// - handle must be valid
+/// - Returns NULL on error
+/// - Caller must call string_free() on the returned string
+#[no_mangle]
+pub unsafe extern "C" fn core_stats_json(handle: *mut CoreHandle) -> *mut c_char {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    match handle.core.stats() {
+        Ok(stats) => json_to_c_string(&stats),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Progress callback for core_maintenance_json(): the step code (0=integrity
+/// check, 1=FTS integrity check, 2=FTS rebuild, 3=FTS optimize, 4=vacuum,
+/// 5=analyze, 6=WAL checkpoint), its 0-based index among the selected
+/// steps, the number of selected steps, and the caller's user_data.
+pub type MaintenanceProgressFn =
+    extern "C" fn(step: c_int, index: c_int, total: c_int, user_data: *mut c_void);
+
+/// Run database maintenance and return the report as JSON
+///
+/// options_json selects steps: `{"integrity_check", "fts_integrity_check",
+/// "fts_rebuild", "fts_optimize", "vacuum", "analyze", "checkpoint"}`, all
+/// booleans; missing fields (or a NULL options_json) take their defaults,
+/// which enable everything but `fts_rebuild`. The report has `steps`,
+/// `integrity_errors`, `fts_errors`, `bytes_freed`, `checkpoint` (`{"busy",
+/// "wal_frames", "checkpointed_frames"}` or null) and `duration_ms`.
+///
+/// # Safety
+/// - handle must be valid
+/// - options_json must be NULL or a valid UTF-8 null-terminated string
+/// - progress may be NULL; it is called on the calling thread before each step
+/// - Returns NULL on error or if the options are malformed
+/// - Caller must call string_free() on the returned string
+#[no_mangle]
+pub unsafe extern "C" fn core_maintenance_json(
+    handle: *mut CoreHandle,
+    options_json: *const c_char,
+    progress: Option<MaintenanceProgressFn>,
+    user_data: *mut c_void,
+) -> *mut c_char {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    let options: MaintenanceOptions = match optional_json_from_c(options_json) {
+        Some(options) => options,
+        None => return ptr::null_mut(),
+    };
+
+    let result = handle.core.maintenance_with_progress(&options, |p| {
+        if let Some(progress) = progress {
+            progress(p.step.code(), p.index as c_int, p.total as c_int, user_data);
+        }
+    });
+
+    match result {
+        Ok(report) => json_to_c_string(&report),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Progress callback for backups and restores: the stage (0=database pages,
+/// 1=blob files), how many pages or files are done, how many there are in
+/// the stage, and the caller's user_data.
+pub type BackupProgressFn =
+    extern "C" fn(stage: c_int, done: c_longlong, total: c_longlong, user_data: *mut c_void);
+
+fn backup_progress(
+    progress: Option<BackupProgressFn>,
+    user_data: *mut c_void,
+) -> impl FnMut(BackupProgress) {
+    move |p| {
+        if let Some(progress) = progress {
+            progress(p.stage.code(), p.done as c_longlong, p.total as c_longlong, user_data);
+        }
+    }
+}
+
+/// Back up the database to a single file and return the report as JSON
+///
+/// options_json is `{"include_blobs": bool}`; a missing field or a NULL
+/// options_json means blobs are not included. The report has `path`,
+/// `bytes`, `blobs`, `removed` and `duration_ms`.
+///
+/// # Safety
+/// - handle must be valid
+/// - path must be a valid UTF-8 null-terminated string
+/// - options_json must be NULL or a valid UTF-8 null-terminated string
+/// - progress may be NULL; it is called on the calling thread
+/// - Returns NULL on error
+/// - Caller must call string_free() on the returned string
+#[no_mangle]
+pub unsafe extern "C" fn core_backup_to(
+    handle: *mut CoreHandle,
+    path: *const c_char,
+    options_json: *const c_char,
+    progress: Option<BackupProgressFn>,
+    user_data: *mut c_void,
+) -> *mut c_char {
+    if handle.is_null() || path.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    let path_str = match CStr::from_ptr(path).to_str() {
+        Ok(s) => s,
         Err(_) => return ptr::null_mut(),
     };
 
-    let source_app = match item.source_app {
-        Some(app) => match CString::new(app) {
-            Ok(s) => s.into_raw(),
-            Err(_) => ptr::null_mut(),
-        },
-        None => ptr::null_mut(),
+    let options: BackupOptions = match optional_json_from_c(options_json) {
+        Some(options) => options,
+        None => return ptr::null_mut(),
+    };
+
+    match handle.core.backup_to_with_progress(
+        Path::new(path_str),
+        &options,
+        backup_progress(progress, user_data),
+    ) {
+        Ok(report) => json_to_c_string(&report),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Write a timestamped backup to a directory, keeping the `keep` newest
+///
+/// Takes the same options and returns the same report as core_backup_to();
+/// `removed` lists the old backups that were deleted.
+///
+/// # Safety
+/// - handle must be valid
+/// - dir must be a valid UTF-8 null-terminated string
+/// - options_json must be NULL or a valid UTF-8 null-terminated string
+/// - progress may be NULL; it is called on the calling thread
+/// - Returns NULL on error, or if keep is 0
+/// - Caller must call string_free() on the returned string
+#[no_mangle]
+pub unsafe extern "C" fn core_backup_rotating(
+    handle: *mut CoreHandle,
+    dir: *const c_char,
+    keep: u32,
+    options_json: *const c_char,
+    progress: Option<BackupProgressFn>,
+    user_data: *mut c_void,
+) -> *mut c_char {
+    if handle.is_null() || dir.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    let dir_str = match CStr::from_ptr(dir).to_str() {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    let options: BackupOptions = match optional_json_from_c(options_json) {
+        Some(options) => options,
+        None => return ptr::null_mut(),
+    };
+
+    match handle.core.backup_rotating_with_progress(
+        Path::new(dir_str),
+        keep as usize,
+        &options,
+        backup_progress(progress, user_data),
+    ) {
+        Ok(report) => json_to_c_string(&report),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// List the backups written by core_backup_rotating() as a JSON array of
+/// paths, newest first
+///
+/// # Safety
+/// - handle must be valid
+/// - dir must be a valid UTF-8 null-terminated string
+/// - Returns NULL on error
+/// - Caller must call string_free() on the returned string
+#[no_mangle]
+pub unsafe extern "C" fn core_list_backups_json(
+    handle: *mut CoreHandle,
+    dir: *const c_char,
+) -> *mut c_char {
+    if handle.is_null() || dir.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    let dir_str = match CStr::from_ptr(dir).to_str() {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    match handle.core.list_backups(Path::new(dir_str)) {
+        Ok(backups) => json_to_c_string(&backups),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Replace the database with a backup and return the report as JSON
+///
+/// The report has `schema_version`, `items`, `blobs` and `duration_ms`.
+/// The current database is untouched if the backup is invalid, damaged or
+/// from a newer version.
+///
+/// # Safety
+/// - handle must be valid
+/// - path must be a valid UTF-8 null-terminated string
+/// - progress may be NULL; it is called on the calling thread
+/// - Returns NULL on error
+/// - Caller must call string_free() on the returned string
+#[no_mangle]
+pub unsafe extern "C" fn core_restore_from(
+    handle: *mut CoreHandle,
+    path: *const c_char,
+    progress: Option<BackupProgressFn>,
+    user_data: *mut c_void,
+) -> *mut c_char {
+    if handle.is_null() || path.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    let path_str = match CStr::from_ptr(path).to_str() {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    match handle
+        .core
+        .restore_from_with_progress(Path::new(path_str), backup_progress(progress, user_data))
+    {
+        Ok(report) => json_to_c_string(&report),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Export items to a file and return the report as JSON
+///
+/// options_json has `format` (`"jsonl"`, `"csv"` or `"markdown"`, default
+/// `"jsonl"`), `query` (a query as taken by core_search_query(); all items
+/// if missing) and `sidecar_dir` (directory to copy the files of image and
+/// file items to; none if missing), all optional, or is NULL to export
+/// everything as JSON Lines. The report has `items`, `blobs`,
+/// `missing_blobs` and `duration_ms`.
+///
+/// # Safety
+/// - handle must be valid
+/// - path must be a valid UTF-8 null-terminated string
+/// - options_json must be NULL or a valid UTF-8 null-terminated string
+/// - Returns NULL on error or if the options are malformed
+/// - Caller must call string_free() on the returned string
+#[no_mangle]
+pub unsafe extern "C" fn core_export(
+    handle: *mut CoreHandle,
+    path: *const c_char,
+    options_json: *const c_char,
+) -> *mut c_char {
+    if handle.is_null() || path.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    let path_str = match CStr::from_ptr(path).to_str() {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    let options: ExportOptions = match optional_json_from_c(options_json) {
+        Some(options) => options,
+        None => return ptr::null_mut(),
+    };
+
+    match handle.core.export_to(Path::new(path_str), &options) {
+        Ok(report) => json_to_c_string(&report),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Import the history of another clipboard manager and return the report
+/// as JSON
+///
+/// options_json has `format` (`"maccy"`, `"copyq"`, `"gpaste"`, `"json"` or
+/// `"plain_text"`) and optionally `source_app` (for items whose origin
+/// isn't recorded), `tags` (added to every item) and `separator` (between
+/// plain-text items, a newline by default). The report has `format`,
+/// `imported`, `duplicates`, `skipped`, `errors` and `duration_ms`.
+///
+/// # Safety
+/// - handle must be valid
+/// - path must be a valid UTF-8 null-terminated string
+/// - options_json must be a valid UTF-8 null-terminated string
+/// - Returns NULL on error or if the options are malformed
+/// - Caller must call string_free() on the returned string
+#[no_mangle]
+pub unsafe extern "C" fn core_import(
+    handle: *mut CoreHandle,
+    path: *const c_char,
+    options_json: *const c_char,
+) -> *mut c_char {
+    if handle.is_null() || path.is_null() || options_json.is_null() {
+        return ptr::null_mut();
+    }
+
+    let handle = &*handle;
+
+    let path_str = match CStr::from_ptr(path).to_str() {
+        Ok(s) => s,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    let options: ImportOptions = match CStr::from_ptr(options_json)
+        .to_str()
+        .ok()
+        .and_then(|s| serde_json::from_str(s).ok())
+    {
+        Some(options) => options,
+        None => return ptr::null_mut(),
     };
 
-    let tags_json = match serde_json::to_string(&item.tags) {
+    match handle.core.import(Path::new(path_str), &options) {
+        Ok(report) => json_to_c_string(&report),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Free a single CItem
+///
+/// # Safety
+/// - item must be a valid pointer returned by core_get_item()
+#[no_mangle]
+pub unsafe extern "C" fn item_free(item: *mut CItem) {
+    if item.is_null() {
+        return;
+    }
+
+    let item = Box::from_raw(item);
+    free_c_item_strings(&item);
+}
+
+/// Free a string returned by the library
+///
+/// # Safety
+/// - s must be a valid pointer returned by a core_* function, or NULL
+#[no_mangle]
+pub unsafe extern "C" fn string_free(s: *mut c_char) {
+    if !s.is_null() {
+        let _ = CString::from_raw(s);
+    }
+}
+
+/// Free a CItemArray
+///
+/// # Safety
+/// - array must be a valid pointer returned by core_search()
+#[no_mangle]
+pub unsafe extern "C" fn item_array_free(array: *mut CItemArray) {
+    if array.is_null() {
+        return;
+    }
+
+    let array = Box::from_raw(array);
+    if !array.items.is_null() {
+        let items = Vec::from_raw_parts(array.items, array.count, array.count);
+        for item in items {
+            free_c_item_strings(&item);
+        }
+    }
+}
+
+// Helper functions
+
+fn json_to_c_string<T: serde::Serialize + ?Sized>(value: &T) -> *mut c_char {
+    match serde_json::to_string(value) {
         Ok(json) => match CString::new(json) {
             Ok(s) => s.into_raw(),
             Err(_) => ptr::null_mut(),
         },
         Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Convert rendered template text to a C string, writing the cursor position
+/// to `cursor_out` if non-null.
+unsafe fn rendered_to_c(rendered: Rendered, cursor_out: *mut c_longlong) -> *mut c_char {
+    let text = match CString::new(rendered.text) {
+        Ok(s) => s.into_raw(),
+        Err(_) => return ptr::null_mut(),
     };
 
+    if !cursor_out.is_null() {
+        *cursor_out = rendered.cursor.map_or(-1, |c| c as c_longlong);
+    }
+
+    text
+}
+
+fn item_kind_from_c(kind: c_int) -> Option<ItemKind> {
+    match kind {
+        0 => Some(ItemKind::Text),
+        1 => Some(ItemKind::Rtf),
+        2 => Some(ItemKind::Image),
+        3 => Some(ItemKind::File),
+        _ => None,
+    }
+}
+
+/// Parse an optional JSON argument: NULL gives the default value, and
+/// malformed JSON gives `None`.
+unsafe fn optional_json_from_c<T: serde::de::DeserializeOwned + Default>(
+    json: *const c_char,
+) -> Option<T> {
+    if json.is_null() {
+        return Some(T::default());
+    }
+
+    CStr::from_ptr(json)
+        .to_str()
+        .ok()
+        .and_then(|s| serde_json::from_str(s).ok())
+}
+
+fn search_mode_from_c(mode: c_int) -> Option<SearchMode> {
+    match mode {
+        0 => Some(SearchMode::Standard),
+        1 => Some(SearchMode::Fuzzy),
+        2 => Some(SearchMode::Regex),
+        _ => None,
+    }
+}
+
+fn string_to_c(s: Option<String>) -> *mut c_char {
+    match s {
+        Some(s) => match CString::new(s) {
+            Ok(s) => s.into_raw(),
+            Err(_) => ptr::null_mut(),
+        },
+        None => ptr::null_mut(),
+    }
+}
+
+/// Convert an Item into a CItem, or None if its content can't be
+/// represented as a C string.
+fn item_to_c_struct(item: Item) -> Option<CItem> {
+    let content_ref = CString::new(item.content_ref).ok()?.into_raw();
+
     let kind = match item.kind {
         ItemKind::Text => 0,
         ItemKind::Rtf => 1,
@@ -355,67 +2303,51 @@ unsafe fn item_to_c(item: Item) -> *mut CItem {
         ItemKind::File => 3,
     };
 
-    Box::into_raw(Box::new(CItem {
+    Some(CItem {
         id: item.id,
         kind,
         content_ref,
-        source_app,
+        source_app: string_to_c(item.source_app),
         created_at: item.created_at,
         pinned: if item.pinned { 1 } else { 0 },
-        tags_json,
-    }))
+        tags_json: json_to_c_string(&item.tags),
+        deleted_at: item.deleted_at.unwrap_or(0),
+        title: string_to_c(item.title),
+        note: string_to_c(item.note),
+    })
 }
 
-unsafe fn items_to_c_array(items: Vec<Item>) -> *mut CItemArray {
-    let mut c_items = Vec::with_capacity(items.len());
+/// Free the strings owned by a CItem.
+unsafe fn free_c_item_strings(item: &CItem) {
+    for s in [
+        item.content_ref,
+        item.source_app,
+        item.tags_json,
+        item.title,
+        item.note,
+    ] {
+        if !s.is_null() {
+            let _ = CString::from_raw(s);
+        }
+    }
+}
 
-    for item in items {
-        let content_ref = match CString::new(item.content_ref) {
-            Ok(s) => s.into_raw(),
-            Err(_) => continue,
-        };
-
-        let source_app = match item.source_app {
-            Some(app) => match CString::new(app) {
-                Ok(s) => s.into_raw(),
-                Err(_) => ptr::null_mut(),
-            },
-            None => ptr::null_mut(),
-        };
-
-        let tags_json = match serde_json::to_string(&item.tags) {
-            Ok(json) => match CString::new(json) {
-                Ok(s) => s.into_raw(),
-                Err(_) => ptr::null_mut(),
-            },
-            Err(_) => ptr::null_mut(),
-        };
-
-        let kind = match item.kind {
-            ItemKind::Text => 0,
-            ItemKind::Rtf => 1,
-            ItemKind::Image => 2,
-            ItemKind::File => 3,
-        };
-
-        c_items.push(CItem {
-            id: item.id,
-            kind,
-            content_ref,
-            source_app,
-            created_at: item.created_at,
-            pinned: if item.pinned { 1 } else { 0 },
-            tags_json,
-        });
+unsafe fn item_to_c(item: Item) -> *mut CItem {
+    match item_to_c_struct(item) {
+        Some(c_item) => Box::into_raw(Box::new(c_item)),
+        None => ptr::null_mut(),
     }
+}
+
+unsafe fn items_to_c_array(items: Vec<Item>) -> *mut CItemArray {
+    // Boxed slice so capacity == count when item_array_free() rebuilds the Vec
+    let c_items: Box<[CItem]> = items.into_iter().filter_map(item_to_c_struct).collect();
 
     let count = c_items.len();
-    let items_ptr = c_items.as_mut_ptr();
-    std::mem::forget(c_items);
+    let items_ptr = Box::into_raw(c_items) as *mut CItem;
 
     Box::into_raw(Box::new(CItemArray {
         items: items_ptr,
         count,
     }))
 }
-
@@ -0,0 +1,398 @@
+//! Fuzzy, typo-tolerant search.
+//!
+//! Three matchers run over the whole database and their results are merged
+//! into one score per item:
+//!
+//! 1. **Substring**: the query as a phrase against `items_trigram`, so
+//!    "config" finds "kubeconfig". Worth [`SUBSTRING_SCORE`].
+//! 2. **Typo**: each query word is replaced by the `items_fts` vocabulary
+//!    terms within a small edit distance (via `items_fts_vocab`), so
+//!    "kubctl" finds "kubectl". Worth [`TYPO_SCORE`].
+//! 3. **Subsequence**: fzf-style matching of the query characters in order
+//!    ("hlo" in "hello"), scored from 0 to 1 by the `fuzzy_score()` SQL
+//!    function, with bonuses for word boundaries and consecutive
+//!    characters. Matches content, title and note of every kind.
+//!
+//! Items are ordered by the sum, then newest first.
+
+use anyhow::{Context, Result};
+use rusqlite::functions::FunctionFlags;
+use rusqlite::{params_from_iter, Connection};
+use std::collections::HashMap;
+
+use crate::db::{Database, ITEM_COLUMNS};
+use crate::models::Item;
+use crate::search::{Params, SearchQuery};
+
+/// Added for a substring match.
+pub const SUBSTRING_SCORE: f64 = 2.0;
+/// Added for a match after typo correction.
+pub const TYPO_SCORE: f64 = 1.0;
+
+/// Only the start of long texts is scanned for subsequence matches.
+const MAX_SCAN_CHARS: usize = 2000;
+
+const SCORE_MATCH: i64 = 16;
+const SCORE_GAP_START: i64 = -3;
+const SCORE_GAP_EXTENSION: i64 = -1;
+const BONUS_BOUNDARY: i64 = 8;
+const BONUS_CAMEL_CASE: i64 = 7;
+const BONUS_CONSECUTIVE: i64 = 4;
+const BONUS_FIRST_CHAR_MULTIPLIER: i64 = 2;
+
+/// Register `fuzzy_score(pattern, text)` on a connection.
+pub fn register(conn: &Connection) -> Result<()> {
+    conn.create_scalar_function(
+        "fuzzy_score",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let pattern = ctx.get_raw(0).as_str().unwrap_or("");
+            let text = ctx.get_raw(1).as_str().unwrap_or("");
+            let pattern: Vec<char> = pattern
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .flat_map(char::to_lowercase)
+                .collect();
+            Ok(subsequence_score(&pattern, text).unwrap_or(0.0))
+        },
+    )
+    .context("Failed to register fuzzy_score")
+}
+
+/// Score `text` as an fzf-style subsequence match for `pattern`, which must
+/// be lowercase. Returns a value in (0, 1], or `None` if not all pattern
+/// characters occur in order.
+pub fn subsequence_score(pattern: &[char], text: &str) -> Option<f64> {
+    if pattern.is_empty() {
+        return None;
+    }
+
+    let text: Vec<char> = text.chars().take(MAX_SCAN_CHARS).collect();
+    let lower: Vec<char> = text
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+
+    // Forward pass: where the earliest complete match ends
+    let mut pi = 0;
+    let mut end = 0;
+    for (i, &c) in lower.iter().enumerate() {
+        if c == pattern[pi] {
+            pi += 1;
+            if pi == pattern.len() {
+                end = i;
+                break;
+            }
+        }
+    }
+    if pi < pattern.len() {
+        return None;
+    }
+
+    // Backward pass: the latest start for that end, giving the shortest window
+    let mut pi = pattern.len();
+    let mut start = end;
+    for i in (0..=end).rev() {
+        if lower[i] == pattern[pi - 1] {
+            pi -= 1;
+            if pi == 0 {
+                start = i;
+                break;
+            }
+        }
+    }
+
+    // Score the window
+    let mut score = 0;
+    let mut pi = 0;
+    let mut in_gap = false;
+    let mut consecutive = 0;
+    for (i, &c) in lower.iter().enumerate().take(end + 1).skip(start) {
+        if pi < pattern.len() && c == pattern[pi] {
+            let mut bonus = bonus_at(&text, i);
+            if consecutive > 0 {
+                bonus = bonus.max(BONUS_CONSECUTIVE);
+            }
+            if pi == 0 {
+                bonus *= BONUS_FIRST_CHAR_MULTIPLIER;
+            }
+            score += SCORE_MATCH + bonus;
+            consecutive += 1;
+            in_gap = false;
+            pi += 1;
+        } else {
+            score += if in_gap {
+                SCORE_GAP_EXTENSION
+            } else {
+                SCORE_GAP_START
+            };
+            consecutive = 0;
+            in_gap = true;
+        }
+    }
+
+    // Reference: the pattern found verbatim at the start of the text
+    let n = pattern.len() as i64;
+    let max = n * SCORE_MATCH
+        + BONUS_BOUNDARY * BONUS_FIRST_CHAR_MULTIPLIER
+        + (n - 1) * BONUS_CONSECUTIVE;
+    Some((score as f64 / max as f64).clamp(0.01, 1.0))
+}
+
+fn bonus_at(text: &[char], i: usize) -> i64 {
+    let Some(prev) = i.checked_sub(1).map(|p| text[p]) else {
+        return BONUS_BOUNDARY;
+    };
+    let c = text[i];
+
+    if !prev.is_alphanumeric() && c.is_alphanumeric() {
+        BONUS_BOUNDARY
+    } else if (prev.is_lowercase() && c.is_uppercase()) || (!prev.is_numeric() && c.is_numeric()) {
+        BONUS_CAMEL_CASE
+    } else {
+        0
+    }
+}
+
+/// Edit distance between `a` and `b`, counting a swap of adjacent
+/// characters as one edit, or `None` if it exceeds `max`.
+pub fn edit_distance(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        let mut row_min = curr[0];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+            if i > 0 && j > 0 && *ca == b[j - 1] && a[i - 1] == *cb {
+                curr[j + 1] = curr[j + 1].min(before[j - 1] + 1);
+            }
+            row_min = row_min.min(curr[j + 1]);
+        }
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut before, &mut prev);
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    Some(prev[b.len()]).filter(|&d| d <= max)
+}
+
+/// Typos tolerated in a word of `len` characters.
+fn max_typos(len: usize) -> usize {
+    match len {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    }
+}
+
+/// Run a fuzzy search. `filters` are SQL conditions on `i`, with their
+/// values already in `params`.
+pub(crate) fn search(
+    conn: &Connection,
+    query: &SearchQuery,
+    filters: &[String],
+    params: Params,
+) -> Result<Vec<Item>> {
+    // Quotes around phrases, left by the operator parser, don't matter
+    // when matching loosely
+    let text = query.text.replace('"', "");
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let wanted = query.limit as usize + query.offset as usize;
+    let cap = wanted.saturating_mul(5).max(100);
+    let mut scores: HashMap<i64, f64> = HashMap::new();
+
+    // Substring
+    if text.chars().count() >= 3 {
+        let mut params = params.clone();
+        let phrase = params.bind(format!("\"{}\"", text.replace('"', "\"\"")));
+        let sql = format!(
+            "SELECT i.id FROM items i
+             INNER JOIN items_trigram t ON t.rowid = i.id
+             WHERE items_trigram MATCH {} AND {}
+             LIMIT {}",
+            phrase,
+            filters.join(" AND "),
+            cap
+        );
+        for id in query_ids(conn, &sql, params)? {
+            *scores.entry(id).or_default() += SUBSTRING_SCORE;
+        }
+    }
+
+    // Typo
+    if let Some(fts_query) = corrected_query(conn, text)? {
+        let mut params = params.clone();
+        let fts_query = params.bind(fts_query);
+        let sql = format!(
+            "SELECT i.id FROM items i
+             INNER JOIN items_fts fts ON fts.item_id = i.id
+             WHERE items_fts MATCH {} AND {}
+             LIMIT {}",
+            fts_query,
+            filters.join(" AND "),
+            cap
+        );
+        for id in query_ids(conn, &sql, params)? {
+            *scores.entry(id).or_default() += TYPO_SCORE;
+        }
+    }
+
+    // Subsequence
+    {
+        let mut params = params.clone();
+        let pattern = params.bind(text.to_string());
+        let score = format!(
+            "MAX(fuzzy_score({0}, i.content_ref), fuzzy_score({0}, i.title), fuzzy_score({0}, i.note))",
+            pattern
+        );
+        let sql = format!(
+            "SELECT i.id, {0} AS score FROM items i
+             WHERE score > 0 AND {1}
+             ORDER BY score DESC
+             LIMIT {2}",
+            score,
+            filters.join(" AND "),
+            cap
+        );
+        let mut stmt = conn.prepare_cached(&sql)?;
+        let rows = stmt
+            .query_map(params_from_iter(params.0), |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, f64>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to run subsequence search")?;
+        for (id, score) in rows {
+            *scores.entry(id).or_default() += score;
+        }
+    }
+
+    if scores.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let ids: Vec<i64> = scores.keys().copied().collect();
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM items i WHERE i.id IN (SELECT value FROM json_each(?1))",
+        ITEM_COLUMNS
+    ))?;
+    let mut items = stmt
+        .query_map([serde_json::to_string(&ids)?], Database::row_to_item)?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to fetch fuzzy search results")?;
+
+    items.sort_by(|a, b| {
+        scores[&b.id]
+            .total_cmp(&scores[&a.id])
+            .then(b.created_at.cmp(&a.created_at))
+    });
+    items.drain(..items.len().min(query.offset as usize));
+    items.truncate(query.limit as usize);
+
+    Ok(items)
+}
+
+/// Build an FTS5 query where each word of `text` matches itself or any
+/// vocabulary term within its typo allowance. Returns `None` if some word
+/// has no candidates or no word is long enough to correct.
+fn corrected_query(conn: &Connection, text: &str) -> Result<Option<String>> {
+    let words: Vec<Vec<char>> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.chars().flat_map(char::to_lowercase).collect())
+        .collect();
+
+    if !words.iter().any(|w| max_typos(w.len()) > 0) {
+        return Ok(None);
+    }
+
+    let mut stmt = conn.prepare_cached(
+        "SELECT term FROM items_fts_vocab WHERE length(term) BETWEEN ?1 AND ?2",
+    )?;
+
+    let mut groups = Vec::with_capacity(words.len());
+    for word in &words {
+        let max = max_typos(word.len());
+        let terms: Vec<String> = stmt
+            .query_map(
+                [word.len().saturating_sub(max) as i64, (word.len() + max) as i64],
+                |row| row.get::<_, String>(0),
+            )?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to read search vocabulary")?;
+
+        let matches: Vec<String> = terms
+            .into_iter()
+            .filter(|term| {
+                let term: Vec<char> = term.chars().collect();
+                edit_distance(word, &term, max).is_some()
+            })
+            .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+            .collect();
+
+        if matches.is_empty() {
+            return Ok(None);
+        }
+        groups.push(format!("({})", matches.join(" OR ")));
+    }
+
+    Ok(Some(groups.join(" AND ")))
+}
+
+fn query_ids(conn: &Connection, sql: &str, params: Params) -> Result<Vec<i64>> {
+    let mut stmt = conn.prepare_cached(sql)?;
+    let ids = stmt
+        .query_map(params_from_iter(params.0), |row| row.get(0))?
+        .collect::<Result<Vec<i64>, _>>()
+        .context("Failed to run fuzzy search")?;
+
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(pattern: &str, text: &str) -> Option<f64> {
+        let pattern: Vec<char> = pattern.chars().collect();
+        subsequence_score(&pattern, text)
+    }
+
+    #[test]
+    fn test_subsequence_score() {
+        assert!(score("hlo", "hello").is_some());
+        assert!(score("abc", "alphabet soup").is_none());
+        assert!(score("xyz", "hello").is_none());
+
+        // Contiguous and boundary matches score higher
+        assert!(score("git", "git push") > score("git", "go install tool"));
+        assert!(score("fb", "FooBar") > score("fb", "fooxbar"));
+        assert!(score("ssh", "ssh-keygen") > score("ssh", "session hash"));
+        assert_eq!(score("exact", "exact"), Some(1.0));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(edit_distance(&chars("kubctl"), &chars("kubectl"), 2), Some(1));
+        assert_eq!(edit_distance(&chars("dokcer"), &chars("docker"), 2), Some(1));
+        assert_eq!(edit_distance(&chars("pdos"), &chars("pods"), 1), Some(1));
+        assert_eq!(edit_distance(&chars("same"), &chars("same"), 0), Some(0));
+        assert_eq!(edit_distance(&chars("short"), &chars("much longer"), 2), None);
+        assert_eq!(edit_distance(&chars("abcd"), &chars("wxyz"), 2), None);
+    }
+}
@@ -0,0 +1,278 @@
+//! Import of history from other clipboard managers.
+//!
+//! Each importer reads a local file into [`NewItem`]s, keeping the original
+//! timestamps and source apps where the format records them. Sources that
+//! don't record times are taken to list items newest first, as clipboard
+//! managers show them, and get consecutive timestamps just before the
+//! import. Everything then goes through deduplication in a single batch,
+//! so importing the same history twice adds nothing.
+//!
+//! Images are copied into `imported/` in the blobs directory, named after
+//! a hash of their contents.
+
+mod copyq;
+mod gpaste;
+mod lists;
+mod maccy;
+
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::Instant;
+
+use crate::db::Database;
+use crate::models::NewItem;
+
+/// Directory of the blobs directory that imported images are copied to.
+pub const BLOBS_SUBDIR: &str = "imported";
+
+/// Format of the file to import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    /// Maccy's SQLite store, `Storage.sqlite`.
+    Maccy,
+    /// A CopyQ tab file (`copyq_tab_*.dat`) or a file exported from CopyQ
+    /// (`.cpq`).
+    CopyQ,
+    /// GPaste's history, `history.xml`.
+    GPaste,
+    /// A JSON array or JSON Lines of strings or objects, including exports
+    /// of this app.
+    Json,
+    /// Plain text with one item per line, or per `separator`.
+    #[serde(rename = "plain_text")]
+    PlainText,
+}
+
+/// How to import a file.
+///
+/// Deserializable from JSON for FFI. Fields other than `format` are
+/// optional.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportOptions {
+    pub format: ImportFormat,
+    /// Source app of the items whose origin isn't recorded.
+    #[serde(default)]
+    pub source_app: Option<String>,
+    /// Tags added to every imported item.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Separator between the items of a plain-text file, a newline by
+    /// default.
+    #[serde(default)]
+    pub separator: Option<String>,
+}
+
+impl ImportOptions {
+    pub fn new(format: ImportFormat) -> Self {
+        Self {
+            format,
+            source_app: None,
+            tags: Vec::new(),
+            separator: None,
+        }
+    }
+}
+
+/// What an import did.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub format: ImportFormat,
+    /// Items added to the history.
+    pub imported: usize,
+    /// Items already in the history, or earlier in the same file.
+    pub duplicates: usize,
+    /// Entries with nothing to import, such as GPaste passwords or
+    /// clipboard formats this app doesn't store.
+    pub skipped: usize,
+    /// Entries that could not be read or added.
+    pub errors: Vec<String>,
+    pub duration_ms: i64,
+}
+
+/// What an importer read from a file.
+#[derive(Debug, Default)]
+struct Entries {
+    items: Vec<NewItem>,
+    /// One per item.
+    extras: Vec<Extras>,
+    skipped: usize,
+    errors: Vec<String>,
+}
+
+/// What an entry holds beyond its [`NewItem`], applied once it is added.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Extras {
+    title: Option<String>,
+    note: Option<String>,
+    pinned: bool,
+}
+
+impl Entries {
+    /// Add an item, with the options' tags and fallback source app.
+    fn push(&mut self, item: NewItem, options: &ImportOptions) {
+        self.push_with(item, Extras::default(), options);
+    }
+
+    /// [`Entries::push`] for an item with a title, note or pin.
+    fn push_with(&mut self, mut item: NewItem, extras: Extras, options: &ImportOptions) {
+        if item.source_app.is_none() {
+            item.source_app = options.source_app.clone();
+        }
+        for tag in &options.tags {
+            if !item.tags.contains(tag) {
+                item.tags.push(tag.clone());
+            }
+        }
+        self.items.push(item);
+        self.extras.push(extras);
+    }
+}
+
+/// Import the file at `path` into `db`, with `now` (ms since epoch) as the
+/// time of the import.
+pub fn run(
+    db: &Database,
+    blobs_dir: &Path,
+    path: &Path,
+    options: &ImportOptions,
+    now: i64,
+) -> Result<ImportReport> {
+    let started = Instant::now();
+
+    let entries = match options.format {
+        ImportFormat::Maccy => maccy::read(path, blobs_dir, options)?,
+        ImportFormat::CopyQ => copyq::read(path, blobs_dir, options, now)?,
+        ImportFormat::GPaste => gpaste::read(path, blobs_dir, options, now)?,
+        ImportFormat::Json => lists::read_json(path, options, now)?,
+        ImportFormat::PlainText => lists::read_text(path, options, now)?,
+    };
+
+    let last_id: i64 = db
+        .reader()
+        .query_row("SELECT COALESCE(MAX(id), 0) FROM items", [], |row| row.get(0))
+        .context("Failed to read item IDs")?;
+
+    // Duplicates return the ID of the item they duplicate, which is older
+    // than the import unless it came earlier in the same batch
+    let mut report = ImportReport {
+        format: options.format,
+        imported: 0,
+        duplicates: 0,
+        skipped: entries.skipped,
+        errors: entries.errors,
+        duration_ms: 0,
+    };
+    // Titles, notes and pins only go to new items, so re-importing doesn't
+    // overwrite what was changed since
+    db.write_tx(|conn| {
+        let mut added = HashSet::new();
+        let results = Database::insert_batch_with(conn, &entries.items, true)?;
+        for (result, extras) in results.into_iter().zip(&entries.extras) {
+            match result {
+                Ok(id) if id > last_id && added.insert(id) => {
+                    report.imported += 1;
+                    if let Err(e) = add_extras(conn, id, extras) {
+                        report.errors.push(format!("{:#}", e));
+                    }
+                }
+                Ok(_) => report.duplicates += 1,
+                Err(e) => report.errors.push(format!("{:#}", e)),
+            }
+        }
+        Ok(())
+    })?;
+
+    report.duration_ms = started.elapsed().as_millis() as i64;
+    Ok(report)
+}
+
+fn add_extras(conn: &Connection, id: i64, extras: &Extras) -> Result<()> {
+    if extras.title.is_some() || extras.note.is_some() {
+        Database::annotate_with(conn, id, extras.title.as_deref(), extras.note.as_deref())?;
+    }
+    if extras.pinned {
+        Database::set_pinned_with(conn, id, true)?;
+    }
+    Ok(())
+}
+
+/// Timestamp for the item at `position` of a list without times, newest
+/// first, imported at `now`.
+fn listed_at(now: i64, position: usize) -> i64 {
+    now - position as i64 - 1
+}
+
+/// Copy image data into the blobs directory, returning its `content_ref`.
+fn store_blob(blobs_dir: &Path, data: &[u8], extension: &str) -> Result<String> {
+    let dir = blobs_dir.join(BLOBS_SUBDIR);
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {:?}", dir))?;
+
+    let name = format!("{:016x}.{}", fnv1a(data), extension);
+    let path = dir.join(&name);
+    if !path.exists() {
+        std::fs::write(&path, data).with_context(|| format!("Failed to write {:?}", path))?;
+    }
+    Ok(format!("{}/{}", BLOBS_SUBDIR, name))
+}
+
+/// 64-bit FNV-1a, stable across builds unlike `DefaultHasher`.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// The local path of a `file://` URI, or `None` for other URIs.
+fn file_path(uri: &str) -> Option<String> {
+    let rest = uri.trim().strip_prefix("file://")?;
+    let path = rest.strip_prefix("localhost").unwrap_or(rest);
+    if !path.starts_with('/') {
+        return None;
+    }
+    percent_decode(path)
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_path() {
+        assert_eq!(file_path("file:///tmp/a.txt").as_deref(), Some("/tmp/a.txt"));
+        assert_eq!(
+            file_path("file://localhost/Users/me/My%20File.pdf\r\n").as_deref(),
+            Some("/Users/me/My File.pdf")
+        );
+        assert_eq!(file_path("file:///caf%C3%A9").as_deref(), Some("/café"));
+        assert_eq!(file_path("https://example.com/a"), None);
+        assert_eq!(file_path("file://server/share"), None);
+        assert_eq!(file_path("file:///bad%zz"), None);
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
@@ -0,0 +1,409 @@
+//! CopyQ's tabs, in Qt's `QDataStream` format.
+//!
+//! A tab file (`copyq_tab_*.dat`) holds the item count, then per item its
+//! data by MIME type. Files exported from CopyQ (`.cpq`) start with a
+//! `CopyQ v…` header and hold a map whose `tabs` list has the `name` and
+//! serialized `data` of each exported tab. Items are listed newest first
+//! and have no timestamps.
+
+use anyhow::{Context, Result};
+use flate2::read::ZlibDecoder;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+use super::{file_path, listed_at, store_blob, Entries, ImportOptions};
+use crate::models::{ItemKind, NewItem};
+
+/// Item data by MIME type.
+type ItemData = HashMap<String, Vec<u8>>;
+
+/// The most an item's data may uncompress to.
+const MAX_ITEM_BYTES: usize = 256 * 1024 * 1024;
+
+/// How deeply values in an export may nest.
+const MAX_DEPTH: usize = 32;
+
+/// Data length marking the second item format, with shortened MIME types
+/// and optional compression.
+const ITEM_FORMAT_V2: i32 = -2;
+
+const MIME_PREFIX: &str = "application/x-copyq-";
+const MIME_TAGS: &str = "application/x-copyq-tags";
+const MIME_TEXT: &str = "text/plain";
+const MIME_URI_LIST: &str = "text/uri-list";
+const IMAGE_TYPES: [(&str, &str); 4] = [
+    ("image/png", "png"),
+    ("image/jpeg", "jpg"),
+    ("image/gif", "gif"),
+    ("image/bmp", "bmp"),
+];
+
+pub(super) fn read(
+    path: &Path,
+    blobs_dir: &Path,
+    options: &ImportOptions,
+    now: i64,
+) -> Result<Entries> {
+    let data = std::fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+
+    let mut entries = Entries::default();
+    let mut position = 0;
+    for tab in tabs(&data).context("Not a CopyQ tab or export")? {
+        for item in tab {
+            match item {
+                Ok(item) => {
+                    add_item(&mut entries, &item, blobs_dir, options, listed_at(now, position))
+                }
+                Err(e) => entries.errors.push(format!("{:#}", e)),
+            }
+            position += 1;
+        }
+    }
+
+    Ok(entries)
+}
+
+/// The items of every tab in `data`, a tab file or an export.
+fn tabs(data: &[u8]) -> Result<Vec<Vec<Result<ItemData>>>> {
+    let mut stream = Stream::new(data);
+
+    // An export starts with its header, as a byte array or a string; a tab
+    // file with the number of items
+    let is_export = Stream::new(data)
+        .bytes()
+        .is_ok_and(|bytes| bytes.is_some_and(|bytes| bytes.starts_with(b"CopyQ v")))
+        || Stream::new(data)
+            .string()
+            .is_ok_and(|header| header.starts_with("CopyQ v"));
+    if !is_export {
+        return Ok(vec![items(&mut stream)?]);
+    }
+
+    stream.bytes()?;
+    let Variant::Map(export) = stream.variant(0)? else {
+        anyhow::bail!("Expected a map after the header");
+    };
+    let Some(Variant::List(tabs)) = export.get("tabs") else {
+        anyhow::bail!("Export has no tabs");
+    };
+
+    let mut all = Vec::new();
+    for tab in tabs {
+        let Variant::Map(tab) = tab else {
+            anyhow::bail!("Expected a map for each tab");
+        };
+        match tab.get("data") {
+            Some(Variant::Bytes(data)) => all.push(items(&mut Stream::new(data))?),
+            _ => anyhow::bail!("Tab has no data"),
+        }
+    }
+    Ok(all)
+}
+
+fn items(stream: &mut Stream) -> Result<Vec<Result<ItemData>>> {
+    let count = stream.i32()?;
+    if count < 0 {
+        anyhow::bail!("Invalid item count {}", count);
+    }
+
+    (0..count).map(|_| item(stream)).collect()
+}
+
+/// The next item in `stream`. The outer error is a damaged stream, the
+/// inner one an item whose data could not be uncompressed.
+fn item(stream: &mut Stream) -> Result<Result<ItemData>> {
+    let mut data = Vec::new();
+    let length = stream.i32()?;
+
+    if length == ITEM_FORMAT_V2 {
+        let size = stream.i32()?;
+        for _ in 0..size {
+            let mime = expand_mime(&stream.string()?);
+            let compressed = stream.bool()?;
+            data.push((mime, compressed, stream.bytes()?.unwrap_or_default()));
+        }
+    } else if length >= 0 {
+        // The first format compressed everything
+        for _ in 0..length {
+            let mime = stream.string()?;
+            let bytes = stream.bytes()?.unwrap_or_default();
+            data.push((mime, !bytes.is_empty(), bytes));
+        }
+    } else {
+        anyhow::bail!("Invalid item length {}", length);
+    }
+
+    Ok(data
+        .into_iter()
+        .map(|(mime, compressed, bytes)| {
+            let bytes = if compressed { uncompress(&bytes)? } else { bytes };
+            Ok((mime, bytes))
+        })
+        .collect())
+}
+
+/// MIME types are stored with their common prefixes shortened to a digit.
+fn expand_mime(mime: &str) -> String {
+    let mut chars = mime.chars();
+    match chars.next() {
+        Some('0') => chars.as_str().to_string(),
+        Some('1') => format!("{}{}", MIME_PREFIX, chars.as_str()),
+        Some('2') => format!("text/{}", chars.as_str()),
+        _ => mime.to_string(),
+    }
+}
+
+/// Undo Qt's `qCompress`: the uncompressed size, big-endian, then zlib.
+///
+/// The size comes from the file, so it only sizes the buffer up to what
+/// zlib can plausibly expand the data to, and no more than the size, up to
+/// `MAX_ITEM_BYTES`, is uncompressed.
+fn uncompress(bytes: &[u8]) -> Result<Vec<u8>> {
+    let (size, zlib) = bytes
+        .split_first_chunk::<4>()
+        .context("Compressed data is too short")?;
+    let limit = (u32::from_be_bytes(*size) as usize).min(MAX_ITEM_BYTES);
+    let mut data = Vec::with_capacity(limit.min(zlib.len().saturating_mul(4)));
+    ZlibDecoder::new(zlib)
+        .take(limit as u64 + 1)
+        .read_to_end(&mut data)
+        .context("Failed to uncompress item data")?;
+    if data.len() > limit {
+        anyhow::bail!("Item data uncompresses to more than {} bytes", limit);
+    }
+    Ok(data)
+}
+
+fn add_item(
+    entries: &mut Entries,
+    data: &ItemData,
+    blobs_dir: &Path,
+    options: &ImportOptions,
+    created_at: i64,
+) {
+    let tags: Vec<String> = data
+        .get(MIME_TAGS)
+        .map(|tags| {
+            String::from_utf8_lossy(tags)
+                .split([',', '\n'])
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    let new_item = |kind, content_ref| NewItem {
+        kind,
+        content_ref,
+        source_app: None,
+        created_at,
+        tags: tags.clone(),
+    };
+
+    let files: Vec<String> = data
+        .get(MIME_URI_LIST)
+        .map(|uris| {
+            String::from_utf8_lossy(uris)
+                .lines()
+                .filter_map(file_path)
+                .collect()
+        })
+        .unwrap_or_default();
+    let text = data
+        .get(MIME_TEXT)
+        .and_then(|text| String::from_utf8(text.clone()).ok())
+        .filter(|text| !text.is_empty());
+    let image = IMAGE_TYPES
+        .iter()
+        .find_map(|(mime, extension)| Some((data.get(*mime)?, *extension)));
+
+    if !files.is_empty() {
+        for file in files {
+            entries.push(new_item(ItemKind::File, file), options);
+        }
+    } else if let Some(text) = text {
+        entries.push(new_item(ItemKind::Text, text), options);
+    } else if let Some((bytes, extension)) = image {
+        match store_blob(blobs_dir, bytes, extension) {
+            Ok(content_ref) => entries.push(new_item(ItemKind::Image, content_ref), options),
+            Err(e) => entries.errors.push(format!("{:#}", e)),
+        }
+    } else {
+        entries.skipped += 1;
+    }
+}
+
+/// The `QVariant` types found in exports.
+#[derive(Debug, Clone, PartialEq)]
+enum Variant {
+    Bool(bool),
+    Int(i64),
+    Double(f64),
+    Map(HashMap<String, Variant>),
+    List(Vec<Variant>),
+    String(String),
+    StringList(Vec<String>),
+    Bytes(Vec<u8>),
+}
+
+/// A big-endian `QDataStream` reader.
+struct Stream<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Stream<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if n > self.data.len() {
+            anyhow::bail!("Unexpected end of data");
+        }
+        let (taken, rest) = self.data.split_at(n);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(self.u32()? as i32)
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+
+    /// A byte array, `None` if null.
+    fn bytes(&mut self) -> Result<Option<Vec<u8>>> {
+        match self.u32()? {
+            u32::MAX => Ok(None),
+            len => Ok(Some(self.take(len as usize)?.to_vec())),
+        }
+    }
+
+    /// A UTF-16 string, empty if null.
+    fn string(&mut self) -> Result<String> {
+        let len = match self.u32()? {
+            u32::MAX => return Ok(String::new()),
+            len if len % 2 != 0 => anyhow::bail!("Invalid string length {}", len),
+            len => len as usize,
+        };
+        let units: Vec<u16> = self
+            .take(len)?
+            .chunks_exact(2)
+            .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+            .collect();
+        String::from_utf16(&units).context("Invalid string")
+    }
+
+    /// A value, nested `depth` maps or lists deep.
+    fn variant(&mut self, depth: usize) -> Result<Variant> {
+        if depth > MAX_DEPTH {
+            anyhow::bail!("Values in export are nested too deeply");
+        }
+
+        let kind = self.u32()?;
+        let _is_null = self.bool()?;
+
+        Ok(match kind {
+            1 => Variant::Bool(self.bool()?),
+            2 => Variant::Int(self.i32()? as i64),
+            3 => Variant::Int(self.u32()? as i64),
+            4 | 5 => Variant::Int(self.u64()? as i64),
+            6 => Variant::Double(f64::from_bits(self.u64()?)),
+            8 => {
+                let len = self.u32()?;
+                let mut map = HashMap::new();
+                for _ in 0..len {
+                    let key = self.string()?;
+                    map.insert(key, self.variant(depth + 1)?);
+                }
+                Variant::Map(map)
+            }
+            9 => Variant::List(
+                (0..self.u32()?)
+                    .map(|_| self.variant(depth + 1))
+                    .collect::<Result<_>>()?,
+            ),
+            10 => Variant::String(self.string()?),
+            11 => Variant::StringList((0..self.u32()?).map(|_| self.string()).collect::<Result<_>>()?),
+            12 => Variant::Bytes(self.bytes()?.unwrap_or_default()),
+            _ => anyhow::bail!("Unsupported value of type {} in export", kind),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_mime() {
+        assert_eq!(expand_mime("2plain"), "text/plain");
+        assert_eq!(expand_mime("1tags"), "application/x-copyq-tags");
+        assert_eq!(expand_mime("0image/png"), "image/png");
+        assert_eq!(expand_mime("image/png"), "image/png");
+    }
+
+    #[test]
+    fn test_uncompress_ignores_bogus_size() {
+        use flate2::write::ZlibEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"hello hello hello").unwrap();
+        let zlib = encoder.finish().unwrap();
+
+        // Claims 4 GiB
+        let mut bytes = vec![0xff, 0xff, 0xff, 0xff];
+        bytes.extend(&zlib);
+        assert_eq!(uncompress(&bytes).unwrap(), b"hello hello hello");
+
+        // Claims less than it holds
+        bytes[..4].copy_from_slice(&5u32.to_be_bytes());
+        assert!(uncompress(&bytes).is_err());
+
+        bytes.truncate(bytes.len() - 4);
+        assert!(uncompress(&bytes).is_err());
+        assert!(uncompress(&[0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_variant_depth() {
+        // Lists of one list, around a bool
+        let nested = |depth| {
+            let mut data = [0, 0, 0, 9, 0, 0, 0, 0, 1].repeat(depth);
+            data.extend([0, 0, 0, 1, 0, 1]);
+            data
+        };
+        assert!(Stream::new(&nested(MAX_DEPTH)).variant(0).is_ok());
+        let err = Stream::new(&nested(MAX_DEPTH + 1)).variant(0).unwrap_err();
+        assert!(err.to_string().contains("nested too deeply"));
+    }
+
+    #[test]
+    fn test_stream() {
+        let mut data = vec![0, 0, 0, 4, 0, b'h', 0, b'i', 0xff, 0xff, 0xff, 0xff, 1];
+        data.extend([0, 0, 0, 10, 0, 0, 0, 0, 4, 0, b'o', 0, b'k']);
+        let mut stream = Stream::new(&data);
+        assert_eq!(stream.string().unwrap(), "hi");
+        assert_eq!(stream.bytes().unwrap(), None);
+        assert!(stream.bool().unwrap());
+        assert_eq!(stream.variant(0).unwrap(), Variant::String("ok".to_string()));
+        assert!(stream.u8().is_err());
+    }
+}
@@ -0,0 +1,102 @@
+//! GPaste's history: `history.xml`, newest first, with one `<item>` per
+//! entry and its contents in a `<value>` child.
+//!
+//! ```xml
+//! <history version="2.0">
+//!   <item kind="Text" uuid="..."><value><![CDATA[hello]]></value></item>
+//!   <item kind="Uris" uuid="..."><value><![CDATA[/home/me/a.txt]]></value></item>
+//!   <item kind="Image" uuid="..." date="1700000000"><value><![CDATA[/home/me/.local/share/gpaste/images/x.png]]></value></item>
+//! </history>
+//! ```
+//!
+//! Only images record when they were copied. Passwords are skipped.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use super::{file_path, listed_at, store_blob, Entries, ImportOptions};
+use crate::models::{ItemKind, NewItem};
+
+pub(super) fn read(
+    path: &Path,
+    blobs_dir: &Path,
+    options: &ImportOptions,
+    now: i64,
+) -> Result<Entries> {
+    let xml = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read GPaste history at {:?}", path))?;
+    let doc = roxmltree::Document::parse(&xml).context("Failed to parse GPaste history")?;
+    if !doc.root_element().has_tag_name("history") {
+        anyhow::bail!("Not a GPaste history");
+    }
+
+    let mut entries = Entries::default();
+    let items = doc
+        .root_element()
+        .children()
+        .filter(|node| node.has_tag_name("item"));
+    for (position, item) in items.enumerate() {
+        let value: String = item
+            .children()
+            .find(|node| node.has_tag_name("value"))
+            .map(|value| {
+                value
+                    .descendants()
+                    .filter(|node| node.is_text())
+                    .filter_map(|node| node.text())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let created_at = match item.attribute("date").and_then(|date| date.parse::<i64>().ok()) {
+            Some(seconds) => match seconds.checked_mul(1000) {
+                Some(ms) => ms,
+                None => {
+                    entries
+                        .errors
+                        .push(format!("Item {}: date {} is out of range", position + 1, seconds));
+                    continue;
+                }
+            },
+            None => listed_at(now, position),
+        };
+
+        let new_item = |kind, content_ref| NewItem {
+            kind,
+            content_ref,
+            source_app: None,
+            created_at,
+            tags: vec![],
+        };
+
+        match item.attribute("kind") {
+            Some("Text") if !value.is_empty() => {
+                entries.push(new_item(ItemKind::Text, value), options)
+            }
+            Some("Uris") => {
+                for line in value.lines().filter(|line| !line.trim().is_empty()) {
+                    let path = file_path(line).unwrap_or_else(|| line.trim().to_string());
+                    entries.push(new_item(ItemKind::File, path), options);
+                }
+            }
+            Some("Image") => {
+                let image = Path::new(value.trim());
+                let extension = image
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    .unwrap_or("png");
+                let stored = std::fs::read(image)
+                    .with_context(|| format!("Failed to read image {:?}", image))
+                    .and_then(|data| store_blob(blobs_dir, &data, extension));
+                match stored {
+                    Ok(content_ref) => {
+                        entries.push(new_item(ItemKind::Image, content_ref), options)
+                    }
+                    Err(e) => entries.errors.push(format!("{:#}", e)),
+                }
+            }
+            _ => entries.skipped += 1,
+        }
+    }
+
+    Ok(entries)
+}
@@ -0,0 +1,231 @@
+//! Generic lists: JSON and plain text.
+//!
+//! JSON is an array, or JSON Lines, of entries. An entry is a string, or an
+//! object with the text in `content_ref`, `content` or `text`, and
+//! optionally `kind`, `source_app` (or `app`), `created_at` (ms or seconds
+//! since epoch, or RFC 3339), `tags`, `title`, `note` and `pinned`. Exports
+//! of this app in JSON Lines are read back this way.
+
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use std::path::Path;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use super::{listed_at, Entries, Extras, ImportOptions};
+use crate::models::{ItemKind, NewItem};
+
+/// Smaller timestamps are taken to be in seconds: as milliseconds they
+/// would be before 1973.
+const SECONDS_BELOW: i64 = 100_000_000_000;
+
+pub(super) fn read_json(path: &Path, options: &ImportOptions, now: i64) -> Result<Entries> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {:?}", path))?;
+
+    let values = match serde_json::from_str::<Value>(&json) {
+        Ok(Value::Array(values)) => values.into_iter().map(Ok).collect(),
+        Ok(value @ Value::Object(_)) => vec![Ok(value)],
+        Ok(_) => anyhow::bail!("Expected a JSON array or JSON Lines"),
+        // Not a single document, so JSON Lines
+        Err(_) => json
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line).map_err(|e| format!("Line {}: {}", i + 1, e))
+            })
+            .collect::<Vec<Result<Value, String>>>(),
+    };
+
+    let mut entries = Entries::default();
+    for (position, value) in values.into_iter().enumerate() {
+        let item = match value {
+            Ok(Value::String(text)) if text.is_empty() => {
+                entries.skipped += 1;
+                continue;
+            }
+            Ok(Value::String(text)) => Ok(Some((
+                NewItem {
+                    kind: ItemKind::Text,
+                    content_ref: text,
+                    source_app: None,
+                    created_at: listed_at(now, position),
+                    tags: vec![],
+                },
+                Extras::default(),
+            ))),
+            Ok(Value::Object(object)) => from_object(&object, listed_at(now, position)),
+            Ok(_) => Err("Expected a string or an object".to_string()),
+            Err(e) => Err(e),
+        };
+
+        match item {
+            Ok(Some((item, extras))) => entries.push_with(item, extras, options),
+            Ok(None) => entries.skipped += 1,
+            Err(e) => entries.errors.push(format!("Entry {}: {}", position + 1, e)),
+        }
+    }
+
+    Ok(entries)
+}
+
+/// The item described by a JSON object, or `None` if it has no content.
+fn from_object(
+    object: &Map<String, Value>,
+    default_time: i64,
+) -> Result<Option<(NewItem, Extras)>, String> {
+    let content = ["content_ref", "content", "text"]
+        .iter()
+        .find_map(|key| object.get(*key)?.as_str());
+    let content = match content {
+        Some(content) if !content.is_empty() => content.to_string(),
+        _ => return Ok(None),
+    };
+
+    let kind = match object.get("kind") {
+        None | Some(Value::Null) => ItemKind::Text,
+        Some(Value::String(kind)) => kind.parse()?,
+        Some(_) => return Err("Invalid item kind".to_string()),
+    };
+
+    let created_at = match object.get("created_at") {
+        None | Some(Value::Null) => default_time,
+        Some(Value::Number(n)) => match n.as_i64() {
+            Some(t) if t < SECONDS_BELOW => t
+                .checked_mul(1000)
+                .ok_or_else(|| format!("created_at {} is out of range", t))?,
+            Some(t) => t,
+            None => return Err(format!("Invalid created_at: {}", n)),
+        },
+        Some(Value::String(s)) => {
+            let time = OffsetDateTime::parse(s, &Rfc3339)
+                .map_err(|e| format!("Invalid created_at {:?}: {}", s, e))?;
+            (time.unix_timestamp_nanos() / 1_000_000) as i64
+        }
+        Some(_) => return Err("Invalid created_at".to_string()),
+    };
+
+    let source_app = ["source_app", "app"]
+        .iter()
+        .find_map(|key| object.get(*key)?.as_str())
+        .map(str::to_string);
+
+    let tags = object
+        .get("tags")
+        .and_then(Value::as_array)
+        .map(|tags| {
+            tags.iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let text = |key| object.get(key).and_then(Value::as_str).map(str::to_string);
+    let extras = Extras {
+        title: text("title"),
+        note: text("note"),
+        pinned: object.get("pinned").and_then(Value::as_bool).unwrap_or(false),
+    };
+
+    Ok(Some((
+        NewItem {
+            kind,
+            content_ref: content,
+            source_app,
+            created_at,
+            tags,
+        },
+        extras,
+    )))
+}
+
+pub(super) fn read_text(path: &Path, options: &ImportOptions, now: i64) -> Result<Entries> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {:?}", path))?;
+    let separator = options.separator.as_deref().unwrap_or("\n");
+
+    let mut entries = Entries::default();
+    let parts = text
+        .split(separator)
+        .map(|part| part.strip_suffix('\r').unwrap_or(part))
+        .filter(|part| !part.trim().is_empty());
+    for (position, part) in parts.enumerate() {
+        let item = NewItem {
+            kind: ItemKind::Text,
+            content_ref: part.to_string(),
+            source_app: None,
+            created_at: listed_at(now, position),
+            tags: vec![],
+        };
+        entries.push(item, options);
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn object(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(object) => object,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_from_object_times() {
+        let (item, _) = from_object(&object(json!({"text": "a", "created_at": 1_700_000_000})), 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(item.created_at, 1_700_000_000_000);
+
+        let (item, _) = from_object(&object(json!({"text": "a", "created_at": 1_700_000_000_123i64})), 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(item.created_at, 1_700_000_000_123);
+
+        let (item, _) = from_object(
+            &object(json!({"text": "a", "created_at": "2023-11-14T22:13:20.5Z"})),
+            0,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(item.created_at, 1_700_000_000_500);
+
+        assert_eq!(from_object(&object(json!({"text": "a"})), 42).unwrap().unwrap().0.created_at, 42);
+        assert!(from_object(&object(json!({"text": "a", "created_at": "yesterday"})), 0).is_err());
+        assert!(from_object(&object(json!({"text": "a", "created_at": i64::MIN})), 0).is_err());
+    }
+
+    #[test]
+    fn test_from_object_fields() {
+        let (item, extras) = from_object(
+            &object(json!({"content": "/tmp/a", "kind": "file", "app": "Finder", "tags": ["x", 1]})),
+            0,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(item.kind, ItemKind::File);
+        assert_eq!(item.source_app.as_deref(), Some("Finder"));
+        assert_eq!(item.tags, ["x"]);
+        assert_eq!(extras, Extras::default());
+
+        let (_, extras) = from_object(
+            &object(json!({"text": "a", "title": "T", "note": null, "pinned": true})),
+            0,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(extras.title.as_deref(), Some("T"));
+        assert_eq!(extras.note, None);
+        assert!(extras.pinned);
+
+        assert!(from_object(&object(json!({"title": "no content"})), 0).unwrap().is_none());
+        assert!(from_object(&object(json!({"text": "a", "kind": "video"})), 0).is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//! Maccy's history: a Core Data SQLite store with one `ZHISTORYITEM` row
+//! per item and its pasteboard types in `ZHISTORYITEMCONTENT`.
+
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OpenFlags};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::{file_path, store_blob, Entries, ImportOptions};
+use crate::models::{ItemKind, NewItem};
+
+/// Seconds from the Unix epoch to 2001-01-01, Core Data's reference date.
+const REFERENCE_DATE: f64 = 978_307_200.0;
+
+const TEXT_TYPES: [&str; 2] = ["public.utf8-plain-text", "NSStringPboardType"];
+const FILE_URL_TYPE: &str = "public.file-url";
+const IMAGE_TYPES: [(&str, &str); 3] = [
+    ("public.png", "png"),
+    ("public.tiff", "tiff"),
+    ("public.jpeg", "jpg"),
+];
+
+pub(super) fn read(path: &Path, blobs_dir: &Path, options: &ImportOptions) -> Result<Entries> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Failed to open Maccy database at {:?}", path))?;
+
+    let rows = conn
+        .prepare(
+            "SELECT Z_PK, ZAPPLICATION, COALESCE(ZLASTCOPIEDAT, ZFIRSTCOPIEDAT)
+             FROM ZHISTORYITEM
+             ORDER BY 3 DESC",
+        )
+        .context("Not a Maccy database")?
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<f64>>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to read Maccy history")?;
+
+    let mut contents = conn
+        .prepare("SELECT ZTYPE, ZVALUE FROM ZHISTORYITEMCONTENT WHERE ZITEM = ?1")
+        .context("Not a Maccy database")?;
+
+    let mut entries = Entries::default();
+    for (pk, app, copied_at) in rows {
+        let Some(copied_at) = copied_at else {
+            entries.errors.push(format!("Item {} has no copy time", pk));
+            continue;
+        };
+        let created_at = ((copied_at + REFERENCE_DATE) * 1000.0).round() as i64;
+
+        // Several rows of the same type, e.g. one file URL per copied file
+        let mut types: HashMap<String, Vec<Vec<u8>>> = HashMap::new();
+        for row in contents.query_map([pk], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<Vec<u8>>>(1)?))
+        })? {
+            let (kind, value) = row.context("Failed to read Maccy item contents")?;
+            types.entry(kind).or_default().extend(value);
+        }
+
+        let new_item = |kind, content_ref| NewItem {
+            kind,
+            content_ref,
+            source_app: app.clone(),
+            created_at,
+            tags: vec![],
+        };
+
+        let files: Vec<String> = types
+            .get(FILE_URL_TYPE)
+            .into_iter()
+            .flatten()
+            .filter_map(|url| file_path(&String::from_utf8_lossy(url)))
+            .collect();
+        let text = TEXT_TYPES
+            .iter()
+            .filter_map(|t| types.get(*t)?.first())
+            .find_map(|value| String::from_utf8(value.clone()).ok())
+            .filter(|text| !text.is_empty());
+        let image = IMAGE_TYPES
+            .iter()
+            .find_map(|(t, extension)| Some((types.get(*t)?.first()?, *extension)));
+
+        if !files.is_empty() {
+            for file in files {
+                entries.push(new_item(ItemKind::File, file), options);
+            }
+        } else if let Some(text) = text {
+            entries.push(new_item(ItemKind::Text, text), options);
+        } else if let Some((data, extension)) = image {
+            match store_blob(blobs_dir, data, extension) {
+                Ok(content_ref) => entries.push(new_item(ItemKind::Image, content_ref), options),
+                Err(e) => entries.errors.push(format!("Item {}: {:#}", pk, e)),
+            }
+        } else {
+            entries.skipped += 1;
+        }
+    }
+
+    Ok(entries)
+}
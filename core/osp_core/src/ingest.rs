@@ -0,0 +1,185 @@
+//! Background ingestion queue with write batching.
+//!
+//! Items handed to [`IngestWorker::enqueue`] are collected on a background
+//! thread and written with deduplication in a single transaction, either
+//! when the batch is full or when the flush interval elapses.
+
+use anyhow::{anyhow, Result};
+use parking_lot::Mutex;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+use crate::config::IngestConfig;
+use crate::db::Database;
+use crate::models::NewItem;
+use crate::paste_queue;
+use crate::trash_cutoff;
+
+enum Message {
+    Item(NewItem),
+    Flush(Sender<()>),
+}
+
+/// Handle to the background ingestion thread.
+///
+/// Dropping the worker writes any pending items and joins the thread.
+pub struct IngestWorker {
+    sender: Mutex<Option<Sender<Message>>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+impl IngestWorker {
+    /// Start the worker thread.
+    ///
+    /// Each batch also purges trash older than `trash_grace_period_ms`.
+    pub fn spawn(
+        db: Arc<Database>,
+        config: IngestConfig,
+        trash_grace_period_ms: i64,
+    ) -> Result<Self> {
+        let (sender, receiver) = mpsc::channel();
+        let last_error = Arc::new(Mutex::new(None));
+
+        let thread = {
+            let last_error = last_error.clone();
+            thread::Builder::new()
+                .name("osp-ingest".to_string())
+                .spawn(move || run(&db, &config, trash_grace_period_ms, receiver, &last_error))?
+        };
+
+        Ok(Self {
+            sender: Mutex::new(Some(sender)),
+            thread: Mutex::new(Some(thread)),
+            last_error,
+        })
+    }
+
+    /// Queue an item for writing. Returns immediately.
+    pub fn enqueue(&self, item: NewItem) -> Result<()> {
+        self.send(Message::Item(item))
+    }
+
+    /// Block until every item queued so far has been written.
+    ///
+    /// Returns the error of the most recent failed batch, if any.
+    pub fn flush(&self) -> Result<()> {
+        let (ack, done) = mpsc::channel();
+        self.send(Message::Flush(ack))?;
+        done.recv()
+            .map_err(|_| anyhow!("Ingestion worker stopped"))?;
+
+        match self.last_error.lock().take() {
+            Some(message) => Err(anyhow!("Failed to write batch: {}", message)),
+            None => Ok(()),
+        }
+    }
+
+    /// Write pending items and stop the worker thread.
+    ///
+    /// Safe to call more than once; later calls do nothing.
+    pub fn shutdown(&self) {
+        // Closing the channel makes the worker drain and exit
+        self.sender.lock().take();
+
+        if let Some(thread) = self.thread.lock().take() {
+            let _ = thread.join();
+        }
+    }
+
+    fn send(&self, message: Message) -> Result<()> {
+        self.sender
+            .lock()
+            .as_ref()
+            .ok_or_else(|| anyhow!("Ingestion worker stopped"))?
+            .send(message)
+            .map_err(|_| anyhow!("Ingestion worker stopped"))
+    }
+}
+
+impl Drop for IngestWorker {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Worker loop: collect items until the batch is full, the interval
+/// elapses, a flush is requested or the channel closes.
+fn run(
+    db: &Database,
+    config: &IngestConfig,
+    trash_grace_period_ms: i64,
+    receiver: mpsc::Receiver<Message>,
+    last_error: &Mutex<Option<String>>,
+) {
+    let max_batch = config.max_batch_items.max(1);
+    let mut pending: Vec<NewItem> = Vec::with_capacity(max_batch);
+    let mut deadline: Option<Instant> = None;
+
+    loop {
+        let message = match deadline {
+            Some(deadline) => {
+                receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        match message {
+            Ok(Message::Item(item)) => {
+                if pending.is_empty() {
+                    deadline = Some(Instant::now() + config.flush_interval());
+                }
+                pending.push(item);
+                if pending.len() >= max_batch {
+                    write_batch(db, &mut pending, trash_grace_period_ms, last_error);
+                    deadline = None;
+                }
+            }
+            Ok(Message::Flush(ack)) => {
+                write_batch(db, &mut pending, trash_grace_period_ms, last_error);
+                deadline = None;
+                let _ = ack.send(());
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                write_batch(db, &mut pending, trash_grace_period_ms, last_error);
+                deadline = None;
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                write_batch(db, &mut pending, trash_grace_period_ms, last_error);
+                break;
+            }
+        }
+    }
+}
+
+fn write_batch(
+    db: &Database,
+    pending: &mut Vec<NewItem>,
+    trash_grace_period_ms: i64,
+    last_error: &Mutex<Option<String>>,
+) {
+    if pending.is_empty() {
+        return;
+    }
+
+    // Items and their paste queue entries are committed together
+    let cutoff = trash_cutoff(trash_grace_period_ms);
+    let written = db.write_tx(|conn| {
+        let results = Database::insert_batch_with(conn, pending, true)?;
+        let ids: Vec<i64> = results.iter().filter_map(|r| r.as_ref().ok().copied()).collect();
+        paste_queue::capture(conn, &ids)?;
+        Database::purge_trash_with(conn, cutoff)?;
+        Ok(results)
+    });
+
+    let error = match written {
+        Ok(results) => results.into_iter().find_map(Result::err),
+        Err(e) => Some(e),
+    };
+    if let Some(e) = error {
+        *last_error.lock() = Some(format!("{:#}", e));
+    }
+    pending.clear();
+}
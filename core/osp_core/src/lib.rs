@@ -40,19 +40,57 @@
 //! let results = core.search("hello", 10).unwrap();
 //! ```
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+mod backup;
+mod collections;
+mod config;
 mod db;
+mod export;
+mod facets;
+mod fuzzy;
+mod import;
+mod ingest;
+mod maintenance;
 mod models;
+mod paste_queue;
+mod pool;
+mod ranking;
+mod recovery;
+mod regexp;
+mod saved_searches;
 mod search;
+mod slots;
+mod snippets;
+mod stats;
+mod usage;
 
 pub mod ffi;
 
-pub use models::{Item, ItemKind, NewItem};
+pub use backup::{BackupOptions, BackupProgress, BackupReport, BackupStage, RestoreReport};
+pub use collections::Collection;
+pub use config::{CaptureRules, Config, IngestConfig};
+pub use export::{ExportFormat, ExportOptions, ExportReport};
+pub use facets::{Count, Facets};
+pub use import::{ImportFormat, ImportOptions, ImportReport};
+pub use maintenance::{
+    Checkpoint, MaintenanceOptions, MaintenanceReport, MaintenanceStep, Progress,
+};
+pub use models::{Item, ItemKind, ItemVersion, NewItem};
+pub use paste_queue::{PasteQueue, QueueMode};
+pub use recovery::{RecoveryReport, TableRecovery};
+pub use saved_searches::SavedSearch;
+pub use search::{ParseError, SearchMode, SearchQuery, SortOrder};
+pub use slots::Slot;
+pub use snippets::{Rendered, Snippet};
+pub use stats::{DedupeStats, Stats};
 
 use db::Database;
+use ingest::IngestWorker;
 
 /// Main handle to the clipboard storage system.
 ///
@@ -60,8 +98,30 @@ use db::Database;
 #[derive(Clone)]
 pub struct Core {
     db: Arc<Database>,
-    #[allow(dead_code)]
+    db_path: PathBuf,
     blobs_dir: PathBuf,
+    ingest: Option<Arc<IngestWorker>>,
+    capture: CaptureRules,
+    trash_grace_period_ms: i64,
+}
+
+/// Current time in milliseconds since the Unix epoch, the unit used for
+/// every timestamp in the database.
+pub(crate) fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Current local time, falling back to UTC when the offset is unknown.
+pub(crate) fn now_local() -> time::OffsetDateTime {
+    time::OffsetDateTime::now_local().unwrap_or_else(|_| time::OffsetDateTime::now_utc())
+}
+
+/// Items trashed at or before this time have outlived the grace period.
+pub(crate) fn trash_cutoff(grace_period_ms: i64) -> i64 {
+    now_millis().saturating_sub(grace_period_ms)
 }
 
 impl Core {
@@ -81,15 +141,109 @@ impl Core {
     /// let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
     /// ```
     pub fn open(db_path: &Path, blobs_dir: &Path) -> Result<Self> {
+        Self::open_with_config(db_path, blobs_dir, Config::default())
+    }
+
+    /// Open or create a clipboard database with the given configuration.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use osp_core::{Config, Core, IngestConfig};
+    /// use std::path::Path;
+    ///
+    /// let config = Config {
+    ///     ingest: Some(IngestConfig::default()),
+    ///     ..Config::default()
+    /// };
+    /// let core = Core::open_with_config(Path::new("clipboard.db"), Path::new("blobs"), config).unwrap();
+    /// ```
+    pub fn open_with_config(db_path: &Path, blobs_dir: &Path, config: Config) -> Result<Self> {
+        Self::with_database(Database::open(db_path)?, db_path, blobs_dir, config)
+    }
+
+    /// Open a clipboard database, recovering it if it is corrupted.
+    ///
+    /// Runs `PRAGMA quick_check` after opening, which reads the whole file.
+    /// If opening or the check finds corruption, the damaged file is moved
+    /// aside as `<db_path>.corrupt-<timestamp>`, a new database is created
+    /// and every row that can still be read is copied into it. The report
+    /// is `None` when the database was healthy.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use osp_core::{Config, Core};
+    /// use std::path::Path;
+    ///
+    /// let (core, report) =
+    ///     Core::open_with_recovery(Path::new("clipboard.db"), Path::new("blobs"), Config::default())
+    ///         .unwrap();
+    /// if let Some(report) = report {
+    ///     eprintln!(
+    ///         "Recovered {} items, damaged database kept at {:?}",
+    ///         report.items_recovered, report.moved_to
+    ///     );
+    /// }
+    /// ```
+    pub fn open_with_recovery(
+        db_path: &Path,
+        blobs_dir: &Path,
+        config: Config,
+    ) -> Result<(Self, Option<RecoveryReport>)> {
+        let reason = match Database::open(db_path) {
+            Ok(db) => {
+                let problem = recovery::check(&db.writer());
+                match problem {
+                    Ok(None) => {
+                        return Ok((Self::with_database(db, db_path, blobs_dir, config)?, None))
+                    }
+                    Ok(Some(problem)) => problem,
+                    Err(e) if recovery::is_corruption(&e) => format!("{:#}", e),
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) if recovery::is_corruption(&e) => format!("{:#}", e),
+            Err(e) => return Err(e),
+        };
+
+        let mut report = RecoveryReport {
+            reason,
+            moved_to: recovery::move_aside(db_path)?,
+            ..Default::default()
+        };
         let db = Database::open(db_path)?;
+        recovery::salvage(&db.writer(), &mut report)?;
+
+        Ok((Self::with_database(db, db_path, blobs_dir, config)?, Some(report)))
+    }
+
+    fn with_database(db: Database, db_path: &Path, blobs_dir: &Path, config: Config) -> Result<Self> {
+        let db = Arc::new(db);
 
         // Ensure blobs directory exists
         std::fs::create_dir_all(blobs_dir)?;
 
-        Ok(Self {
-            db: Arc::new(db),
+        let ingest = match config.ingest {
+            Some(ingest_config) => Some(Arc::new(IngestWorker::spawn(
+                db.clone(),
+                ingest_config,
+                config.trash_grace_period_ms,
+            )?)),
+            None => None,
+        };
+
+        let core = Self {
+            db,
+            db_path: db_path.to_path_buf(),
             blobs_dir: blobs_dir.to_path_buf(),
-        })
+            ingest,
+            capture: config.capture,
+            trash_grace_period_ms: config.trash_grace_period_ms,
+        };
+        core.purge_expired_trash()?;
+
+        Ok(core)
     }
 
     /// Add a new item to the clipboard.
@@ -117,6 +271,53 @@ impl Core {
         self.db.insert_item(&item)
     }
 
+    /// Add many items in a single transaction.
+    ///
+    /// Much faster than calling [`Core::add_item`] in a loop for imports.
+    /// Returns one result per item, in input order; a failing item doesn't
+    /// prevent the others from being inserted.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use osp_core::{Core, NewItem, ItemKind};
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    ///
+    /// let items = (0..1000).map(|i| NewItem {
+    ///     kind: ItemKind::Text,
+    ///     content_ref: format!("Item {}", i),
+    ///     source_app: None,
+    ///     created_at: 1234567890000 + i,
+    ///     tags: vec![],
+    /// });
+    ///
+    /// let ids: Vec<i64> = core.add_items(items).into_iter().filter_map(Result::ok).collect();
+    /// ```
+    pub fn add_items(&self, items: impl IntoIterator<Item = NewItem>) -> Vec<Result<i64>> {
+        self.insert_batch(items.into_iter().collect(), false)
+    }
+
+    /// Add many items in a single transaction with deduplication.
+    ///
+    /// Like [`Core::add_items`], but each item is checked against the
+    /// database and the earlier items of the same batch, as in
+    /// [`Core::dedupe_insert`]. Duplicates bump the existing item and
+    /// return its ID.
+    pub fn dedupe_insert_items(&self, items: impl IntoIterator<Item = NewItem>) -> Vec<Result<i64>> {
+        self.insert_batch(items.into_iter().collect(), true)
+    }
+
+    fn insert_batch(&self, items: Vec<NewItem>, dedupe: bool) -> Vec<Result<i64>> {
+        match self.db.insert_batch(&items, dedupe) {
+            Ok(results) => results,
+            Err(e) => items
+                .iter()
+                .map(|_| Err(anyhow::anyhow!("{:#}", e)))
+                .collect(),
+        }
+    }
+
     /// Get an item by ID.
     ///
     /// # Example
@@ -132,7 +333,12 @@ impl Core {
         self.db.get_item(id)
     }
 
-    /// Delete an item by ID.
+    /// Move an item to the trash.
+    ///
+    /// Trashed items are hidden from [`Core::get`], search and dedupe until
+    /// they are restored with [`Core::restore`]. They are purged for good
+    /// once [`Config::trash_grace_period_ms`] has passed. The item's
+    /// quick-paste slot, if any, is cleared.
     ///
     /// # Example
     ///
@@ -141,11 +347,475 @@ impl Core {
     /// # use std::path::Path;
     /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
     /// core.delete(1).unwrap();
+    /// core.restore(1).unwrap();
     /// ```
     pub fn delete(&self, id: i64) -> Result<()> {
+        self.db.trash_item(id, now_millis())?;
+        self.purge_expired_trash()?;
+        Ok(())
+    }
+
+    /// Delete an item immediately, bypassing the trash.
+    pub fn delete_permanently(&self, id: i64) -> Result<()> {
         self.db.delete_item(id)
     }
 
+    /// Move every item that is neither pinned nor in a quick-paste slot to
+    /// the trash.
+    ///
+    /// Returns the number of items trashed.
+    pub fn clear_history(&self) -> Result<usize> {
+        let count = self.db.trash_unpinned(now_millis())?;
+        self.purge_expired_trash()?;
+        Ok(count)
+    }
+
+    /// List trashed items, most recently deleted first.
+    pub fn trash_list(&self, limit: u32) -> Result<Vec<Item>> {
+        self.db.trashed_items(limit)
+    }
+
+    /// Take an item back out of the trash.
+    pub fn restore(&self, id: i64) -> Result<()> {
+        self.db.restore_item(id)
+    }
+
+    /// Permanently delete everything in the trash.
+    ///
+    /// Returns the number of items removed.
+    pub fn empty_trash(&self) -> Result<usize> {
+        self.db.purge_trash(i64::MAX)
+    }
+
+    /// Permanently delete trashed items older than the grace period.
+    ///
+    /// Runs automatically on open, after deletes and with every capture;
+    /// exposed so frontends can also call it on a timer. Returns the number
+    /// of items removed.
+    pub fn purge_expired_trash(&self) -> Result<usize> {
+        self.db.purge_trash(trash_cutoff(self.trash_grace_period_ms))
+    }
+
+    /// How long trashed items are kept, in milliseconds (see
+    /// [`Config::trash_grace_period_ms`]).
+    pub fn trash_grace_period_ms(&self) -> i64 {
+        self.trash_grace_period_ms
+    }
+
+    /// Replace the content of a text or rtf item.
+    ///
+    /// The previous content is kept and can be listed with [`Core::versions`]
+    /// and brought back with [`Core::revert`]. The item is reindexed for
+    /// search and deduplication.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::Core;
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// core.update_content(1, "Fixed typo").unwrap();
+    /// let versions = core.versions(1).unwrap();
+    /// core.revert(1, versions[0].version).unwrap();
+    /// ```
+    pub fn update_content(&self, id: i64, new_text: &str) -> Result<()> {
+        self.db.update_content(id, new_text, now_millis())
+    }
+
+    /// List the previous contents of an item, oldest first.
+    pub fn versions(&self, id: i64) -> Result<Vec<ItemVersion>> {
+        self.db.item_versions(id)
+    }
+
+    /// Restore an item's content to a previous version.
+    ///
+    /// The content being replaced is recorded as a new version, so a revert
+    /// can itself be reverted.
+    pub fn revert(&self, id: i64, version: i64) -> Result<()> {
+        self.db.revert_content(id, version, now_millis())
+    }
+
+    /// Set or clear the user-defined title and note of an item.
+    ///
+    /// Both are indexed for full-text search, with titles ranked above
+    /// content. Search only titles with `title:` (e.g. `title:deploy`).
+    /// `None` or an empty string clears the field.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::Core;
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// core.annotate(1, Some("Nginx config"), Some("Staging only")).unwrap();
+    /// let results = core.search("title:nginx", 10).unwrap();
+    /// ```
+    pub fn annotate(&self, id: i64, title: Option<&str>, note: Option<&str>) -> Result<()> {
+        let title = title.map(str::trim).filter(|s| !s.is_empty());
+        let note = note.map(str::trim).filter(|s| !s.is_empty());
+        self.db.annotate(id, title, note)
+    }
+
+    /// Create a snippet: a text item named `name`, expanded by `abbreviation`.
+    ///
+    /// The template may contain `{{date}}`, `{{date:%Y-%m-%d}}`, `{{time}}`,
+    /// `{{clipboard}}` and `{{cursor}}` placeholders; see
+    /// [`Core::render_template`]. Abbreviations must be unique and contain no
+    /// whitespace.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::Core;
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// core.create_snippet("Signature", ";sig", "Regards,\n{{cursor}}").unwrap();
+    /// let rendered = core.expand_snippet(";sig").unwrap().unwrap();
+    /// println!("{} (cursor at {:?})", rendered.text, rendered.cursor);
+    /// ```
+    pub fn create_snippet(&self, name: &str, abbreviation: &str, template: &str) -> Result<i64> {
+        snippets::validate_abbreviation(abbreviation)?;
+        let name = Some(name.trim()).filter(|s| !s.is_empty());
+        let item = NewItem {
+            kind: ItemKind::Text,
+            content_ref: template.to_string(),
+            source_app: None,
+            created_at: now_millis(),
+            tags: vec![],
+        };
+
+        self.db.write_tx(|conn| {
+            let id = Database::insert_with(conn, &item)?;
+            Database::annotate_with(conn, id, name, None)?;
+            snippets::set_abbreviation(conn, id, abbreviation)?;
+            Ok(id)
+        })
+    }
+
+    /// Turn an existing text item into a snippet, or change its abbreviation.
+    pub fn set_snippet_abbreviation(&self, id: i64, abbreviation: &str) -> Result<()> {
+        self.db
+            .write_tx(|conn| snippets::set_abbreviation(conn, id, abbreviation))
+    }
+
+    /// Remove an item's abbreviation. The item itself stays in the history.
+    pub fn remove_snippet(&self, id: i64) -> Result<()> {
+        snippets::remove(&self.db.writer(), id)
+    }
+
+    /// List all snippets, ordered by abbreviation.
+    pub fn snippets(&self) -> Result<Vec<Snippet>> {
+        snippets::list(&self.db.reader())
+    }
+
+    /// Look up a snippet by its exact abbreviation.
+    pub fn snippet(&self, abbreviation: &str) -> Result<Option<Snippet>> {
+        snippets::find(&self.db.reader(), abbreviation)
+    }
+
+    /// Render a text item as a template.
+    ///
+    /// `{{date}}` and `{{time}}` use the local time zone, `{{clipboard}}` is
+    /// the most recent text item that isn't a snippet, and `{{cursor}}` is
+    /// removed and reported as [`Rendered::cursor`].
+    pub fn render_template(&self, id: i64) -> Result<Rendered> {
+        let item = self.db.get_item(id)?;
+        if !matches!(item.kind, ItemKind::Text | ItemKind::Rtf) {
+            anyhow::bail!("Only text and rtf items can be rendered");
+        }
+
+        let context = snippets::RenderContext {
+            now: now_local(),
+            clipboard: snippets::latest_text(&self.db.reader())?,
+        };
+        Ok(snippets::render(&item.content_ref, &context))
+    }
+
+    /// Render the snippet with the given abbreviation.
+    ///
+    /// Returns `None` if no snippet uses this abbreviation.
+    pub fn expand_snippet(&self, abbreviation: &str) -> Result<Option<Rendered>> {
+        match self.snippet(abbreviation)? {
+            Some(snippet) => self.render_template(snippet.id).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Create a named collection and return its ID.
+    ///
+    /// Names are unique, ignoring case. Items can belong to any number of
+    /// collections, each with its own manual order.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::Core;
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// let deploy = core.create_collection("Deploy commands").unwrap();
+    /// core.add_to_collection(deploy, 1, None).unwrap();
+    /// core.add_to_collection(deploy, 2, Some(0)).unwrap();
+    /// let items = core.collection_items(deploy).unwrap();
+    /// ```
+    pub fn create_collection(&self, name: &str) -> Result<i64> {
+        collections::create(&self.db.writer(), name, now_millis())
+    }
+
+    /// Rename a collection.
+    pub fn rename_collection(&self, id: i64, name: &str) -> Result<()> {
+        collections::rename(&self.db.writer(), id, name)
+    }
+
+    /// Delete a collection. Its items stay in the history.
+    pub fn delete_collection(&self, id: i64) -> Result<()> {
+        collections::delete(&self.db.writer(), id)
+    }
+
+    /// List all collections, ordered by name.
+    pub fn collections(&self) -> Result<Vec<Collection>> {
+        collections::list(&self.db.reader())
+    }
+
+    /// List the collections an item belongs to.
+    pub fn item_collections(&self, item_id: i64) -> Result<Vec<Collection>> {
+        collections::for_item(&self.db.reader(), item_id)
+    }
+
+    /// List the items of a collection in their manual order.
+    pub fn collection_items(&self, id: i64) -> Result<Vec<Item>> {
+        collections::items(&self.db.reader(), id)
+    }
+
+    /// Add an item to a collection at `position` (0-based), or at the end if
+    /// `None`. An item already in the collection is moved instead.
+    ///
+    /// Positions index the items listed by [`Core::collection_items`], so
+    /// trashed items are not counted.
+    pub fn add_to_collection(&self, id: i64, item_id: i64, position: Option<usize>) -> Result<()> {
+        self.db
+            .write_tx(|conn| collections::add_item(conn, id, item_id, position))
+    }
+
+    /// Remove an item from a collection. The item stays in the history.
+    pub fn remove_from_collection(&self, id: i64, item_id: i64) -> Result<()> {
+        self.db
+            .write_tx(|conn| collections::remove_item(conn, id, item_id))
+    }
+
+    /// Move an item to a new position (0-based) within a collection, among
+    /// the items listed by [`Core::collection_items`].
+    pub fn move_in_collection(&self, id: i64, item_id: i64, position: usize) -> Result<()> {
+        self.db
+            .write_tx(|conn| collections::move_item(conn, id, item_id, position))
+    }
+
+    /// Save a search under a name and return its ID.
+    ///
+    /// Saved searches work as smart collections: the query, inline
+    /// operators included, is rerun each time, so `after:7d` always covers
+    /// the last seven days. Names are unique, ignoring case. Fails if the
+    /// query doesn't parse.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::{Core, SearchQuery};
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// let id = core
+    ///     .save_search("Recent screenshots", &SearchQuery::new("kind:image after:7d", 50))
+    ///     .unwrap();
+    /// let first_page = core.run_saved_search(id, 0).unwrap();
+    /// ```
+    pub fn save_search(&self, name: &str, query: &SearchQuery) -> Result<i64> {
+        saved_searches::create(&self.db.writer(), name, query, now_millis())
+    }
+
+    /// Replace the name and query of a saved search.
+    pub fn update_saved_search(&self, id: i64, name: &str, query: &SearchQuery) -> Result<()> {
+        saved_searches::update(&self.db.writer(), id, name, query)
+    }
+
+    /// Delete a saved search.
+    pub fn delete_saved_search(&self, id: i64) -> Result<()> {
+        saved_searches::delete(&self.db.writer(), id)
+    }
+
+    /// List all saved searches, ordered by name.
+    pub fn list_saved_searches(&self) -> Result<Vec<SavedSearch>> {
+        saved_searches::list(&self.db.reader())
+    }
+
+    /// Run a saved search and return one page (0-based) of results. Pages
+    /// hold the query's `limit` items.
+    pub fn run_saved_search(&self, id: i64, page: u32) -> Result<Vec<Item>> {
+        let search = saved_searches::get(&self.db.reader(), id)?;
+        self.search_input(saved_searches::page_query(&search, page))
+    }
+
+    /// Bind an item to quick-paste slot 1-9, replacing the slot's previous
+    /// item. An item is in at most one slot, so it leaves any other slot.
+    ///
+    /// Slotted items are kept by [`Core::clear_history`]; the slot is
+    /// cleared when the item is deleted.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::Core;
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// core.assign_slot(1, 42).unwrap();
+    /// if let Some(item) = core.slot(1).unwrap() {
+    ///     println!("Slot 1: {}", item.content_ref);
+    /// }
+    /// ```
+    pub fn assign_slot(&self, slot: u8, id: i64) -> Result<()> {
+        self.db.write_tx(|conn| slots::assign(conn, slot, id))
+    }
+
+    /// Empty a quick-paste slot. Returns whether it held an item.
+    pub fn clear_slot(&self, slot: u8) -> Result<bool> {
+        slots::clear(&self.db.writer(), slot)
+    }
+
+    /// The item in a quick-paste slot, or `None` if the slot is empty.
+    pub fn slot(&self, slot: u8) -> Result<Option<Item>> {
+        slots::get(&self.db.reader(), slot)
+    }
+
+    /// All occupied quick-paste slots, in slot order.
+    pub fn slots(&self) -> Result<Vec<Slot>> {
+        slots::list(&self.db.reader())
+    }
+
+    /// Record that the user picked `item_id` from the results for `query`.
+    ///
+    /// `query` is the search box input, operators included. Items picked
+    /// for a query rank higher the next time its text (ignoring operators,
+    /// case and extra whitespace) is searched with [`SortOrder::Relevance`].
+    /// The boost fades over time.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::Core;
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// let results = core.search("deploy", 10).unwrap();
+    /// core.record_selection("deploy", results[2].id).unwrap();
+    /// ```
+    pub fn record_selection(&self, query: &str, item_id: i64) -> Result<()> {
+        ranking::record_selection(&self.db.writer(), query, item_id, now_millis())
+    }
+
+    /// Forget everything learned by [`Core::record_selection`].
+    ///
+    /// Returns the number of query/item associations removed.
+    pub fn reset_learned_ranking(&self) -> Result<usize> {
+        ranking::reset(&self.db.writer())
+    }
+
+    /// Record that an item was pasted from Haste, optionally into
+    /// `target_app` (e.g. a bundle ID).
+    ///
+    /// Uses feed the frecency score used by [`SortOrder::Frecency`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::{Core, SearchQuery, SortOrder};
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// core.record_use(1, Some("com.apple.Terminal")).unwrap();
+    ///
+    /// let query = SearchQuery {
+    ///     sort: SortOrder::Frecency,
+    ///     ..SearchQuery::new("ssh", 10)
+    /// };
+    /// let results = core.search_with(&query).unwrap();
+    /// ```
+    pub fn record_use(&self, id: i64, target_app: Option<&str>) -> Result<()> {
+        usage::record(&self.db.writer(), id, target_app, now_millis())
+    }
+
+    /// Suggest items for pasting into `target_app`.
+    ///
+    /// Ranks the items previously pasted into that app (see
+    /// [`Core::record_use`]) by how often and how recently they were pasted
+    /// there. Frontends can show these first when opening over the app.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::Core;
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// for item in core.suggest("com.apple.Terminal", 5).unwrap() {
+    ///     println!("{}", item.content_ref);
+    /// }
+    /// ```
+    pub fn suggest(&self, target_app: &str, limit: u32) -> Result<Vec<Item>> {
+        usage::suggest(&self.db.reader(), target_app, limit)
+    }
+
+    /// Current paste queue options.
+    pub fn paste_queue(&self) -> Result<PasteQueue> {
+        paste_queue::options(&self.db.reader())
+    }
+
+    /// Change the paste queue options: FIFO or LIFO mode, whether queue
+    /// mode is active, and whether captures are queued automatically while
+    /// it is. Options persist across restarts.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::{Core, PasteQueue, QueueMode};
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// core.set_paste_queue(&PasteQueue {
+    ///     mode: QueueMode::Fifo,
+    ///     active: true,
+    ///     auto_enqueue: true,
+    /// }).unwrap();
+    ///
+    /// // ...the user copies several values, each captured with dedupe_insert...
+    ///
+    /// while let Some(item) = core.queue_pop().unwrap() {
+    ///     println!("paste {}", item.content_ref);
+    /// }
+    /// ```
+    pub fn set_paste_queue(&self, options: &PasteQueue) -> Result<()> {
+        paste_queue::set_options(&self.db.writer(), options)
+    }
+
+    /// Add an item to the paste queue.
+    pub fn queue_push(&self, id: i64) -> Result<()> {
+        paste_queue::push(&self.db.writer(), id)
+    }
+
+    /// Remove and return the next item to paste, or `None` if the queue is
+    /// empty.
+    pub fn queue_pop(&self) -> Result<Option<Item>> {
+        self.db.write_tx(paste_queue::pop)
+    }
+
+    /// The next item to paste, without removing it.
+    pub fn queue_peek(&self) -> Result<Option<Item>> {
+        paste_queue::peek(&self.db.reader())
+    }
+
+    /// All queued items, in the order they will be pasted.
+    pub fn queue_items(&self) -> Result<Vec<Item>> {
+        paste_queue::items(&self.db.reader())
+    }
+
+    /// Empty the paste queue. Returns the number of entries removed.
+    pub fn queue_clear(&self) -> Result<usize> {
+        paste_queue::clear(&self.db.writer())
+    }
+
     /// Set the pinned status of an item.
     ///
     /// Pinned items can be given special treatment in the UI.
@@ -165,7 +835,11 @@ impl Core {
     /// Search for items matching the query.
     ///
     /// Uses FTS5 full-text search for queries >= 3 characters,
-    /// falls back to LIKE for shorter queries.
+    /// falls back to LIKE for shorter queries. CJK text and substring
+    /// queries (`*config`, or words with no whole-word match) use the
+    /// trigram index. Inline operators such as `kind:image` or
+    /// `after:yesterday` are applied as filters (see [`SearchQuery::parse`]);
+    /// a malformed operator fails with a [`ParseError`].
     ///
     /// # Arguments
     ///
@@ -178,13 +852,293 @@ impl Core {
     /// # use osp_core::Core;
     /// # use std::path::Path;
     /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
-    /// let results = core.search("rust", 10).unwrap();
+    /// let results = core.search("rust app:Terminal after:3d", 10).unwrap();
     /// for item in results {
     ///     println!("{}: {}", item.id, item.content_ref);
     /// }
     /// ```
     pub fn search(&self, query: &str, limit: u32) -> Result<Vec<Item>> {
-        self.db.search(query, limit)
+        self.search_input(SearchQuery::new(query, limit))
+    }
+
+    /// Search with `query.text` taken as typed into the search box.
+    ///
+    /// Inline operators are moved into the filters first (see
+    /// [`SearchQuery::parse_operators`]), so the same input finds the same
+    /// items whatever the mode, collection or sort order. A malformed
+    /// operator fails with a [`ParseError`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::{Core, SearchMode, SearchQuery};
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// let query = SearchQuery {
+    ///     mode: SearchMode::Fuzzy,
+    ///     ..SearchQuery::new("kubctl app:Terminal", 10)
+    /// };
+    /// let results = core.search_input(query).unwrap();
+    /// ```
+    pub fn search_input(&self, query: SearchQuery) -> Result<Vec<Item>> {
+        self.db.search(&query.parse_operators()?)
+    }
+
+    /// Search with filters, a sort order and a match mode, e.g. restricted
+    /// to one collection, ranked by frecency or fuzzy. Inline operators in
+    /// `query.text` are not parsed; see [`Core::search_input`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::{Core, SearchQuery};
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// let query = SearchQuery {
+    ///     collection: Some(1),
+    ///     ..SearchQuery::new("kubectl", 10)
+    /// };
+    /// let results = core.search_with(&query).unwrap();
+    /// ```
+    pub fn search_with(&self, query: &SearchQuery) -> Result<Vec<Item>> {
+        self.db.search(query)
+    }
+
+    /// Count the items matching a query by kind, source app, tag, pinned
+    /// state and creation day and week, without loading them.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::{Core, SearchQuery};
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// let facets = core.facets(&SearchQuery::parse("deploy after:30d").unwrap()).unwrap();
+    /// for kind in &facets.kinds {
+    ///     println!("{:?} ({})", kind.value, kind.count);
+    /// }
+    /// println!("Pinned ({})", facets.pinned);
+    /// ```
+    pub fn facets(&self, query: &SearchQuery) -> Result<Facets> {
+        facets::facets(&self.db.reader(), query)
+    }
+
+    /// Storage and usage report: item counts, text, blob and database
+    /// sizes, oldest and newest items, top source apps, items per day for
+    /// the last 30 days and the dedupe hit rate.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::Core;
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// let stats = core.stats().unwrap();
+    /// println!("{} items, {} bytes of text", stats.total, stats.text_bytes);
+    /// for day in &stats.days {
+    ///     println!("{}: {}", day.value, day.count);
+    /// }
+    /// ```
+    pub fn stats(&self) -> Result<Stats> {
+        stats::stats(
+            &self.db.reader(),
+            &self.db_path,
+            &self.blobs_dir,
+            stats::WINDOW_DAYS,
+            now_millis(),
+        )
+    }
+
+    /// Check and compact the database: integrity checks, search index
+    /// optimization, vacuum, `ANALYZE` and a WAL checkpoint, as selected by
+    /// `options`.
+    ///
+    /// Captures wait until it finishes, so run it while the app is idle.
+    /// When the integrity check finds problems, nothing is written and the
+    /// report lists them.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::{Core, MaintenanceOptions};
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// let report = core.maintenance(&MaintenanceOptions::default()).unwrap();
+    /// if !report.is_healthy() {
+    ///     eprintln!("Database problems: {:?}", report.integrity_errors);
+    /// }
+    /// println!("Freed {} bytes", report.bytes_freed);
+    /// ```
+    pub fn maintenance(&self, options: &MaintenanceOptions) -> Result<MaintenanceReport> {
+        self.maintenance_with_progress(options, |_| {})
+    }
+
+    /// Like [`Core::maintenance`], calling `progress` before each step.
+    pub fn maintenance_with_progress(
+        &self,
+        options: &MaintenanceOptions,
+        progress: impl FnMut(Progress),
+    ) -> Result<MaintenanceReport> {
+        maintenance::run(&self.db.writer(), options, progress)
+    }
+
+    /// Back up the database, and optionally the blobs directory, to a single
+    /// file at `path`, replacing it if it exists.
+    ///
+    /// Safe while the app is running: the copy is a consistent snapshot and
+    /// captures are not blocked.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::{BackupOptions, Core};
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// let options = BackupOptions { include_blobs: true };
+    /// let report = core.backup_to(Path::new("clipboard-backup.db"), &options).unwrap();
+    /// println!("Backed up {} bytes", report.bytes);
+    /// ```
+    pub fn backup_to(&self, path: &Path, options: &BackupOptions) -> Result<BackupReport> {
+        self.backup_to_with_progress(path, options, |_| {})
+    }
+
+    /// Like [`Core::backup_to`], calling `progress` as pages and blobs are
+    /// copied.
+    pub fn backup_to_with_progress(
+        &self,
+        path: &Path,
+        options: &BackupOptions,
+        mut progress: impl FnMut(BackupProgress),
+    ) -> Result<BackupReport> {
+        backup::backup(&self.db.reader(), &self.blobs_dir, path, options, &mut progress)
+    }
+
+    /// Write a new timestamped backup to `dir` and delete the oldest ones,
+    /// keeping the `keep` most recent. Meant for automatic backups.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::{BackupOptions, Core};
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// core.backup_rotating(Path::new("backups"), 7, &BackupOptions::default()).unwrap();
+    /// ```
+    pub fn backup_rotating(&self, dir: &Path, keep: usize, options: &BackupOptions) -> Result<BackupReport> {
+        self.backup_rotating_with_progress(dir, keep, options, |_| {})
+    }
+
+    /// Like [`Core::backup_rotating`], calling `progress` as pages and blobs
+    /// are copied.
+    pub fn backup_rotating_with_progress(
+        &self,
+        dir: &Path,
+        keep: usize,
+        options: &BackupOptions,
+        progress: impl FnMut(BackupProgress),
+    ) -> Result<BackupReport> {
+        if keep == 0 {
+            anyhow::bail!("Must keep at least one backup");
+        }
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create backup directory {:?}", dir))?;
+
+        let mut report = self.backup_to_with_progress(&backup::rotating_path(dir)?, options, progress)?;
+        report.removed = backup::prune(dir, keep)?;
+        Ok(report)
+    }
+
+    /// Backups written by [`Core::backup_rotating`] to `dir`, newest first.
+    pub fn list_backups(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        backup::list(dir)
+    }
+
+    /// Replace the database with a backup made by [`Core::backup_to`], and
+    /// write the blobs it includes back to the blobs directory.
+    ///
+    /// The backup's schema version is checked first, and the current
+    /// database is left untouched if it is newer than this version supports,
+    /// damaged, or not a backup at all. Older backups are migrated.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::Core;
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// let report = core.restore_from(Path::new("clipboard-backup.db")).unwrap();
+    /// println!("Restored {} items", report.items);
+    /// ```
+    pub fn restore_from(&self, path: &Path) -> Result<RestoreReport> {
+        self.restore_from_with_progress(path, |_| {})
+    }
+
+    /// Like [`Core::restore_from`], calling `progress` as blobs and pages
+    /// are copied.
+    pub fn restore_from_with_progress(
+        &self,
+        path: &Path,
+        mut progress: impl FnMut(BackupProgress),
+    ) -> Result<RestoreReport> {
+        // Queued captures go into the current database, which is replaced
+        self.flush()?;
+        backup::restore(&mut self.db.writer(), &self.blobs_dir, path, &mut progress)
+    }
+
+    /// Export the items matching `options.query` (the whole history by
+    /// default) to `out`, oldest first, as JSON Lines, CSV or Markdown.
+    ///
+    /// Items are streamed, so large histories don't have to fit in memory.
+    /// With `options.sidecar_dir` set, the files of image and file items
+    /// are copied there too.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::{Core, ExportFormat, ExportOptions, SearchQuery};
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// let options = ExportOptions {
+    ///     format: ExportFormat::Markdown,
+    ///     query: Some(SearchQuery::parse("app:Terminal after:7d").unwrap()),
+    ///     ..Default::default()
+    /// };
+    /// core.export(&mut std::io::stdout(), &options).unwrap();
+    /// ```
+    pub fn export(&self, out: &mut impl Write, options: &ExportOptions) -> Result<ExportReport> {
+        export::export(&self.db.reader(), &self.blobs_dir, out, options)
+    }
+
+    /// Like [`Core::export`], writing to a file at `path`.
+    pub fn export_to(&self, path: &Path, options: &ExportOptions) -> Result<ExportReport> {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create export at {:?}", path))?;
+        self.export(&mut std::io::BufWriter::new(file), options)
+    }
+
+    /// Import the history of another clipboard manager, or a generic list,
+    /// from the file at `path`.
+    ///
+    /// Original timestamps and source apps are kept where the format has
+    /// them. All items are added in one batch with deduplication, so
+    /// duplicates, within the file or of existing items, are counted rather
+    /// than added.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use osp_core::{Core, ImportFormat, ImportOptions};
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    /// let report = core
+    ///     .import(Path::new("Storage.sqlite"), &ImportOptions::new(ImportFormat::Maccy))
+    ///     .unwrap();
+    /// println!("{} imported, {} duplicates", report.imported, report.duplicates);
+    /// ```
+    pub fn import(&self, path: &Path, options: &ImportOptions) -> Result<ImportReport> {
+        // Queued captures are older than the import
+        self.flush()?;
+        import::run(&self.db, &self.blobs_dir, path, options, now_millis())
     }
 
     /// Insert an item only if no duplicate exists.
@@ -195,6 +1149,10 @@ impl Core {
     /// - For text/rtf: normalized content (whitespace-collapsed)
     /// - For image/file: exact content_ref path
     ///
+    /// While the paste queue is active with auto-enqueue on (see
+    /// [`Core::set_paste_queue`]), the item is also added to the queue.
+    /// Expired trash is purged in the same transaction.
+    ///
     /// # Example
     ///
     /// ```no_run
@@ -217,11 +1175,69 @@ impl Core {
     /// }
     /// ```
     pub fn dedupe_insert(&self, item: NewItem) -> Result<Option<i64>> {
-        if self.db.has_duplicate(&item)? {
-            // Update timestamp of existing duplicate to "bump" it to the top
-            self.db.update_duplicate_timestamp(&item)
-        } else {
-            Ok(Some(self.db.insert_item(&item)?))
+        // A duplicate has its timestamp updated to "bump" it to the top.
+        // Queued in the same transaction so a capture is never half-recorded.
+        let cutoff = trash_cutoff(self.trash_grace_period_ms);
+        let id = self.db.write_tx(|conn| {
+            let id = Database::dedupe_insert_with(conn, &item)?;
+            paste_queue::capture(conn, &[id])?;
+            Database::purge_trash_with(conn, cutoff)?;
+            Ok(id)
+        })?;
+
+        Ok(Some(id))
+    }
+
+    /// Queue an item for deduplicated insertion and return immediately.
+    ///
+    /// Items rejected by the capture rules (see [`Config::capture`]) are
+    /// dropped. With the ingestion worker enabled (see [`Config::ingest`]),
+    /// items are written in batches on a background thread; call
+    /// [`Core::flush`] to wait for them. Without it, this behaves like
+    /// [`Core::dedupe_insert`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use osp_core::{Core, NewItem, ItemKind};
+    /// # use std::path::Path;
+    /// # let core = Core::open(Path::new("clipboard.db"), Path::new("blobs")).unwrap();
+    ///
+    /// core.enqueue(NewItem {
+    ///     kind: ItemKind::Text,
+    ///     content_ref: "Hello!".to_string(),
+    ///     source_app: None,
+    ///     created_at: 1234567890000,
+    ///     tags: vec![],
+    /// }).unwrap();
+    /// core.flush().unwrap();
+    /// ```
+    pub fn enqueue(&self, item: NewItem) -> Result<()> {
+        if !self.capture.allows(&item) {
+            return Ok(());
+        }
+
+        match &self.ingest {
+            Some(worker) => worker.enqueue(item),
+            None => self.dedupe_insert(item).map(|_| ()),
+        }
+    }
+
+    /// Wait until every item passed to [`Core::enqueue`] has been written.
+    pub fn flush(&self) -> Result<()> {
+        match &self.ingest {
+            Some(worker) => worker.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Write pending queued items and stop the ingestion worker.
+    ///
+    /// Called automatically when the last clone of this `Core` is dropped.
+    /// After shutdown, [`Core::enqueue`] returns an error.
+    pub fn shutdown(&self) {
+        if let Some(worker) = &self.ingest {
+            worker.shutdown();
         }
     }
 }
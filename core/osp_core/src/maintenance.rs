@@ -0,0 +1,296 @@
+//! Database maintenance: integrity checks, FTS index upkeep, vacuum,
+//! `ANALYZE` and WAL checkpointing.
+//!
+//! Everything runs on the writer connection, so captures wait until
+//! maintenance is done; frontends are expected to run it while idle.
+//!
+//! The database is created without auto-vacuum. The first vacuum switches
+//! it to incremental auto-vacuum, which takes one full `VACUUM`; later runs
+//! only release the free pages with `PRAGMA incremental_vacuum`.
+
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// FTS5 tables checked, rebuilt and optimized.
+pub const FTS_TABLES: [&str; 2] = ["items_fts", "items_trigram"];
+
+/// `PRAGMA auto_vacuum` value for incremental auto-vacuum.
+const AUTO_VACUUM_INCREMENTAL: i64 = 2;
+
+/// Steps to run. All default to on except `fts_rebuild`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaintenanceOptions {
+    /// `PRAGMA integrity_check`.
+    pub integrity_check: bool,
+    /// FTS5 `integrity-check` on each search index.
+    pub fts_integrity_check: bool,
+    /// Rebuild each search index from scratch. Slow on large histories.
+    pub fts_rebuild: bool,
+    /// Merge each search index into a single segment.
+    pub fts_optimize: bool,
+    /// Release free pages back to the file system.
+    pub vacuum: bool,
+    /// Refresh the query planner statistics.
+    pub analyze: bool,
+    /// `PRAGMA wal_checkpoint(TRUNCATE)`.
+    pub checkpoint: bool,
+}
+
+impl Default for MaintenanceOptions {
+    fn default() -> Self {
+        Self {
+            integrity_check: true,
+            fts_integrity_check: true,
+            fts_rebuild: false,
+            fts_optimize: true,
+            vacuum: true,
+            analyze: true,
+            checkpoint: true,
+        }
+    }
+}
+
+/// A maintenance step, in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceStep {
+    IntegrityCheck,
+    FtsIntegrityCheck,
+    FtsRebuild,
+    FtsOptimize,
+    Vacuum,
+    Analyze,
+    Checkpoint,
+}
+
+impl MaintenanceStep {
+    /// The step's code in core_maintenance_json()'s progress callback.
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+}
+
+/// Reported before each step starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Progress {
+    pub step: MaintenanceStep,
+    /// 0-based position of the step among those selected.
+    pub index: usize,
+    /// Number of steps selected.
+    pub total: usize,
+}
+
+/// Outcome of a `PRAGMA wal_checkpoint(TRUNCATE)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// A reader or writer prevented the checkpoint from completing.
+    pub busy: bool,
+    /// Frames in the WAL before the checkpoint.
+    pub wal_frames: i64,
+    /// Frames copied back into the database.
+    pub checkpointed_frames: i64,
+}
+
+/// What a maintenance run found and did.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceReport {
+    /// Steps that ran, in order.
+    pub steps: Vec<MaintenanceStep>,
+    /// Problems reported by `PRAGMA integrity_check`, other than in the
+    /// search indexes. When there are any, the steps that write to the
+    /// database are skipped.
+    pub integrity_errors: Vec<String>,
+    /// Search indexes that failed the FTS5 integrity check, with the error.
+    /// Running with `fts_rebuild` repairs them.
+    pub fts_errors: Vec<String>,
+    /// Size by which vacuuming shrank the database file.
+    pub bytes_freed: i64,
+    pub checkpoint: Option<Checkpoint>,
+    pub duration_ms: i64,
+}
+
+impl MaintenanceReport {
+    /// No integrity or FTS problems were found.
+    pub fn is_healthy(&self) -> bool {
+        self.integrity_errors.is_empty() && self.fts_errors.is_empty()
+    }
+}
+
+fn selected(options: &MaintenanceOptions) -> Vec<MaintenanceStep> {
+    [
+        (options.integrity_check, MaintenanceStep::IntegrityCheck),
+        (options.fts_integrity_check, MaintenanceStep::FtsIntegrityCheck),
+        (options.fts_rebuild, MaintenanceStep::FtsRebuild),
+        (options.fts_optimize, MaintenanceStep::FtsOptimize),
+        (options.vacuum, MaintenanceStep::Vacuum),
+        (options.analyze, MaintenanceStep::Analyze),
+        (options.checkpoint, MaintenanceStep::Checkpoint),
+    ]
+    .into_iter()
+    .filter_map(|(enabled, step)| enabled.then_some(step))
+    .collect()
+}
+
+/// Run the selected steps on the writer connection, calling `progress`
+/// before each.
+pub fn run(
+    conn: &Connection,
+    options: &MaintenanceOptions,
+    mut progress: impl FnMut(Progress),
+) -> Result<MaintenanceReport> {
+    let started = Instant::now();
+    let steps = selected(options);
+    let mut report = MaintenanceReport::default();
+
+    for (index, &step) in steps.iter().enumerate() {
+        let writes = !matches!(
+            step,
+            MaintenanceStep::IntegrityCheck | MaintenanceStep::FtsIntegrityCheck
+        );
+        if writes && !report.integrity_errors.is_empty() {
+            break;
+        }
+
+        progress(Progress {
+            step,
+            index,
+            total: steps.len(),
+        });
+
+        match step {
+            MaintenanceStep::IntegrityCheck => {
+                let (errors, fts_errors) = integrity_check(conn)?;
+                report.integrity_errors = errors;
+                if !options.fts_integrity_check {
+                    report.fts_errors = fts_errors;
+                }
+            }
+            MaintenanceStep::FtsIntegrityCheck => report.fts_errors = fts_integrity_check(conn),
+            MaintenanceStep::FtsRebuild => fts_command(conn, "rebuild")?,
+            MaintenanceStep::FtsOptimize => fts_command(conn, "optimize")?,
+            MaintenanceStep::Vacuum => report.bytes_freed = vacuum(conn)?,
+            MaintenanceStep::Analyze => conn.execute_batch("ANALYZE").context("Failed to analyze")?,
+            MaintenanceStep::Checkpoint => report.checkpoint = Some(checkpoint(conn)?),
+        }
+        report.steps.push(step);
+    }
+
+    report.duration_ms = started.elapsed().as_millis() as i64;
+    Ok(report)
+}
+
+/// Returns the problems found, split into those in the search indexes and
+/// the rest. A damaged search index can be rebuilt from the text it stores,
+/// so it doesn't stop the steps that write.
+fn integrity_check(conn: &Connection) -> Result<(Vec<String>, Vec<String>)> {
+    let messages = conn
+        .prepare("PRAGMA integrity_check")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to check database integrity")?;
+
+    let (fts_errors, errors) = messages
+        .into_iter()
+        .filter(|m| m != "ok")
+        .partition(|m| m.contains("FTS5 table"));
+    Ok((errors, fts_errors))
+}
+
+/// Corruption is reported by the FTS5 check as an error, so errors are
+/// collected rather than returned.
+fn fts_integrity_check(conn: &Connection) -> Vec<String> {
+    FTS_TABLES
+        .iter()
+        .filter_map(|table| {
+            conn.execute(
+                &format!("INSERT INTO {0} ({0}) VALUES ('integrity-check')", table),
+                [],
+            )
+            .err()
+            .map(|e| format!("{}: {}", table, e))
+        })
+        .collect()
+}
+
+fn fts_command(conn: &Connection, command: &str) -> Result<()> {
+    for table in FTS_TABLES {
+        conn.execute(
+            &format!("INSERT INTO {0} ({0}) VALUES ('{1}')", table, command),
+            [],
+        )
+        .with_context(|| format!("Failed to {} {}", command, table))?;
+    }
+    Ok(())
+}
+
+/// Returns the number of bytes released.
+pub(crate) fn vacuum(conn: &Connection) -> Result<i64> {
+    let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+    let pages_before: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+
+    let auto_vacuum: i64 = conn.query_row("PRAGMA auto_vacuum", [], |row| row.get(0))?;
+    if auto_vacuum == AUTO_VACUUM_INCREMENTAL {
+        // Each step frees one page
+        let mut stmt = conn.prepare("PRAGMA incremental_vacuum")?;
+        let mut rows = stmt.query([])?;
+        while rows.next().context("Failed to vacuum database")?.is_some() {}
+    } else {
+        // Changing the auto-vacuum mode only takes effect after a VACUUM
+        conn.execute_batch("PRAGMA auto_vacuum = INCREMENTAL; VACUUM;")
+            .context("Failed to vacuum database")?;
+    }
+
+    let pages_after: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+    Ok((pages_before - pages_after).max(0) * page_size)
+}
+
+fn checkpoint(conn: &Connection) -> Result<Checkpoint> {
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
+        Ok(Checkpoint {
+            busy: row.get::<_, i64>(0)? != 0,
+            wal_frames: row.get(1)?,
+            checkpointed_frames: row.get(2)?,
+        })
+    })
+    .context("Failed to checkpoint WAL")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selected() {
+        assert_eq!(
+            selected(&MaintenanceOptions::default()),
+            vec![
+                MaintenanceStep::IntegrityCheck,
+                MaintenanceStep::FtsIntegrityCheck,
+                MaintenanceStep::FtsOptimize,
+                MaintenanceStep::Vacuum,
+                MaintenanceStep::Analyze,
+                MaintenanceStep::Checkpoint,
+            ]
+        );
+
+        let options = MaintenanceOptions {
+            integrity_check: false,
+            fts_rebuild: true,
+            vacuum: false,
+            ..Default::default()
+        };
+        assert_eq!(selected(&options)[..2], [
+            MaintenanceStep::FtsIntegrityCheck,
+            MaintenanceStep::FtsRebuild
+        ]);
+    }
+
+    #[test]
+    fn test_step_codes() {
+        assert_eq!(MaintenanceStep::IntegrityCheck.code(), 0);
+        assert_eq!(MaintenanceStep::Checkpoint.code(), 6);
+    }
+}
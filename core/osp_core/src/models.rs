@@ -50,6 +50,26 @@ pub struct Item {
     pub created_at: i64,
     pub pinned: bool,
     pub tags: Vec<String>,
+    /// When the item was moved to the trash, `None` if it isn't trashed.
+    #[serde(default)]
+    pub deleted_at: Option<i64>,
+    /// User-defined title, searchable with `title:`.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// User-defined free-form note.
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// A previous revision of an item's content, recorded when it was edited.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemVersion {
+    pub item_id: i64,
+    /// Sequential per item, starting at 1 for the original content.
+    pub version: i64,
+    pub content_ref: String,
+    /// When this content was replaced by a newer one.
+    pub replaced_at: i64,
 }
 
 /// A new item to be inserted.
@@ -57,8 +77,10 @@ pub struct Item {
 pub struct NewItem {
     pub kind: ItemKind,
     pub content_ref: String,
+    #[serde(default)]
     pub source_app: Option<String>,
     pub created_at: i64,
+    #[serde(default)]
     pub tags: Vec<String>,
 }
 
@@ -72,6 +94,11 @@ impl NewItem {
             .join(" ")
     }
 
+    /// Key stored alongside the item and compared for deduplication.
+    pub fn dedupe_key(&self) -> String {
+        dedupe_key(self.kind, &self.content_ref)
+    }
+
     /// Compute a stable hash for deduplication.
     /// For text/rtf items, hash the normalized content.
     /// For image/file items, hash the content_ref path.
@@ -92,6 +119,17 @@ impl NewItem {
     }
 }
 
+/// Deduplication key for content of the given kind.
+///
+/// - For text/rtf: normalized content (whitespace-collapsed)
+/// - For image/file: exact content_ref path
+pub fn dedupe_key(kind: ItemKind, content_ref: &str) -> String {
+    match kind {
+        ItemKind::Text | ItemKind::Rtf => content_ref.split_whitespace().collect::<Vec<_>>().join(" "),
+        ItemKind::Image | ItemKind::File => content_ref.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -126,5 +164,11 @@ mod tests {
         };
         assert_eq!(item1.dedupe_hash(), item2.dedupe_hash());
     }
+
+    #[test]
+    fn test_dedupe_key() {
+        assert_eq!(dedupe_key(ItemKind::Text, "  a \n b "), "a b");
+        assert_eq!(dedupe_key(ItemKind::File, "/tmp/a  b"), "/tmp/a  b");
+    }
 }
 
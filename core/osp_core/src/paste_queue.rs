@@ -0,0 +1,153 @@
+//! Paste queue ("paste stack") for pasting several copied values in order.
+//!
+//! Entries live in the `paste_queue` table and the queue's options in
+//! `settings`, so both survive restarts. The mode decides which end
+//! [`pop`] takes from: the oldest entry (FIFO) or the newest (LIFO).
+//! Entries whose item is in the trash are skipped.
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::db::{Database, ITEM_COLUMNS, ITEM_COLUMN_COUNT};
+use crate::models::Item;
+
+const SETTINGS_KEY: &str = "paste_queue";
+
+/// Order in which queued items are pasted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueueMode {
+    /// First in, first out: paste in the order items were queued.
+    #[default]
+    Fifo,
+    /// Last in, first out: paste the most recently queued item first.
+    Lifo,
+}
+
+/// Paste queue options.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PasteQueue {
+    pub mode: QueueMode,
+    /// Whether the user is in paste queue mode.
+    pub active: bool,
+    /// While active, queue every newly captured item.
+    pub auto_enqueue: bool,
+}
+
+/// Load the queue options, or the defaults if never saved.
+pub fn options(conn: &Connection) -> Result<PasteQueue> {
+    Ok(Database::setting(conn, SETTINGS_KEY)?.unwrap_or_default())
+}
+
+/// Save the queue options.
+pub fn set_options(conn: &Connection, options: &PasteQueue) -> Result<()> {
+    Database::set_setting(conn, SETTINGS_KEY, options)
+}
+
+/// Append an item to the queue. The same item may be queued more than once.
+pub fn push(conn: &Connection, id: i64) -> Result<()> {
+    let live: bool = conn
+        .prepare_cached("SELECT EXISTS(SELECT 1 FROM items WHERE id = ?1 AND deleted_at IS NULL)")?
+        .query_row(params![id], |row| row.get(0))?;
+    if !live {
+        anyhow::bail!("Item not found");
+    }
+
+    conn.prepare_cached("INSERT INTO paste_queue (item_id) VALUES (?1)")?
+        .execute(params![id])
+        .context("Failed to queue item")?;
+
+    Ok(())
+}
+
+/// Queue newly captured items if the queue is active with auto-enqueue on.
+pub fn capture(conn: &Connection, ids: &[i64]) -> Result<()> {
+    let options = options(conn)?;
+    if !(options.active && options.auto_enqueue) {
+        return Ok(());
+    }
+
+    for &id in ids {
+        push(conn, id)?;
+    }
+
+    Ok(())
+}
+
+/// The next item to paste, without removing it.
+pub fn peek(conn: &Connection) -> Result<Option<Item>> {
+    Ok(next(conn)?.map(|(_, item)| item))
+}
+
+/// Remove and return the next item to paste.
+pub fn pop(conn: &Connection) -> Result<Option<Item>> {
+    let Some((seq, item)) = next(conn)? else {
+        return Ok(None);
+    };
+
+    conn.prepare_cached("DELETE FROM paste_queue WHERE seq = ?1")?
+        .execute(params![seq])
+        .context("Failed to dequeue item")?;
+
+    Ok(Some(item))
+}
+
+/// All queued items, in the order they will be pasted.
+pub fn items(conn: &Connection) -> Result<Vec<Item>> {
+    let mode = options(conn)?.mode;
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {}
+         FROM paste_queue q
+         INNER JOIN items i ON i.id = q.item_id
+         WHERE i.deleted_at IS NULL
+         ORDER BY q.seq {}",
+        ITEM_COLUMNS,
+        order(mode)
+    ))?;
+
+    let items = stmt
+        .query_map([], Database::row_to_item)?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to fetch paste queue")?;
+
+    Ok(items)
+}
+
+/// Remove every entry. Returns the number removed.
+pub fn clear(conn: &Connection) -> Result<usize> {
+    let rows = conn
+        .prepare_cached("DELETE FROM paste_queue")?
+        .execute([])
+        .context("Failed to clear paste queue")?;
+
+    Ok(rows)
+}
+
+fn next(conn: &Connection) -> Result<Option<(i64, Item)>> {
+    let mode = options(conn)?.mode;
+    let next = conn
+        .prepare_cached(&format!(
+            "SELECT {}, q.seq
+             FROM paste_queue q
+             INNER JOIN items i ON i.id = q.item_id
+             WHERE i.deleted_at IS NULL
+             ORDER BY q.seq {}
+             LIMIT 1",
+            ITEM_COLUMNS,
+            order(mode)
+        ))?
+        .query_row([], |row| Ok((row.get(ITEM_COLUMN_COUNT)?, Database::row_to_item(row)?)))
+        .optional()
+        .context("Failed to fetch paste queue")?;
+
+    Ok(next)
+}
+
+fn order(mode: QueueMode) -> &'static str {
+    match mode {
+        QueueMode::Fifo => "ASC",
+        QueueMode::Lifo => "DESC",
+    }
+}
@@ -0,0 +1,75 @@
+//! Read-only connection pool.
+//!
+//! SQLite in WAL mode allows any number of readers alongside a single
+//! writer, so searches get their own connections and never queue behind
+//! clipboard captures or bulk inserts.
+
+use anyhow::{Context, Result};
+use parking_lot::{Mutex, MutexGuard};
+use rusqlite::{Connection, OpenFlags};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::db::Database;
+
+/// Number of prepared statements cached per connection.
+pub const STATEMENT_CACHE_CAPACITY: usize = 64;
+
+/// Fixed-size pool of read-only connections.
+pub struct ReaderPool {
+    conns: Vec<Mutex<Connection>>,
+    next: AtomicUsize,
+}
+
+impl ReaderPool {
+    /// Open `size` read-only connections to an existing database.
+    ///
+    /// The database must already exist and be in WAL mode.
+    pub fn open(db_path: &Path, size: usize) -> Result<Self> {
+        let size = size.max(1);
+        let mut conns = Vec::with_capacity(size);
+
+        for _ in 0..size {
+            let conn = Connection::open_with_flags(
+                db_path,
+                OpenFlags::SQLITE_OPEN_READ_ONLY
+                    | OpenFlags::SQLITE_OPEN_NO_MUTEX
+                    | OpenFlags::SQLITE_OPEN_URI,
+            )
+            .with_context(|| format!("Failed to open reader connection at {:?}", db_path))?;
+
+            conn.execute_batch(
+                "PRAGMA mmap_size=268435456;
+                 PRAGMA cache_size=-20000;
+                 PRAGMA temp_store=MEMORY;",
+            )
+            .context("Failed to set reader pragmas")?;
+            conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+            Database::register_functions(&conn)?;
+
+            conns.push(Mutex::new(conn));
+        }
+
+        Ok(Self {
+            conns,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// Borrow a reader connection.
+    ///
+    /// Picks the first idle connection starting from a rotating offset and
+    /// only blocks when every reader is busy.
+    pub fn get(&self) -> MutexGuard<'_, Connection> {
+        let len = self.conns.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+
+        for i in 0..len {
+            if let Some(conn) = self.conns[(start + i) % len].try_lock() {
+                return conn;
+            }
+        }
+
+        self.conns[start % len].lock()
+    }
+}
@@ -0,0 +1,89 @@
+//! Relevance boosts: recency and learning from search clickthrough.
+//!
+//! Each time the user picks a result, [`record_selection`] adds 1 to the
+//! weight of the (normalized query text, item) pair; the learned boost is
+//! [`SELECTION_BOOST`] per unit of weight. Weights decay
+//! hyperbolically: a selection counts half after [`SELECTION_HALF_LIFE_MS`],
+//! a third after twice that, and so on. Decay is applied when reading, and
+//! folded into the stored weight whenever it is updated.
+//!
+//! For FTS queries the bm25 score (negative, lower is better) is scaled by
+//! `(1 + learned boost) * (1 + recency boost)`, so boosts reorder results
+//! of similar relevance without overriding e.g. title matches.
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+
+use crate::search::SearchQuery;
+
+/// Boost per recorded selection: one pick scales the score by 1.5.
+pub const SELECTION_BOOST: f64 = 0.5;
+
+/// Age at which a recorded selection counts half: 30 days.
+pub const SELECTION_HALF_LIFE_MS: i64 = 30 * 24 * 60 * 60 * 1000;
+
+/// Recency boost of item `i`: 0.5 when copied just now, 0.25 after a week,
+/// approaching 0 for old items.
+pub const RECENCY_BOOST: &str =
+    "(0.5 / (1.0 + (unixepoch('subsec') * 1000 - i.created_at) / 604800000.0))";
+
+/// Normalize query text for learned associations: lowercase, with
+/// whitespace trimmed and collapsed. `text` is what is left of the search
+/// box input once its inline operators are parsed out, so `deploy
+/// app:Terminal` and `deploy` share what they learn.
+pub fn normalize_query(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// SQL expression for the decayed learned boost of item `i` for the
+/// normalized query bound at `query_param`.
+pub fn learned_boost_sql(query_param: &str) -> String {
+    format!(
+        "(SELECT {:.1} * COALESCE(SUM(s.weight / (1.0 + (unixepoch('subsec') * 1000 - s.updated_at) / {}.0)), 0)
+          FROM search_selections s
+          WHERE s.query = {} AND s.item_id = i.id)",
+        SELECTION_BOOST, SELECTION_HALF_LIFE_MS, query_param
+    )
+}
+
+/// Record that the user picked `item_id` from the results for `query`,
+/// the search box input as passed to [`Core::search`](crate::Core::search).
+pub fn record_selection(conn: &Connection, query: &str, item_id: i64, now: i64) -> Result<()> {
+    let query = normalize_query(&SearchQuery::parse(query)?.text);
+    if query.is_empty() {
+        anyhow::bail!("Query has no text to learn from");
+    }
+
+    let live: bool = conn
+        .prepare_cached("SELECT EXISTS(SELECT 1 FROM items WHERE id = ?1 AND deleted_at IS NULL)")?
+        .query_row(params![item_id], |row| row.get(0))?;
+    if !live {
+        anyhow::bail!("Item not found");
+    }
+
+    conn.prepare_cached(&format!(
+        "INSERT INTO search_selections (query, item_id, weight, updated_at)
+         VALUES (?1, ?2, 1.0, ?3)
+         ON CONFLICT(query, item_id) DO UPDATE SET
+           weight = weight / (1.0 + MAX(excluded.updated_at - updated_at, 0) / {}.0) + 1.0,
+           updated_at = excluded.updated_at",
+        SELECTION_HALF_LIFE_MS
+    ))?
+    .execute(params![query, item_id, now])
+    .context("Failed to record selection")?;
+
+    Ok(())
+}
+
+/// Forget all learned associations. Returns the number removed.
+pub fn reset(conn: &Connection) -> Result<usize> {
+    let rows = conn
+        .prepare_cached("DELETE FROM search_selections")?
+        .execute([])
+        .context("Failed to reset learned ranking")?;
+
+    Ok(rows)
+}
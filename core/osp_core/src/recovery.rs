@@ -0,0 +1,260 @@
+//! Recovery of a corrupted database.
+//!
+//! The damaged file (with its WAL and shared-memory files) is renamed to
+//! `<name>.corrupt-<timestamp>` and a fresh database is created in its
+//! place. Rows are then copied over table by table in rowid order. When a
+//! read hits a damaged page, copying resumes at increasingly distant rowids
+//! until the B-tree can be entered past the damage, so one bad page only
+//! loses the rows stored on it. The search indexes are derived data and are
+//! rebuilt from the recovered items instead of being copied.
+
+use anyhow::{Context, Result};
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection, ErrorCode, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use time::macros::format_description;
+
+use crate::now_local;
+
+/// Most times copying a table resumes after a read error.
+const MAX_RESUMES: usize = 256;
+
+/// What was recovered from one table.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableRecovery {
+    pub table: String,
+    /// Rows copied into the new database.
+    pub recovered: i64,
+    /// Rows read but rejected by the new database, e.g. because the item
+    /// they belong to was lost.
+    pub dropped: i64,
+    /// Damaged ranges that could not be read. Their rows are lost.
+    pub errors: Vec<String>,
+}
+
+/// What happened when a corrupted database was recovered.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecoveryReport {
+    /// The corruption that was detected.
+    pub reason: String,
+    /// Where the damaged database file was moved.
+    pub moved_to: PathBuf,
+    /// Per table, in copy order.
+    pub tables: Vec<TableRecovery>,
+    /// Items copied into the new database.
+    pub items_recovered: i64,
+}
+
+/// Whether `err` was caused by a damaged database file.
+pub fn is_corruption(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<rusqlite::Error>(),
+            Some(rusqlite::Error::SqliteFailure(e, _))
+                if matches!(e.code, ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase)
+        )
+    })
+}
+
+/// Run `PRAGMA quick_check` and describe the first problem it finds.
+pub fn check(conn: &Connection) -> Result<Option<String>> {
+    let problems = conn
+        .prepare("PRAGMA quick_check")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to check database integrity")?;
+
+    Ok(problems.into_iter().find(|p| p != "ok"))
+}
+
+/// Move the damaged database at `db_path` aside, returning its new path.
+///
+/// The caller must have closed every connection to it.
+pub fn move_aside(db_path: &Path) -> Result<PathBuf> {
+    let stamp = now_local()
+        .format(format_description!(
+            "[year][month][day]-[hour][minute][second]"
+        ))
+        .context("Failed to format timestamp")?;
+
+    let mut moved_to = with_suffix(db_path, &format!(".corrupt-{}", stamp));
+    let mut n = 1;
+    while moved_to.exists() {
+        n += 1;
+        moved_to = with_suffix(db_path, &format!(".corrupt-{}-{}", stamp, n));
+    }
+
+    std::fs::rename(db_path, &moved_to)
+        .with_context(|| format!("Failed to move damaged database to {:?}", moved_to))?;
+    for suffix in ["-wal", "-shm"] {
+        let from = with_suffix(db_path, suffix);
+        if from.exists() {
+            std::fs::rename(&from, with_suffix(&moved_to, suffix))
+                .with_context(|| format!("Failed to move {:?}", from))?;
+        }
+    }
+
+    Ok(moved_to)
+}
+
+/// Copy what can be read from the damaged database at `report.moved_to`
+/// into the fresh database `conn`, then rebuild the search index.
+pub fn salvage(conn: &Connection, report: &mut RecoveryReport) -> Result<()> {
+    let tables = tables(conn)?;
+
+    let src = match Connection::open_with_flags(&report.moved_to, OpenFlags::SQLITE_OPEN_READ_ONLY) {
+        Ok(src) => src,
+        Err(e) => {
+            report.tables = tables
+                .into_iter()
+                .map(|table| TableRecovery {
+                    table,
+                    errors: vec![format!("Failed to open damaged database: {}", e)],
+                    ..Default::default()
+                })
+                .collect();
+            return Ok(());
+        }
+    };
+
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to begin transaction")?;
+    for table in tables {
+        let recovery = copy_table(&src, &tx, &table)?;
+        if table == "items" {
+            report.items_recovered = recovery.recovered;
+        }
+        report.tables.push(recovery);
+    }
+
+    // items_trigram is kept up to date by triggers; items_fts is not
+    tx.execute(
+        "INSERT INTO items_fts (item_id, text, title, note)
+         SELECT id, CASE WHEN kind IN ('text', 'rtf') THEN content_ref END, title, note
+         FROM items
+         WHERE kind IN ('text', 'rtf') OR title IS NOT NULL OR note IS NOT NULL",
+        [],
+    )
+    .context("Failed to rebuild search index")?;
+
+    tx.commit().context("Failed to commit transaction")?;
+    Ok(())
+}
+
+/// Tables of the new database that hold data, in creation order so tables
+/// are copied before the tables that reference them.
+fn tables(conn: &Connection) -> Result<Vec<String>> {
+    let tables = conn
+        .prepare(
+            "SELECT name FROM sqlite_schema
+             WHERE type = 'table'
+               AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\'
+               AND name NOT LIKE 'items\\_fts%' ESCAPE '\\'
+               AND name NOT LIKE 'items\\_trigram%' ESCAPE '\\'
+             ORDER BY rowid",
+        )?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()
+        .context("Failed to list tables")?;
+
+    Ok(tables)
+}
+
+fn columns(conn: &Connection, table: &str) -> rusqlite::Result<Vec<String>> {
+    conn.prepare(&format!("SELECT name FROM pragma_table_info('{}')", table))?
+        .query_map([], |row| row.get(0))?
+        .collect()
+}
+
+fn copy_table(src: &Connection, dst: &Connection, table: &str) -> Result<TableRecovery> {
+    let mut recovery = TableRecovery {
+        table: table.to_string(),
+        ..Default::default()
+    };
+
+    // Columns the damaged database has too, in case it was never migrated
+    let src_columns = match columns(src, table) {
+        Ok(columns) => columns,
+        Err(e) => {
+            recovery.errors.push(format!("Failed to read schema: {}", e));
+            return Ok(recovery);
+        }
+    };
+    let columns: Vec<String> = columns(dst, table)?
+        .into_iter()
+        .filter(|c| src_columns.contains(c))
+        .collect();
+    if columns.is_empty() {
+        return Ok(recovery);
+    }
+
+    let mut insert = dst.prepare(&format!(
+        "INSERT OR IGNORE INTO {} ({}) VALUES ({})",
+        table,
+        columns.join(", "),
+        vec!["?"; columns.len()].join(", ")
+    ))?;
+    let select = format!(
+        "SELECT rowid, {} FROM {} WHERE rowid >= ?1 ORDER BY rowid",
+        columns.join(", "),
+        table
+    );
+
+    // Rows are read from rowid `from` on. After a read error, reading
+    // resumes right after the last good row, then further and further out
+    // while the damage keeps getting in the way.
+    let mut from = i64::MIN;
+    let mut base = 0;
+    let mut skip: i64 = 1;
+    for _ in 0..MAX_RESUMES {
+        let mut last = None;
+        let result = (|| -> rusqlite::Result<()> {
+            let mut stmt = src.prepare(&select)?;
+            let mut rows = stmt.query([from])?;
+            while let Some(row) = rows.next()? {
+                last = Some(row.get::<_, i64>(0)?);
+                let values = (1..=columns.len())
+                    .map(|i| row.get::<_, Value>(i))
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                match insert.execute(params_from_iter(values)) {
+                    Ok(_) => recovery.recovered += 1,
+                    Err(_) => recovery.dropped += 1,
+                }
+            }
+            Ok(())
+        })();
+
+        let Err(e) = result else {
+            return Ok(recovery);
+        };
+
+        match last {
+            Some(rowid) => {
+                base = rowid;
+                skip = 1;
+            }
+            None if from != i64::MIN => skip = skip.saturating_mul(2),
+            None => {}
+        }
+        if last.is_some() || recovery.errors.is_empty() {
+            recovery.errors.push(format!("Rows after rowid {} are damaged: {}", base, e));
+        }
+
+        from = match base.checked_add(skip) {
+            Some(from) => from,
+            None => break,
+        };
+    }
+
+    Ok(recovery)
+}
+
+/// `path` with `suffix` appended to its file name.
+pub(crate) fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name: OsString = path.as_os_str().to_owned();
+    name.push(suffix);
+    name.into()
+}
@@ -0,0 +1,144 @@
+//! Regular expression search.
+//!
+//! Provides SQLite's `REGEXP` operator, backed by the `regex` crate. The
+//! pattern is compiled once per statement and cached by SQLite. Matching
+//! is linear in the text length, but a search still scans every item, so
+//! it runs under [`guard`]: patterns are size-limited, long texts are
+//! only matched up to [`MAX_TEXT_BYTES`], and the statement is interrupted
+//! after [`TIMEOUT`].
+
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+use rusqlite::functions::FunctionFlags;
+use rusqlite::Connection;
+use std::time::{Duration, Instant};
+
+/// Longest accepted pattern, in bytes.
+pub const MAX_PATTERN_BYTES: usize = 1024;
+/// Upper bound on the compiled size of a pattern.
+const MAX_COMPILED_BYTES: usize = 1 << 20;
+/// Only the start of longer texts is matched.
+pub const MAX_TEXT_BYTES: usize = 1 << 20;
+/// How long a regex search may run before it is interrupted.
+pub const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// SQLite virtual machine steps between timeout checks.
+const PROGRESS_STEPS: i32 = 1000;
+
+/// Compile a search pattern within the size limits.
+pub fn compile(pattern: &str) -> Result<Regex> {
+    if pattern.len() > MAX_PATTERN_BYTES {
+        anyhow::bail!("Regex is longer than {} bytes", MAX_PATTERN_BYTES);
+    }
+
+    RegexBuilder::new(pattern)
+        .size_limit(MAX_COMPILED_BYTES)
+        .dfa_size_limit(MAX_COMPILED_BYTES)
+        .build()
+        .context("Invalid regex")
+}
+
+/// Register the `regexp(pattern, text)` function behind `text REGEXP pattern`.
+pub fn register(conn: &Connection) -> Result<()> {
+    conn.create_scalar_function(
+        "regexp",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let regex = ctx.get_or_create_aux(0, |pattern| -> Result<Regex> {
+                compile(pattern.as_str()?)
+            })?;
+            let Ok(text) = ctx.get_raw(1).as_str() else {
+                return Ok(false);
+            };
+            Ok(regex.is_match(truncate(text)))
+        },
+    )
+    .context("Failed to register regexp")
+}
+
+/// Cut `text` to at most [`MAX_TEXT_BYTES`], on a character boundary.
+fn truncate(text: &str) -> &str {
+    if text.len() <= MAX_TEXT_BYTES {
+        return text;
+    }
+    let mut end = MAX_TEXT_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Run `f` with a [`TIMEOUT`] on the statements it executes on `conn`.
+///
+/// A statement still running at the deadline fails with "Regex search
+/// timed out".
+pub fn guard<T>(conn: &Connection, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    let deadline = Instant::now() + TIMEOUT;
+    conn.progress_handler(PROGRESS_STEPS, Some(move || Instant::now() > deadline));
+
+    let result = f();
+    conn.progress_handler(PROGRESS_STEPS, None::<fn() -> bool>);
+
+    match result {
+        Err(err) if is_interrupt(&err) => Err(err.context("Regex search timed out")),
+        result => result,
+    }
+}
+
+fn is_interrupt(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<rusqlite::Error>(),
+            Some(rusqlite::Error::SqliteFailure(e, _))
+                if e.code == rusqlite::ErrorCode::OperationInterrupted
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_limits() {
+        assert!(compile(r"\b[0-9a-f]{8}-[0-9a-f]{4}\b").is_ok());
+        assert!(compile("(unclosed").is_err());
+        assert!(compile(&"a".repeat(MAX_PATTERN_BYTES + 1)).is_err());
+
+        // Small to write, huge once compiled
+        assert!(compile(r"\w{1000}\w{1000}\w{1000}").is_err());
+    }
+
+    #[test]
+    fn test_guard_interrupts_long_statements() {
+        let conn = Connection::open_in_memory().unwrap();
+        let started = Instant::now();
+        let result = guard(&conn, || {
+            conn.query_row(
+                "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n)
+                 SELECT COUNT(*) FROM n",
+                [],
+                |row| row.get::<_, i64>(0),
+            )
+            .context("Failed to count")
+        });
+
+        let err = result.unwrap_err();
+        assert_eq!(err.to_string(), "Regex search timed out");
+        assert!(started.elapsed() < TIMEOUT * 2);
+
+        // The handler is removed afterwards
+        let one: i64 = conn.query_row("SELECT 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(one, 1);
+    }
+
+    #[test]
+    fn test_truncate() {
+        let text = "é".repeat(MAX_TEXT_BYTES);
+        let cut = truncate(&text);
+        assert!(cut.len() <= MAX_TEXT_BYTES);
+        assert!(cut.chars().all(|c| c == 'é'));
+        assert_eq!(truncate("short"), "short");
+    }
+}
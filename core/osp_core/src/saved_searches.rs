@@ -0,0 +1,142 @@
+//! Saved searches: named queries that act as live smart collections.
+//!
+//! The query is stored as JSON with its inline operators still in `text`,
+//! and parsed each time it runs, so relative dates like `after:7d` move
+//! with the clock and new items show up without any bookkeeping.
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::regexp;
+use crate::search::{SearchMode, SearchQuery};
+
+/// A named, stored search.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub id: i64,
+    pub name: String,
+    pub query: SearchQuery,
+    pub created_at: i64,
+}
+
+fn validate(name: &str, query: &SearchQuery) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        anyhow::bail!("Saved search name must not be empty");
+    }
+
+    match query.mode {
+        SearchMode::Standard | SearchMode::Fuzzy => {
+            query.clone().parse_operators()?;
+        }
+        SearchMode::Regex => {
+            regexp::compile(&query.text)?;
+        }
+    }
+
+    Ok(name.to_string())
+}
+
+/// Save a query under a name and return its ID. Names are unique, ignoring
+/// case. Fails if the query doesn't parse.
+pub fn create(conn: &Connection, name: &str, query: &SearchQuery, created_at: i64) -> Result<i64> {
+    let name = validate(name, query)?;
+    ensure_name_free(conn, &name, None)?;
+
+    conn.prepare_cached("INSERT INTO saved_searches (name, query, created_at) VALUES (?1, ?2, ?3)")?
+        .execute(params![name, serde_json::to_string(query)?, created_at])
+        .context("Failed to save search")?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+/// Replace the name and query of a saved search.
+pub fn update(conn: &Connection, id: i64, name: &str, query: &SearchQuery) -> Result<()> {
+    let name = validate(name, query)?;
+    ensure_name_free(conn, &name, Some(id))?;
+
+    let rows = conn
+        .prepare_cached("UPDATE saved_searches SET name = ?1, query = ?2 WHERE id = ?3")?
+        .execute(params![name, serde_json::to_string(query)?, id])
+        .context("Failed to update saved search")?;
+
+    if rows == 0 {
+        anyhow::bail!("Saved search not found");
+    }
+
+    Ok(())
+}
+
+/// Delete a saved search.
+pub fn delete(conn: &Connection, id: i64) -> Result<()> {
+    let rows = conn
+        .prepare_cached("DELETE FROM saved_searches WHERE id = ?1")?
+        .execute(params![id])
+        .context("Failed to delete saved search")?;
+
+    if rows == 0 {
+        anyhow::bail!("Saved search not found");
+    }
+
+    Ok(())
+}
+
+/// List all saved searches, ordered by name.
+pub fn list(conn: &Connection) -> Result<Vec<SavedSearch>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, name, query, created_at FROM saved_searches ORDER BY name",
+    )?;
+
+    let searches = stmt
+        .query_map([], row_to_saved_search)?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to fetch saved searches")?;
+
+    Ok(searches)
+}
+
+/// Look up a saved search by ID.
+pub fn get(conn: &Connection, id: i64) -> Result<SavedSearch> {
+    conn.prepare_cached("SELECT id, name, query, created_at FROM saved_searches WHERE id = ?1")?
+        .query_row(params![id], row_to_saved_search)
+        .optional()
+        .context("Failed to fetch saved search")?
+        .context("Saved search not found")
+}
+
+/// The query to run for a page of results (0-based), operators still
+/// unparsed.
+pub fn page_query(search: &SavedSearch, page: u32) -> SearchQuery {
+    let mut query = search.query.clone();
+    query.offset = query.offset.saturating_add(page.saturating_mul(query.limit));
+    query
+}
+
+fn ensure_name_free(conn: &Connection, name: &str, except: Option<i64>) -> Result<()> {
+    let existing: Option<i64> = conn
+        .prepare_cached("SELECT id FROM saved_searches WHERE name = ?1")?
+        .query_row(params![name], |row| row.get(0))
+        .optional()?;
+
+    match existing {
+        Some(other) if Some(other) != except => {
+            anyhow::bail!("A saved search named {:?} already exists", name)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn row_to_saved_search(row: &rusqlite::Row) -> rusqlite::Result<SavedSearch> {
+    let query_json: String = row.get(2)?;
+    let query = serde_json::from_str(&query_json).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+
+    Ok(SavedSearch {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        query,
+        created_at: row.get(3)?,
+    })
+}
@@ -1,66 +1,518 @@
 //! Full-text search implementation using SQLite FTS5.
 //!
-//! This module provides helper functions for search functionality,
-//! though the main search logic is currently in db.rs for simplicity.
+//! Queries of 3 characters or more go through `items_fts`, which matches
+//! whole words and prefixes. Two cases go through the trigram index
+//! `items_trigram` instead: CJK text, which `unicode61` can't split into
+//! words, and substring queries, written with a leading `*` (`*config`) or
+//! retried automatically when a word search finds nothing. Shorter queries
+//! fall back to `LIKE`, which FTS5 tokens can't serve well. Fuzzy and regex
+//! searches are separate modes (see [`SearchMode`]). Statements are
+//! assembled from the filters in use, with values always bound as
+//! parameters so the statement cache still applies.
 
 use anyhow::{Context, Result};
-use rusqlite::{params, Connection};
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
 
-use crate::models::Item;
+use crate::db::{Database, ITEM_COLUMNS};
+use crate::models::{Item, ItemKind};
+use crate::{fuzzy, ranking, regexp, usage};
 
-#[allow(dead_code)]
-fn search_with_like(conn: &Connection, query: &str, limit: u32) -> Result<Vec<Item>> {
-    let pattern = format!("%{}%", query);
-    let mut stmt = conn.prepare(
-        "SELECT id, kind, content_ref, source_app, created_at, pinned, tags
-         FROM items
-         WHERE content_ref LIKE ?1
-         ORDER BY created_at DESC
-         LIMIT ?2",
-    )?;
+mod parse;
 
-    let items = stmt
-        .query_map(params![pattern, limit], row_to_item)?
-        .collect::<Result<Vec<_>, _>>()
-        .context("Failed to fetch LIKE search results")?;
+pub use parse::ParseError;
 
-    Ok(items)
+/// FTS5 ranking with per-column weights, in `items_fts` column order:
+/// item_id (unindexed), text, title, note.
+///
+/// A match in a user-given title counts far more than one in the content.
+pub const FTS_RANK: &str = "bm25(items_fts, 0.0, 1.0, 10.0, 2.0)";
+
+/// [`FTS_RANK`] for `items_trigram`, whose columns are text, title, note.
+const TRIGRAM_RANK: &str = "bm25(items_trigram, 1.0, 10.0, 2.0)";
+
+/// Shortest text the FTS indexes can match; trigrams need 3 characters.
+const MIN_INDEXED_CHARS: usize = 3;
+
+/// Result order for [`SearchQuery`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// Best match first, boosted by recency and by past selections for the
+    /// same query (see [`Core::record_selection`]). Short queries only use
+    /// the learned boost, then newest first.
+    ///
+    /// [`Core::record_selection`]: crate::Core::record_selection
+    #[default]
+    Relevance,
+    /// Newest first.
+    Recent,
+    /// Pinned items first, then by frecency (see [`Core::record_use`]),
+    /// then newest first. Items never pasted have a frecency of 0, so
+    /// within each group they follow used items in recency order.
+    ///
+    /// [`Core::record_use`]: crate::Core::record_use
+    Frecency,
+}
+
+/// How [`SearchQuery::text`] is matched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    /// FTS5 word matching, with `LIKE` for queries under 3 characters.
+    #[default]
+    Standard,
+    /// Typo-tolerant substring and subsequence matching over all items,
+    /// ordered by match score. [`SearchQuery::sort`] does not apply.
+    Fuzzy,
+    /// The text is a regular expression (`regex` crate syntax) matched
+    /// against content, title and note. Use `(?i)` to ignore case.
+    Regex,
+}
+
+/// A search request with optional filters.
+///
+/// Filters combine with AND. Within `kinds` and `apps` any value may
+/// match; every entry of `tags` and none of `exclude` must.
+///
+/// # Example
+///
+/// ```
+/// use osp_core::SearchQuery;
+///
+/// let query = SearchQuery {
+///     collection: Some(3),
+///     ..SearchQuery::new("deploy", 20)
+/// };
+/// assert_eq!(query.limit, 20);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchQuery {
+    pub text: String,
+    /// Maximum number of results, [`DEFAULT_LIMIT`] by default.
+    pub limit: u32,
+    /// Number of results to skip, for paging.
+    pub offset: u32,
+    /// Only return items in this collection.
+    pub collection: Option<i64>,
+    pub sort: SortOrder,
+    pub mode: SearchMode,
+    /// Only return items of these kinds.
+    pub kinds: Vec<ItemKind>,
+    /// Only return items whose source app contains one of these, ignoring
+    /// case.
+    pub apps: Vec<String>,
+    /// Only return items with all of these tags, ignoring case.
+    pub tags: Vec<String>,
+    pub pinned: Option<bool>,
+    /// Only return items created at or after this time (ms since epoch).
+    pub after: Option<i64>,
+    /// Only return items created before this time (ms since epoch).
+    pub before: Option<i64>,
+    /// Skip items whose content, title or note contains one of these.
+    pub exclude: Vec<String>,
+}
+
+/// Result limit of a [`SearchQuery`] that doesn't set one.
+pub const DEFAULT_LIMIT: u32 = 100;
+
+impl Default for SearchQuery {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            limit: DEFAULT_LIMIT,
+            offset: 0,
+            collection: None,
+            sort: SortOrder::default(),
+            mode: SearchMode::default(),
+            kinds: vec![],
+            apps: vec![],
+            tags: vec![],
+            pinned: None,
+            after: None,
+            before: None,
+            exclude: vec![],
+        }
+    }
+}
+
+impl SearchQuery {
+    pub fn new(text: impl Into<String>, limit: u32) -> Self {
+        Self {
+            text: text.into(),
+            limit,
+            ..Self::default()
+        }
+    }
+
+    /// A regular expression search, e.g. for all copied UUIDs:
+    ///
+    /// ```
+    /// use osp_core::{SearchMode, SearchQuery};
+    ///
+    /// let query = SearchQuery::regex(r"(?i)\b[0-9a-f]{8}(-[0-9a-f]{4}){3}-[0-9a-f]{12}\b");
+    /// assert_eq!(query.mode, SearchMode::Regex);
+    /// ```
+    pub fn regex(pattern: impl Into<String>) -> Self {
+        Self {
+            text: pattern.into(),
+            mode: SearchMode::Regex,
+            ..Self::default()
+        }
+    }
+}
+
+/// Search items using FTS5, or LIKE for short queries.
+///
+/// FTS5 column filters work as-is, e.g. `title:deploy` only matches titles.
+/// Inline operators such as `kind:image` must already have been moved into
+/// the query's filters by [`SearchQuery::parse`].
+pub fn search(conn: &Connection, query: &SearchQuery) -> Result<Vec<Item>> {
+    if query.mode == SearchMode::Fuzzy {
+        let mut params = Params::default();
+        let conditions = filters(query, &mut params);
+        return fuzzy::search(conn, query, &conditions, params);
+    }
+
+    if query.mode == SearchMode::Regex {
+        // Report a bad pattern as such, not as a failed statement
+        regexp::compile(&query.text)?;
+        return regexp::guard(conn, || run(conn, query, Plan::Regex));
+    }
+
+    run(conn, query, standard_plan(conn, query)?)
+}
+
+/// Index for a standard search. A word search that matches nothing at all,
+/// whatever the page, is run as a substring search instead: "config" is
+/// not a word of "kubeconfig".
+fn standard_plan(conn: &Connection, query: &SearchQuery) -> Result<Plan> {
+    match plan(&query.text) {
+        Plan::Fts if !trigram_words(&query.text).is_empty() => {
+            let fts = matches(query, Plan::Fts);
+            let found: bool = conn
+                .prepare_cached(&format!("SELECT EXISTS(SELECT 1 {})", fts.clauses()))?
+                .query_row(params_from_iter(fts.params.0.iter()), |row| row.get(0))
+                .context("Failed to run FTS search")?;
+            Ok(if found { Plan::Fts } else { Plan::Trigram })
+        }
+        plan => Ok(plan),
+    }
+}
+
+/// Index used to match the text of a standard search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Plan {
+    /// Words and prefixes, through `items_fts`.
+    Fts,
+    /// Substrings, through `items_trigram`.
+    Trigram,
+    /// `LIKE` on every row, for text too short for either index.
+    Like,
+    /// `REGEXP` on every row.
+    Regex,
+}
+
+fn plan(text: &str) -> Plan {
+    let substring = text.trim_start().starts_with('*') || text.chars().any(is_cjk);
+
+    if text.chars().count() < MIN_INDEXED_CHARS {
+        Plan::Like
+    } else if substring && trigram_words(text).is_empty() {
+        // e.g. two CJK words of two characters each
+        Plan::Like
+    } else if substring {
+        Plan::Trigram
+    } else {
+        Plan::Fts
+    }
+}
+
+/// Whitespace-separated words of `text` without `*` wildcards.
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split_whitespace()
+        .map(|word| word.trim_matches('*'))
+        .filter(|word| !word.is_empty())
+}
+
+/// Words long enough to look up in `items_trigram`.
+fn trigram_words(text: &str) -> Vec<&str> {
+    words(text)
+        .filter(|word| word.chars().count() >= MIN_INDEXED_CHARS)
+        .collect()
 }
 
-#[allow(dead_code)]
-fn search_with_fts(conn: &Connection, query: &str, limit: u32) -> Result<Vec<Item>> {
-    let mut stmt = conn.prepare(
-        "SELECT i.id, i.kind, i.content_ref, i.source_app, i.created_at, i.pinned, i.tags
+/// Whether `c` is Chinese, Japanese or Korean, which `unicode61` doesn't
+/// split into words.
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{11FF}'       // Hangul Jamo
+        | '\u{3040}'..='\u{30FF}'     // Hiragana, Katakana
+        | '\u{3130}'..='\u{318F}'     // Hangul Compatibility Jamo
+        | '\u{3400}'..='\u{4DBF}'     // CJK Extension A
+        | '\u{4E00}'..='\u{9FFF}'     // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}'     // Hangul Syllables
+        | '\u{F900}'..='\u{FAFF}'     // CJK Compatibility Ideographs
+        | '\u{FF66}'..='\u{FF9F}'     // Halfwidth Katakana
+        | '\u{20000}'..='\u{2FA1F}'   // CJK Extensions B-F, supplement
+    )
+}
+
+/// The items a query matches, as parts of a statement on `items i`.
+pub(crate) struct Matches {
+    pub(crate) join: &'static str,
+    /// Conditions on `i`, all of which must hold.
+    pub(crate) conditions: Vec<String>,
+    pub(crate) params: Params,
+    /// Text match score, lower is better.
+    rank: Option<&'static str>,
+}
+
+impl Matches {
+    /// `FROM` and `WHERE` clauses selecting the matched items.
+    pub(crate) fn clauses(&self) -> String {
+        format!(
+            "FROM items i {} WHERE {}",
+            self.join,
+            self.conditions.join(" AND ")
+        )
+    }
+}
+
+/// The items `query` matches, chosen the way [`search`] would.
+///
+/// Fuzzy queries have no SQL form, so they are run and their results
+/// matched by ID.
+pub(crate) fn matching(conn: &Connection, query: &SearchQuery) -> Result<Matches> {
+    match query.mode {
+        SearchMode::Fuzzy => {
+            let all = SearchQuery {
+                limit: u32::MAX,
+                offset: 0,
+                ..query.clone()
+            };
+            let ids: Vec<i64> = search(conn, &all)?.into_iter().map(|item| item.id).collect();
+
+            let mut params = Params::default();
+            let ids = params.bind(serde_json::to_string(&ids)?);
+            Ok(Matches {
+                join: "",
+                conditions: vec![format!("i.id IN (SELECT value FROM json_each({}))", ids)],
+                params,
+                rank: None,
+            })
+        }
+        SearchMode::Regex => {
+            regexp::compile(&query.text)?;
+            Ok(matches(query, Plan::Regex))
+        }
+        SearchMode::Standard => Ok(matches(query, standard_plan(conn, query)?)),
+    }
+}
+
+fn matches(query: &SearchQuery, plan: Plan) -> Matches {
+    let mut params = Params::default();
+    let mut conditions = filters(query, &mut params);
+
+    let (join, rank) = match plan {
+        Plan::Fts => {
+            conditions.push(format!("items_fts MATCH {}", params.bind(query.text.clone())));
+            ("INNER JOIN items_fts fts ON fts.item_id = i.id", Some(FTS_RANK))
+        }
+        Plan::Trigram => {
+            // Each word is a phrase, so FTS5 syntax is matched literally
+            let phrases: Vec<String> = trigram_words(&query.text)
+                .into_iter()
+                .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+                .collect();
+            conditions.push(format!(
+                "items_trigram MATCH {}",
+                params.bind(phrases.join(" "))
+            ));
+
+            // Words too short for trigrams still have to match
+            for word in words(&query.text).filter(|w| w.chars().count() < MIN_INDEXED_CHARS) {
+                let pattern = params.bind(format!("%{}%", word));
+                conditions.push(format!(
+                    "(i.content_ref LIKE {0} OR i.title LIKE {0} OR i.note LIKE {0})",
+                    pattern
+                ));
+            }
+
+            ("INNER JOIN items_trigram t ON t.rowid = i.id", Some(TRIGRAM_RANK))
+        }
+        Plan::Like => {
+            let pattern = params.bind(format!("%{}%", query.text.trim_matches('*')));
+            conditions.push(format!(
+                "(i.content_ref LIKE {0} OR i.title LIKE {0} OR i.note LIKE {0})",
+                pattern
+            ));
+            ("", None)
+        }
+        Plan::Regex => {
+            let pattern = params.bind(query.text.clone());
+            conditions.push(format!(
+                "(i.content_ref REGEXP {0} OR i.title REGEXP {0} OR i.note REGEXP {0})",
+                pattern
+            ));
+            ("", None)
+        }
+    };
+
+    Matches {
+        join,
+        conditions,
+        params,
+        rank,
+    }
+}
+
+fn run(conn: &Connection, query: &SearchQuery, plan: Plan) -> Result<Vec<Item>> {
+    let Matches {
+        join,
+        conditions,
+        mut params,
+        rank,
+    } = matches(query, plan);
+
+    let order = match query.sort {
+        SortOrder::Relevance => {
+            let learned = ranking::learned_boost_sql(
+                &params.bind(ranking::normalize_query(&query.text)),
+            );
+            match rank {
+                Some(rank) => format!(
+                    "{} * (1.0 + {}) * (1.0 + {}), i.created_at DESC",
+                    rank,
+                    learned,
+                    ranking::RECENCY_BOOST
+                ),
+                None => format!("{} DESC, i.created_at DESC", learned),
+            }
+        }
+        SortOrder::Recent => "i.created_at DESC".to_string(),
+        SortOrder::Frecency => format!(
+            "i.pinned DESC, {} DESC, i.created_at DESC",
+            usage::frecency_sql()
+        ),
+    };
+
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {}
          FROM items i
-         INNER JOIN items_fts fts ON fts.item_id = i.id
-         WHERE items_fts MATCH ?1
-         ORDER BY fts.rank, i.created_at DESC
-         LIMIT ?2",
-    )?;
+         {}
+         WHERE {}
+         ORDER BY {}
+         LIMIT {} OFFSET {}",
+        ITEM_COLUMNS,
+        join,
+        conditions.join(" AND "),
+        order,
+        params.bind(query.limit),
+        params.bind(query.offset)
+    ))?;
 
     let items = stmt
-        .query_map(params![query, limit], row_to_item)?
+        .query_map(params_from_iter(params.0), Database::row_to_item)?
         .collect::<Result<Vec<_>, _>>()
-        .context("Failed to fetch FTS search results")?;
+        .with_context(|| match plan {
+            Plan::Fts => "Failed to fetch FTS search results",
+            Plan::Trigram => "Failed to fetch substring search results",
+            Plan::Like => "Failed to fetch search results",
+            Plan::Regex => "Failed to fetch regex search results",
+        })?;
 
     Ok(items)
 }
 
-/// Helper to convert a row to an Item.
-#[allow(dead_code)]
-fn row_to_item(row: &rusqlite::Row) -> rusqlite::Result<Item> {
-    let kind_str: String = row.get(1)?;
-    let tags_json: String = row.get(6)?;
-    let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+/// Conditions on `i` shared by all search modes: not trashed, plus the
+/// query's filters.
+fn filters(query: &SearchQuery, params: &mut Params) -> Vec<String> {
+    let mut conditions = vec!["i.deleted_at IS NULL".to_string()];
+
+    if let Some(collection) = query.collection {
+        conditions.push(format!(
+            "i.id IN (SELECT item_id FROM collection_items WHERE collection_id = {})",
+            params.bind(collection)
+        ));
+    }
+
+    if !query.kinds.is_empty() {
+        let kinds: Vec<String> = query
+            .kinds
+            .iter()
+            .map(|kind| params.bind(kind.as_str().to_string()))
+            .collect();
+        conditions.push(format!("i.kind IN ({})", kinds.join(", ")));
+    }
+
+    if !query.apps.is_empty() {
+        let apps: Vec<String> = query
+            .apps
+            .iter()
+            .map(|app| format!("i.source_app LIKE {}", params.bind(format!("%{}%", app))))
+            .collect();
+        conditions.push(format!("({})", apps.join(" OR ")));
+    }
+
+    for tag in &query.tags {
+        conditions.push(format!(
+            "EXISTS (SELECT 1 FROM json_each(i.tags) WHERE value = {} COLLATE NOCASE)",
+            params.bind(tag.clone())
+        ));
+    }
+
+    if let Some(pinned) = query.pinned {
+        conditions.push(format!("i.pinned = {}", params.bind(pinned)));
+    }
+
+    if let Some(after) = query.after {
+        conditions.push(format!("i.created_at >= {}", params.bind(after)));
+    }
 
-    Ok(Item {
-        id: row.get(0)?,
-        kind: kind_str.parse().unwrap(),
-        content_ref: row.get(2)?,
-        source_app: row.get(3)?,
-        created_at: row.get(4)?,
-        pinned: row.get::<_, i32>(5)? != 0,
-        tags,
-    })
+    if let Some(before) = query.before {
+        conditions.push(format!("i.created_at < {}", params.bind(before)));
+    }
+
+    for text in &query.exclude {
+        conditions.push(format!(
+            "NOT (i.content_ref LIKE {0} OR ifnull(i.title, '') LIKE {0} OR ifnull(i.note, '') LIKE {0})",
+            params.bind(format!("%{}%", text))
+        ));
+    }
+
+    conditions
 }
 
+/// Positional parameters for a statement assembled at runtime.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Params(pub(crate) Vec<Value>);
+
+impl Params {
+    /// Add a value and return its placeholder.
+    pub(crate) fn bind(&mut self, value: impl Into<Value>) -> String {
+        self.0.push(value.into());
+        format!("?{}", self.0.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan() {
+        assert_eq!(plan("ls"), Plan::Like);
+        assert_eq!(plan("kubectl"), Plan::Fts);
+        assert_eq!(plan("title:deploy"), Plan::Fts);
+        assert_eq!(plan("*config"), Plan::Trigram);
+        assert_eq!(plan("*ab"), Plan::Like);
+        assert_eq!(plan("东京塔"), Plan::Trigram);
+        assert_eq!(plan("東京"), Plan::Like);
+        assert_eq!(plan("東京 駅"), Plan::Like);
+        assert_eq!(plan("서울특별시"), Plan::Trigram);
+        assert_eq!(plan("カタカナ test"), Plan::Trigram);
+    }
+}
@@ -0,0 +1,403 @@
+//! Inline query operators typed into the search box.
+//!
+//! `kind:image app:Safari tag:work pinned:yes after:2025-01-01
+//! before:yesterday -draft "exact phrase"` becomes a [`SearchQuery`] whose
+//! filters hold the operators and whose text keeps the remaining words and
+//! phrases for full-text search. Words with any other `name:` prefix, such
+//! as `title:deploy`, are left to FTS5.
+//!
+//! Dates are `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM` in local time, `today`,
+//! `yesterday`, or an age such as `12h`, `3d` or `2w`. A day means its
+//! start, so `before:today` excludes today and `after:yesterday` includes
+//! yesterday.
+
+use serde::Serialize;
+use time::macros::format_description;
+use time::{Date, Duration, OffsetDateTime, PrimitiveDateTime, Time};
+
+use super::{SearchMode, SearchQuery};
+use crate::models::ItemKind;
+
+/// A query that can't be parsed, with the span to highlight.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[error("{message} (at {start})")]
+pub struct ParseError {
+    pub message: String,
+    /// Start of the offending text, counted in characters.
+    pub start: usize,
+    /// End of the offending text (exclusive), counted in characters.
+    pub end: usize,
+}
+
+impl SearchQuery {
+    /// Parse search box input with inline operators.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use osp_core::{ItemKind, SearchQuery};
+    ///
+    /// let query = SearchQuery::parse(r#"kind:image app:Safari "release notes""#).unwrap();
+    /// assert_eq!(query.kinds, [ItemKind::Image]);
+    /// assert_eq!(query.apps, ["Safari"]);
+    /// assert_eq!(query.text, "\"release notes\"");
+    ///
+    /// let err = SearchQuery::parse("pinned:maybe").unwrap_err();
+    /// assert_eq!((err.start, err.end), (7, 12));
+    /// ```
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        SearchQuery::new(input, super::DEFAULT_LIMIT).parse_operators()
+    }
+
+    /// Move the inline operators in `text` into the filters, keeping the
+    /// filters already set. Operators taking one value (`pinned:`,
+    /// `before:`, `after:`) replace it.
+    ///
+    /// Stored queries keep their operators in `text` and are parsed when
+    /// run, so `after:7d` always means the last seven days. Regex queries
+    /// are returned as they are: their text is the pattern.
+    pub fn parse_operators(self) -> Result<Self, ParseError> {
+        if self.mode == SearchMode::Regex {
+            return Ok(self);
+        }
+        parse_at(self, crate::now_local())
+    }
+}
+
+/// [`SearchQuery::parse_operators`] with relative dates resolved against
+/// `now`.
+pub(crate) fn parse_at(mut query: SearchQuery, now: OffsetDateTime) -> Result<SearchQuery, ParseError> {
+    let input = std::mem::take(&mut query.text);
+    let mut text = Vec::new();
+    let mut lexer = Lexer {
+        chars: input.chars().collect(),
+        pos: 0,
+    };
+
+    while let Some(token) = lexer.next()? {
+        match token {
+            Token::Word(word) => text.push(word),
+            Token::Phrase(phrase) => text.push(format!("\"{}\"", phrase)),
+            Token::Exclude(value) => query.exclude.push(value),
+            Token::Operator { name, value } => {
+                apply(&mut query, &name, &value.text, now).map_err(|message| ParseError {
+                    message,
+                    start: value.start,
+                    end: value.end,
+                })?
+            }
+        }
+    }
+
+    query.text = text.join(" ");
+    Ok(query)
+}
+
+const OPERATORS: [&str; 6] = ["kind", "app", "tag", "pinned", "before", "after"];
+
+fn apply(query: &mut SearchQuery, name: &str, value: &str, now: OffsetDateTime) -> Result<(), String> {
+    if value.is_empty() {
+        return Err(format!("Missing value for {}:", name));
+    }
+
+    match name {
+        "kind" => {
+            let kind = value
+                .to_lowercase()
+                .parse::<ItemKind>()
+                .map_err(|_| format!("Unknown kind {:?}, expected text, rtf, image or file", value))?;
+            query.kinds.push(kind);
+        }
+        "app" => query.apps.push(value.to_string()),
+        "tag" => query.tags.push(value.to_string()),
+        "pinned" => {
+            query.pinned = Some(match value.to_lowercase().as_str() {
+                "yes" | "true" | "1" => true,
+                "no" | "false" | "0" => false,
+                _ => return Err(format!("Expected yes or no, not {:?}", value)),
+            });
+        }
+        "before" => query.before = Some(parse_date(value, now)?),
+        "after" => query.after = Some(parse_date(value, now)?),
+        _ => unreachable!("not an operator: {}", name),
+    }
+
+    Ok(())
+}
+
+/// Parse a date operator value into ms since epoch.
+fn parse_date(value: &str, now: OffsetDateTime) -> Result<i64, String> {
+    let invalid = || {
+        format!(
+            "Invalid date {:?}, expected e.g. 2025-01-31, yesterday or 3d",
+            value
+        )
+    };
+    let out_of_range = || format!("Date {:?} is out of range", value);
+    let start_of = |date: Date| PrimitiveDateTime::new(date, Time::MIDNIGHT).assume_offset(now.offset());
+
+    let at = match value.to_lowercase().as_str() {
+        "today" => start_of(now.date()),
+        "yesterday" => start_of(now.date().previous_day().ok_or_else(invalid)?),
+        lower => {
+            if let Some(age) = parse_age(lower) {
+                age.and_then(|age| now.checked_sub(age))
+                    .ok_or_else(out_of_range)?
+            } else if let Ok(date) = Date::parse(value, format_description!("[year]-[month]-[day]")) {
+                start_of(date)
+            } else if let Ok(datetime) = PrimitiveDateTime::parse(
+                value,
+                format_description!("[year]-[month]-[day]T[hour]:[minute]"),
+            ) {
+                datetime.assume_offset(now.offset())
+            } else {
+                return Err(invalid());
+            }
+        }
+    };
+
+    Ok((at.unix_timestamp_nanos() / 1_000_000) as i64)
+}
+
+/// Parse an age like `12h`, `3d` or `2w`. `Some(None)` if it is too long
+/// to represent.
+fn parse_age(value: &str) -> Option<Option<Duration>> {
+    let unit = value.chars().last()?;
+    let seconds_per_unit = match unit {
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    let count = &value[..value.len() - unit.len_utf8()];
+    if count.is_empty() || !count.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    Some(
+        count
+            .parse::<i64>()
+            .ok()
+            .and_then(|count| count.checked_mul(seconds_per_unit))
+            .map(Duration::seconds),
+    )
+}
+
+enum Token {
+    Word(String),
+    Phrase(String),
+    Exclude(String),
+    Operator { name: String, value: Span },
+}
+
+struct Span {
+    text: String,
+    start: usize,
+    end: usize,
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Lexer {
+    fn next(&mut self) -> Result<Option<Token>, ParseError> {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+        let Some(c) = self.peek() else {
+            return Ok(None);
+        };
+
+        if c == '"' {
+            let phrase = self.quoted()?;
+            return Ok(Some(Token::Phrase(phrase.text)));
+        }
+
+        let start = self.pos;
+        let next_is_text = self
+            .chars
+            .get(self.pos + 1)
+            .is_some_and(|c| !c.is_whitespace());
+        if c == '-' && next_is_text {
+            self.pos += 1;
+            let value = if self.peek() == Some('"') {
+                self.quoted()?
+            } else {
+                self.word()
+            };
+            if value.text.is_empty() {
+                return Err(ParseError {
+                    message: "Nothing to exclude".to_string(),
+                    start,
+                    end: value.end,
+                });
+            }
+            return Ok(Some(Token::Exclude(value.text)));
+        }
+
+        let word = self.word();
+        if let Some((name, rest)) = word.text.split_once(':') {
+            let name = name.to_lowercase();
+            if OPERATORS.contains(&name.as_str()) {
+                let value_start = start + name.chars().count() + 1;
+                let value = if rest.starts_with('"') {
+                    // The value may contain spaces: rescan it as a quoted string
+                    self.pos = value_start;
+                    self.quoted()?
+                } else {
+                    Span {
+                        text: rest.to_string(),
+                        start: value_start,
+                        end: word.end,
+                    }
+                };
+                return Ok(Some(Token::Operator { name, value }));
+            }
+        }
+
+        Ok(Some(Token::Word(word.text)))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    /// Read up to the next whitespace.
+    fn word(&mut self) -> Span {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| !c.is_whitespace()) {
+            self.pos += 1;
+        }
+        Span {
+            text: self.chars[start..self.pos].iter().collect(),
+            start,
+            end: self.pos,
+        }
+    }
+
+    /// Read a string in double quotes, starting at the opening quote.
+    fn quoted(&mut self) -> Result<Span, ParseError> {
+        let start = self.pos;
+        self.pos += 1;
+        let Some(len) = self.chars[self.pos..].iter().position(|&c| c == '"') else {
+            return Err(ParseError {
+                message: "Missing closing quote".to_string(),
+                start,
+                end: self.chars.len(),
+            });
+        };
+
+        let text = self.chars[self.pos..self.pos + len].iter().collect();
+        self.pos += len + 1;
+        Ok(Span {
+            text,
+            start,
+            end: self.pos,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    const NOW: OffsetDateTime = datetime!(2025-03-07 14:30 UTC);
+
+    fn parse(input: &str) -> SearchQuery {
+        parse_at(SearchQuery::new(input, 10), NOW).unwrap()
+    }
+
+    fn error(input: &str) -> (String, usize, usize) {
+        let err = parse_at(SearchQuery::new(input, 10), NOW).unwrap_err();
+        (err.message, err.start, err.end)
+    }
+
+    fn millis(at: OffsetDateTime) -> i64 {
+        (at.unix_timestamp_nanos() / 1_000_000) as i64
+    }
+
+    #[test]
+    fn test_parse_operators() {
+        let query = parse(
+            r#"kind:image KIND:File app:"Visual Studio Code" tag:work pinned:yes deploy -draft -"old notes" "exact phrase""#,
+        );
+        assert_eq!(query.kinds, [ItemKind::Image, ItemKind::File]);
+        assert_eq!(query.apps, ["Visual Studio Code"]);
+        assert_eq!(query.tags, ["work"]);
+        assert_eq!(query.pinned, Some(true));
+        assert_eq!(query.exclude, ["draft", "old notes"]);
+        assert_eq!(query.text, r#"deploy "exact phrase""#);
+    }
+
+    #[test]
+    fn test_parse_leaves_other_text() {
+        let query = parse("title:deploy https://example.com - a-b");
+        assert_eq!(query.text, "title:deploy https://example.com - a-b");
+        assert!(query.exclude.is_empty());
+    }
+
+    #[test]
+    fn test_parse_keeps_existing_filters() {
+        let query = SearchQuery {
+            kinds: vec![ItemKind::Text],
+            pinned: Some(true),
+            limit: 5,
+            ..SearchQuery::new("kind:file pinned:no ssh", 5)
+        };
+        let query = parse_at(query, NOW).unwrap();
+        assert_eq!(query.kinds, [ItemKind::Text, ItemKind::File]);
+        assert_eq!(query.pinned, Some(false));
+        assert_eq!(query.text, "ssh");
+        assert_eq!(query.limit, 5);
+    }
+
+    #[test]
+    fn test_parse_dates() {
+        assert_eq!(parse("after:2025-01-01").after, Some(millis(datetime!(2025-01-01 0:00 UTC))));
+        assert_eq!(
+            parse("before:2025-01-01T09:15").before,
+            Some(millis(datetime!(2025-01-01 9:15 UTC)))
+        );
+        assert_eq!(parse("before:today").before, Some(millis(datetime!(2025-03-07 0:00 UTC))));
+        assert_eq!(parse("after:Yesterday").after, Some(millis(datetime!(2025-03-06 0:00 UTC))));
+        assert_eq!(parse("after:3d").after, Some(millis(datetime!(2025-03-04 14:30 UTC))));
+        assert_eq!(parse("after:12h").after, Some(millis(datetime!(2025-03-07 2:30 UTC))));
+        assert_eq!(parse("after:2w").after, Some(millis(datetime!(2025-02-21 14:30 UTC))));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let (message, start, end) = error("deploy kind:video");
+        assert!(message.starts_with("Unknown kind"));
+        assert_eq!((start, end), (12, 17));
+
+        let (message, start, end) = error("after:someday");
+        assert!(message.starts_with("Invalid date"));
+        assert_eq!((start, end), (6, 13));
+        assert_eq!(error("pinned:").0, "Missing value for pinned:");
+        assert_eq!(error(r#"ok "unclosed"#), ("Missing closing quote".to_string(), 3, 12));
+        assert_eq!(error(r#"app:"Safari"#).1, 4);
+        assert_eq!(error(r#"-"""#).0, "Nothing to exclude");
+
+        // Positions are in characters
+        assert_eq!(error("grüße before:nope").1, 13);
+    }
+
+    #[test]
+    fn test_parse_age_overflow() {
+        let inputs = [
+            "after:99999999999999999d",
+            "before:9999999999999999999999w",
+            "after:2562047788016h",
+        ];
+        for input in inputs {
+            let (message, start, end) = error(input);
+            assert!(message.ends_with("is out of range"), "{}: {}", input, message);
+            assert_eq!((start, end), (input.find(':').unwrap() + 1, input.len()));
+        }
+        assert!(error("after:+3d").0.starts_with("Invalid date"));
+    }
+}
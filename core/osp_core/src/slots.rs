@@ -0,0 +1,109 @@
+//! Numbered quick-paste slots.
+//!
+//! Slots 1-9 each hold at most one item, independent of recency, so a
+//! frontend can bind them to fixed hotkeys. An item sits in at most one
+//! slot; assigning it elsewhere moves it. Slotted items survive
+//! [`Core::clear_history`](crate::Core::clear_history) like pinned ones, and
+//! the slot is cleared when the item is trashed or deleted.
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::db::{Database, ITEM_COLUMNS, ITEM_COLUMN_COUNT};
+use crate::models::Item;
+
+/// Lowest slot number.
+pub const FIRST_SLOT: u8 = 1;
+/// Highest slot number.
+pub const LAST_SLOT: u8 = 9;
+
+/// An occupied slot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Slot {
+    pub slot: u8,
+    pub item: Item,
+}
+
+fn validate_slot(slot: u8) -> Result<()> {
+    if !(FIRST_SLOT..=LAST_SLOT).contains(&slot) {
+        anyhow::bail!("Slot must be between {} and {}", FIRST_SLOT, LAST_SLOT);
+    }
+    Ok(())
+}
+
+/// Bind an item to a slot, replacing whatever the slot held.
+pub fn assign(conn: &Connection, slot: u8, id: i64) -> Result<()> {
+    validate_slot(slot)?;
+
+    let live: bool = conn
+        .prepare_cached("SELECT EXISTS(SELECT 1 FROM items WHERE id = ?1 AND deleted_at IS NULL)")?
+        .query_row(params![id], |row| row.get(0))?;
+    if !live {
+        anyhow::bail!("Item not found");
+    }
+
+    conn.prepare_cached("DELETE FROM slots WHERE slot = ?1 OR item_id = ?2")?
+        .execute(params![slot, id])
+        .context("Failed to clear slot")?;
+    conn.prepare_cached("INSERT INTO slots (slot, item_id) VALUES (?1, ?2)")?
+        .execute(params![slot, id])
+        .context("Failed to assign slot")?;
+
+    Ok(())
+}
+
+/// Empty a slot. Returns whether it held an item.
+pub fn clear(conn: &Connection, slot: u8) -> Result<bool> {
+    validate_slot(slot)?;
+
+    let rows = conn
+        .prepare_cached("DELETE FROM slots WHERE slot = ?1")?
+        .execute(params![slot])
+        .context("Failed to clear slot")?;
+
+    Ok(rows > 0)
+}
+
+/// The item in a slot, if any.
+pub fn get(conn: &Connection, slot: u8) -> Result<Option<Item>> {
+    validate_slot(slot)?;
+
+    let item = conn
+        .prepare_cached(&format!(
+            "SELECT {}
+             FROM slots s
+             INNER JOIN items i ON i.id = s.item_id
+             WHERE s.slot = ?1 AND i.deleted_at IS NULL",
+            ITEM_COLUMNS
+        ))?
+        .query_row(params![slot], Database::row_to_item)
+        .optional()
+        .context("Failed to fetch slot")?;
+
+    Ok(item)
+}
+
+/// All occupied slots, in slot order.
+pub fn list(conn: &Connection) -> Result<Vec<Slot>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {}, s.slot
+         FROM slots s
+         INNER JOIN items i ON i.id = s.item_id
+         WHERE i.deleted_at IS NULL
+         ORDER BY s.slot",
+        ITEM_COLUMNS
+    ))?;
+
+    let slots = stmt
+        .query_map([], |row| {
+            Ok(Slot {
+                item: Database::row_to_item(row)?,
+                slot: row.get(ITEM_COLUMN_COUNT)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to fetch slots")?;
+
+    Ok(slots)
+}
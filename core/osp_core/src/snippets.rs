@@ -0,0 +1,316 @@
+//! Snippet library and template rendering.
+//!
+//! A snippet is a regular text item with a trigger abbreviation (e.g.
+//! `;addr`) stored in the `snippets` table; its title is the snippet name.
+//! Any text item can be rendered as a template, with these placeholders:
+//!
+//! - `{{date}}` / `{{date:%Y-%m-%d}}`: current local date, strftime-style format
+//! - `{{time}}` / `{{time:%H:%M:%S}}`: current local time, same format syntax
+//! - `{{clipboard}}`: most recent text item in the history
+//! - `{{cursor}}`: removed from the output; its position is reported so the
+//!   frontend can place the caret there
+//!
+//! Unknown placeholders are left untouched.
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use time::{OffsetDateTime, Weekday};
+
+/// A text item reachable through an abbreviation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet {
+    /// ID of the underlying item.
+    pub id: i64,
+    pub abbreviation: String,
+    /// Snippet name, stored as the item's title.
+    pub name: Option<String>,
+    /// Unrendered template text, the item's content.
+    pub template: String,
+}
+
+/// Result of rendering a template.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rendered {
+    pub text: String,
+    /// Position of `{{cursor}}` in `text`, counted in characters.
+    pub cursor: Option<usize>,
+}
+
+/// Values substituted into templates.
+#[derive(Debug, Clone)]
+pub struct RenderContext {
+    pub now: OffsetDateTime,
+    pub clipboard: Option<String>,
+}
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_TIME_FORMAT: &str = "%H:%M";
+
+/// Render a template, expanding all known placeholders.
+pub fn render(template: &str, context: &RenderContext) -> Rendered {
+    let mut text = String::with_capacity(template.len());
+    let mut cursor = None;
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+
+        text.push_str(&rest[..start]);
+        let placeholder = &rest[start..start + len + 4];
+        let body = placeholder[2..placeholder.len() - 2].trim();
+        let (name, arg) = match body.split_once(':') {
+            Some((name, arg)) => (name.trim(), Some(arg)),
+            None => (body, None),
+        };
+
+        match name {
+            "date" => text.push_str(&format_datetime(
+                &context.now,
+                arg.unwrap_or(DEFAULT_DATE_FORMAT),
+            )),
+            "time" => text.push_str(&format_datetime(
+                &context.now,
+                arg.unwrap_or(DEFAULT_TIME_FORMAT),
+            )),
+            "clipboard" => text.push_str(context.clipboard.as_deref().unwrap_or("")),
+            "cursor" => {
+                if cursor.is_none() {
+                    cursor = Some(text.chars().count());
+                }
+            }
+            _ => text.push_str(placeholder),
+        }
+
+        rest = &rest[start + len + 4..];
+    }
+    text.push_str(rest);
+
+    Rendered { text, cursor }
+}
+
+/// Format a date with strftime-style specifiers.
+///
+/// Supports `%Y %y %m %d %e %H %I %M %S %p %b %B %a %A %j %%`; anything else
+/// is copied verbatim.
+pub fn format_datetime(now: &OffsetDateTime, format: &str) -> String {
+    let mut out = String::with_capacity(format.len() * 2);
+    let mut chars = format.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('Y') => out.push_str(&now.year().to_string()),
+            Some('y') => out.push_str(&format!("{:02}", now.year().rem_euclid(100))),
+            Some('m') => out.push_str(&format!("{:02}", u8::from(now.month()))),
+            Some('d') => out.push_str(&format!("{:02}", now.day())),
+            Some('e') => out.push_str(&format!("{:2}", now.day())),
+            Some('H') => out.push_str(&format!("{:02}", now.hour())),
+            Some('I') => out.push_str(&format!("{:02}", (now.hour() + 11) % 12 + 1)),
+            Some('M') => out.push_str(&format!("{:02}", now.minute())),
+            Some('S') => out.push_str(&format!("{:02}", now.second())),
+            Some('p') => out.push_str(if now.hour() < 12 { "AM" } else { "PM" }),
+            Some('b') => out.push_str(&now.month().to_string()[..3]),
+            Some('B') => out.push_str(&now.month().to_string()),
+            Some('a') => out.push_str(&weekday_name(now.weekday())[..3]),
+            Some('A') => out.push_str(weekday_name(now.weekday())),
+            Some('j') => out.push_str(&format!("{:03}", now.ordinal())),
+            Some('%') => out.push('%'),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+
+    out
+}
+
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Monday => "Monday",
+        Weekday::Tuesday => "Tuesday",
+        Weekday::Wednesday => "Wednesday",
+        Weekday::Thursday => "Thursday",
+        Weekday::Friday => "Friday",
+        Weekday::Saturday => "Saturday",
+        Weekday::Sunday => "Sunday",
+    }
+}
+
+/// Check that an abbreviation is usable as a trigger.
+pub fn validate_abbreviation(abbreviation: &str) -> Result<()> {
+    if abbreviation.is_empty() {
+        anyhow::bail!("Abbreviation must not be empty");
+    }
+    if abbreviation.chars().any(char::is_whitespace) {
+        anyhow::bail!("Abbreviation must not contain whitespace");
+    }
+    Ok(())
+}
+
+/// Make a text item reachable through `abbreviation`, replacing any
+/// abbreviation it already had.
+pub fn set_abbreviation(conn: &Connection, id: i64, abbreviation: &str) -> Result<()> {
+    validate_abbreviation(abbreviation)?;
+
+    let kind: Option<String> = conn
+        .prepare_cached("SELECT kind FROM items WHERE id = ?1 AND deleted_at IS NULL")?
+        .query_row(params![id], |row| row.get(0))
+        .optional()?;
+
+    match kind.as_deref() {
+        Some("text") | Some("rtf") => {}
+        Some(_) => anyhow::bail!("Only text and rtf items can be snippets"),
+        None => anyhow::bail!("Item not found"),
+    }
+
+    let taken: Option<i64> = conn
+        .prepare_cached("SELECT item_id FROM snippets WHERE abbreviation = ?1")?
+        .query_row(params![abbreviation], |row| row.get(0))
+        .optional()?;
+
+    if taken.is_some_and(|other| other != id) {
+        anyhow::bail!("Abbreviation {:?} is already in use", abbreviation);
+    }
+
+    conn.prepare_cached(
+        "INSERT INTO snippets (item_id, abbreviation) VALUES (?1, ?2)
+         ON CONFLICT(item_id) DO UPDATE SET abbreviation = excluded.abbreviation",
+    )?
+    .execute(params![id, abbreviation])
+    .context("Failed to save abbreviation")?;
+
+    Ok(())
+}
+
+/// Remove an item's abbreviation. The item itself is kept.
+pub fn remove(conn: &Connection, id: i64) -> Result<()> {
+    let rows = conn
+        .prepare_cached("DELETE FROM snippets WHERE item_id = ?1")?
+        .execute(params![id])
+        .context("Failed to remove snippet")?;
+
+    if rows == 0 {
+        anyhow::bail!("Snippet not found");
+    }
+
+    Ok(())
+}
+
+/// List all snippets, ordered by abbreviation. Trashed items are skipped.
+pub fn list(conn: &Connection) -> Result<Vec<Snippet>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT i.id, s.abbreviation, i.title, i.content_ref
+         FROM snippets s
+         INNER JOIN items i ON i.id = s.item_id
+         WHERE i.deleted_at IS NULL
+         ORDER BY s.abbreviation",
+    )?;
+
+    let snippets = stmt
+        .query_map([], row_to_snippet)?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to fetch snippets")?;
+
+    Ok(snippets)
+}
+
+/// Look up a snippet by its exact abbreviation.
+pub fn find(conn: &Connection, abbreviation: &str) -> Result<Option<Snippet>> {
+    let snippet = conn
+        .prepare_cached(
+            "SELECT i.id, s.abbreviation, i.title, i.content_ref
+             FROM snippets s
+             INNER JOIN items i ON i.id = s.item_id
+             WHERE s.abbreviation = ?1 AND i.deleted_at IS NULL",
+        )?
+        .query_row(params![abbreviation], row_to_snippet)
+        .optional()
+        .context("Failed to look up snippet")?;
+
+    Ok(snippet)
+}
+
+/// Content of the most recent text item, used for `{{clipboard}}`.
+///
+/// Snippets are not clipboard history, so they are skipped.
+pub fn latest_text(conn: &Connection) -> Result<Option<String>> {
+    let text = conn
+        .prepare_cached(
+            "SELECT content_ref FROM items
+             WHERE kind = 'text' AND deleted_at IS NULL
+               AND id NOT IN (SELECT item_id FROM snippets)
+             ORDER BY created_at DESC
+             LIMIT 1",
+        )?
+        .query_row([], |row| row.get(0))
+        .optional()?;
+
+    Ok(text)
+}
+
+fn row_to_snippet(row: &rusqlite::Row) -> rusqlite::Result<Snippet> {
+    Ok(Snippet {
+        id: row.get(0)?,
+        abbreviation: row.get(1)?,
+        name: row.get(2)?,
+        template: row.get(3)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    fn context() -> RenderContext {
+        RenderContext {
+            now: datetime!(2025-03-07 14:05:09 UTC),
+            clipboard: Some("pasted".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_render_placeholders() {
+        let rendered = render(
+            "On {{date}} at {{time}}: {{clipboard}} ({{ date:%d/%m/%y }})",
+            &context(),
+        );
+        assert_eq!(rendered.text, "On 2025-03-07 at 14:05: pasted (07/03/25)");
+        assert_eq!(rendered.cursor, None);
+    }
+
+    #[test]
+    fn test_render_cursor() {
+        let rendered = render("Hi {{cursor}}, thanks! {{cursor}}", &context());
+        assert_eq!(rendered.text, "Hi , thanks! ");
+        assert_eq!(rendered.cursor, Some(3));
+
+        // Counted in characters, not bytes
+        let rendered = render("Grüße {{cursor}}", &context());
+        assert_eq!(rendered.cursor, Some(6));
+    }
+
+    #[test]
+    fn test_render_leaves_unknown_text() {
+        let rendered = render("{{unknown}} {{date", &context());
+        assert_eq!(rendered.text, "{{unknown}} {{date");
+    }
+
+    #[test]
+    fn test_format_datetime() {
+        let now = datetime!(2025-03-07 09:05:09 UTC);
+        assert_eq!(
+            format_datetime(&now, "%A %a %B %b %e %j %I%p %S %% %Q"),
+            "Friday Fri March Mar  7 066 09AM 09 % %Q"
+        );
+    }
+}
@@ -0,0 +1,256 @@
+//! Usage statistics and storage report.
+//!
+//! Everything except file sizes is computed in SQL over the `items` table,
+//! so a report costs a handful of aggregate queries regardless of history
+//! size. Dedupe attempts and hits are counted as items are captured and
+//! kept in the `settings` table under [`DEDUPE_KEY`].
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::db::{Database, ITEM_COLUMNS};
+use crate::facets::Count;
+use crate::models::{Item, ItemKind};
+
+/// Days covered by [`Stats::days`] in [`Core::stats`](crate::Core::stats).
+pub const WINDOW_DAYS: u32 = 30;
+
+/// Most source apps listed in [`Stats::apps`].
+pub const TOP_APPS: usize = 10;
+
+/// Settings key holding the dedupe counters.
+pub const DEDUPE_KEY: &str = "dedupe_stats";
+
+/// How often a deduplicating capture found an existing item.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DedupeStats {
+    /// Captures checked for a duplicate.
+    pub attempts: i64,
+    /// Captures that bumped an existing item instead of inserting.
+    pub hits: i64,
+    /// `hits / attempts`, or 0 before the first attempt.
+    pub hit_rate: f64,
+}
+
+/// Storage and usage report.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stats {
+    /// Items outside the trash.
+    pub total: i64,
+    /// Items in the trash.
+    pub trashed: i64,
+    /// Items outside the trash by kind, most common first.
+    pub kinds: Vec<Count<ItemKind>>,
+    /// UTF-8 size of all text and RTF content, trash included.
+    pub text_bytes: i64,
+    /// Size of the files in the blobs directory.
+    pub blob_bytes: u64,
+    /// Size of the database file.
+    pub database_bytes: u64,
+    /// Size of the write-ahead log, 0 when it has been checkpointed away.
+    pub wal_bytes: u64,
+    /// Item with the earliest timestamp, outside the trash.
+    pub oldest: Option<Item>,
+    /// Item with the latest timestamp, outside the trash.
+    pub newest: Option<Item>,
+    /// Items by source app, most common first; `None` for items without
+    /// one. At most [`TOP_APPS`].
+    pub apps: Vec<Count<Option<String>>>,
+    /// Items by local day of their timestamp (`YYYY-MM-DD`), oldest first,
+    /// for every day of the window including today and days without any.
+    pub days: Vec<Count<String>>,
+    /// Duplicate captures since the counters were introduced.
+    pub dedupe: DedupeStats,
+}
+
+/// Compute the report for the database at `db_path`, with `window_days`
+/// days of counts ending on the local day of `now`.
+pub fn stats(
+    conn: &Connection,
+    db_path: &Path,
+    blobs_dir: &Path,
+    window_days: u32,
+    now: i64,
+) -> Result<Stats> {
+    let (total, trashed, text_bytes) = conn
+        .prepare_cached(
+            "SELECT COUNT(*) FILTER (WHERE deleted_at IS NULL),
+                    COUNT(*) FILTER (WHERE deleted_at IS NOT NULL),
+                    COALESCE(SUM(length(CAST(content_ref AS BLOB)))
+                        FILTER (WHERE kind IN ('text', 'rtf')), 0)
+             FROM items",
+        )?
+        .query_row([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .context("Failed to count items")?;
+
+    let kinds = conn
+        .prepare_cached(
+            "SELECT kind, COUNT(*) FROM items WHERE deleted_at IS NULL
+             GROUP BY kind ORDER BY 2 DESC, 1",
+        )?
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to count items by kind")?
+        .into_iter()
+        .map(|(value, count)| {
+            ItemKind::from_str(&value)
+                .map(|value| Count { value, count })
+                .map_err(anyhow::Error::msg)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let apps = conn
+        .prepare_cached(&format!(
+            "SELECT source_app, COUNT(*) FROM items WHERE deleted_at IS NULL
+             GROUP BY source_app ORDER BY 2 DESC, 1 LIMIT {}",
+            TOP_APPS
+        ))?
+        .query_map([], |row| {
+            Ok(Count {
+                value: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to count items by app")?;
+
+    Ok(Stats {
+        total,
+        trashed,
+        kinds,
+        text_bytes,
+        blob_bytes: dir_size(blobs_dir)?,
+        database_bytes: file_size(db_path)?,
+        wal_bytes: file_size(&wal_path(db_path))?,
+        oldest: edge_item(conn, "ASC")?,
+        newest: edge_item(conn, "DESC")?,
+        apps,
+        days: days(conn, window_days, now)?,
+        dedupe: dedupe(conn)?,
+    })
+}
+
+/// Count one deduplicating capture.
+pub fn record_dedupe(conn: &Connection, hit: bool) -> Result<()> {
+    conn.prepare_cached(
+        "INSERT INTO settings (key, value) VALUES (?1, json_object('attempts', 1, 'hits', ?2))
+         ON CONFLICT(key) DO UPDATE SET value = json_set(value,
+             '$.attempts', json_extract(value, '$.attempts') + 1,
+             '$.hits', json_extract(value, '$.hits') + ?2)",
+    )?
+    .execute(params![DEDUPE_KEY, hit as i64])
+    .context("Failed to record dedupe")?;
+
+    Ok(())
+}
+
+fn dedupe(conn: &Connection) -> Result<DedupeStats> {
+    let mut stats: DedupeStats = Database::setting(conn, DEDUPE_KEY)?.unwrap_or_default();
+    if stats.attempts > 0 {
+        stats.hit_rate = stats.hits as f64 / stats.attempts as f64;
+    }
+    Ok(stats)
+}
+
+fn edge_item(conn: &Connection, order: &str) -> Result<Option<Item>> {
+    let item = conn
+        .prepare_cached(&format!(
+            "SELECT {} FROM items i WHERE i.deleted_at IS NULL
+             ORDER BY i.created_at {order}, i.id {order} LIMIT 1",
+            ITEM_COLUMNS
+        ))?
+        .query_row([], Database::row_to_item)
+        .optional()
+        .context("Failed to fetch item")?;
+
+    Ok(item)
+}
+
+fn days(conn: &Connection, window_days: u32, now: i64) -> Result<Vec<Count<String>>> {
+    if window_days == 0 {
+        return Ok(Vec::new());
+    }
+
+    // Every local day of the window, joined with the items counted per day.
+    // The timestamp bound only narrows the scan; it reaches a day further
+    // back than needed so no UTC offset cuts off the first day.
+    let days = conn
+        .prepare_cached(
+            "WITH RECURSIVE calendar(day, n) AS (
+                 SELECT date(?1 / 1000, 'unixepoch', 'localtime'), 1
+                 UNION ALL
+                 SELECT date(day, '-1 day'), n + 1 FROM calendar WHERE n < ?2
+             ),
+             counts(day, count) AS (
+                 SELECT date(created_at / 1000, 'unixepoch', 'localtime'), COUNT(*)
+                 FROM items
+                 WHERE deleted_at IS NULL AND created_at >= ?1 - (?2 + 1) * 86400000
+                 GROUP BY 1
+             )
+             SELECT d.day, COALESCE(c.count, 0)
+             FROM calendar d LEFT JOIN counts c ON c.day = d.day
+             ORDER BY d.day",
+        )?
+        .query_map(params![now, window_days], |row| {
+            Ok(Count {
+                value: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to count items by day")?;
+
+    Ok(days)
+}
+
+fn wal_path(db_path: &Path) -> std::path::PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push("-wal");
+    path.into()
+}
+
+fn file_size(path: &Path) -> Result<u64> {
+    match std::fs::metadata(path) {
+        Ok(metadata) => Ok(metadata.len()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e).with_context(|| format!("Failed to read size of {:?}", path)),
+    }
+}
+
+fn dir_size(dir: &Path) -> Result<u64> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {:?}", dir)),
+    };
+
+    let mut size = 0;
+    for entry in entries {
+        let entry = entry.with_context(|| format!("Failed to read {:?}", dir))?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else if file_type.is_file() {
+            size += entry.metadata()?.len();
+        }
+    }
+
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wal_path() {
+        assert_eq!(
+            wal_path(Path::new("/tmp/clipboard.db")),
+            Path::new("/tmp/clipboard.db-wal")
+        );
+    }
+}
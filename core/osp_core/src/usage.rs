@@ -0,0 +1,77 @@
+//! Paste events and frecency.
+//!
+//! Every paste from Haste is recorded in the `uses` table. Frecency is the
+//! sum of all uses of an item, each weighted by its age, so items pasted
+//! often and recently score highest:
+//!
+//! | Age of use  | Weight |
+//! |-------------|--------|
+//! | < 4 days    | 100    |
+//! | < 14 days   | 70     |
+//! | < 31 days   | 50     |
+//! | < 90 days   | 30     |
+//! | older       | 10     |
+//!
+//! Suggestions apply the same weighting to the uses in one target app.
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+
+use crate::db::{Database, ITEM_COLUMNS};
+use crate::models::Item;
+
+/// Weight of the use `u` by its age, relative to the current time.
+pub const USE_WEIGHT: &str = "CASE
+    WHEN unixepoch('subsec') * 1000 - u.used_at < 4 * 86400000 THEN 100
+    WHEN unixepoch('subsec') * 1000 - u.used_at < 14 * 86400000 THEN 70
+    WHEN unixepoch('subsec') * 1000 - u.used_at < 31 * 86400000 THEN 50
+    WHEN unixepoch('subsec') * 1000 - u.used_at < 90 * 86400000 THEN 30
+    ELSE 10
+  END";
+
+/// SQL expression for the frecency of the item aliased as `i`.
+pub fn frecency_sql() -> String {
+    format!(
+        "(SELECT COALESCE(SUM({}), 0) FROM uses u WHERE u.item_id = i.id)",
+        USE_WEIGHT
+    )
+}
+
+/// Record that an item was pasted, optionally into `target_app`.
+pub fn record(conn: &Connection, id: i64, target_app: Option<&str>, used_at: i64) -> Result<()> {
+    let live: bool = conn
+        .prepare_cached("SELECT EXISTS(SELECT 1 FROM items WHERE id = ?1 AND deleted_at IS NULL)")?
+        .query_row(params![id], |row| row.get(0))?;
+    if !live {
+        anyhow::bail!("Item not found");
+    }
+
+    conn.prepare_cached("INSERT INTO uses (item_id, target_app, used_at) VALUES (?1, ?2, ?3)")?
+        .execute(params![id, target_app, used_at])
+        .context("Failed to record use")?;
+
+    Ok(())
+}
+
+/// Items pasted into `target_app`, by per-app frecency, then most recent use.
+///
+/// Items never pasted into the app are not included.
+pub fn suggest(conn: &Connection, target_app: &str, limit: u32) -> Result<Vec<Item>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {}
+         FROM uses u
+         INNER JOIN items i ON i.id = u.item_id
+         WHERE u.target_app = ?1 AND i.deleted_at IS NULL
+         GROUP BY i.id
+         ORDER BY SUM({}) DESC, MAX(u.used_at) DESC
+         LIMIT ?2",
+        ITEM_COLUMNS, USE_WEIGHT
+    ))?;
+
+    let items = stmt
+        .query_map(params![target_app, limit], Database::row_to_item)?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to fetch suggestions")?;
+
+    Ok(items)
+}
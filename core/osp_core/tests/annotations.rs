@@ -0,0 +1,121 @@
+//! Tests for item titles and notes.
+
+use osp_core::{Core, ItemKind};
+use tempfile::tempdir;
+
+mod common;
+use common::{new_item, open};
+
+#[test]
+fn test_annotate_and_get() {
+    let (_dir, core) = open();
+    let id = core
+        .add_item(new_item(ItemKind::Text, "server { listen 80; }", 1000))
+        .unwrap();
+
+    core.annotate(id, Some("Nginx config"), Some("Staging box")).unwrap();
+    let item = core.get(id).unwrap();
+    assert_eq!(item.title.as_deref(), Some("Nginx config"));
+    assert_eq!(item.note.as_deref(), Some("Staging box"));
+
+    // Empty strings clear the fields
+    core.annotate(id, Some(""), None).unwrap();
+    let item = core.get(id).unwrap();
+    assert!(item.title.is_none());
+    assert!(item.note.is_none());
+
+    assert!(core.annotate(9999, Some("missing"), None).is_err());
+}
+
+#[test]
+fn test_search_titles_and_notes() {
+    let (_dir, core) = open();
+    let config = core
+        .add_item(new_item(ItemKind::Text, "worker_processes auto;", 1000))
+        .unwrap();
+    core.annotate(config, Some("Nginx config"), Some("copied from staging"))
+        .unwrap();
+    core.add_item(new_item(ItemKind::Text, "unrelated text", 1001))
+        .unwrap();
+
+    // Found by title and by note, not only by content
+    let results = core.search("nginx", 10).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id, config);
+    assert_eq!(core.search("staging", 10).unwrap().len(), 1);
+
+    // Clearing the title removes it from the index
+    core.annotate(config, None, Some("copied from staging")).unwrap();
+    assert!(core.search("nginx", 10).unwrap().is_empty());
+}
+
+#[test]
+fn test_title_filter_and_weight() {
+    let (_dir, core) = open();
+
+    // Mentions "deploy" in content only, and is newer
+    let content_match = core
+        .add_item(new_item(ItemKind::Text, "run deploy after tests pass", 2000))
+        .unwrap();
+
+    // Named "Deploy" by the user
+    let titled = core
+        .add_item(new_item(ItemKind::Text, "make release && ./ship.sh", 1000))
+        .unwrap();
+    core.annotate(titled, Some("Deploy"), None).unwrap();
+
+    // Title matches rank above content matches
+    let results = core.search("deploy", 10).unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].id, titled);
+    assert_eq!(results[1].id, content_match);
+
+    // title: restricts the match to titles
+    let results = core.search("title:deploy", 10).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id, titled);
+}
+
+#[test]
+fn test_annotated_images_are_searchable() {
+    let (_dir, core) = open();
+    let image = core
+        .add_item(new_item(ItemKind::Image, "/blobs/architecture.png", 1000))
+        .unwrap();
+    core.annotate(image, Some("Architecture diagram"), None).unwrap();
+
+    let results = core.search("diagram", 10).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id, image);
+
+    // Permanent deletion removes the FTS entry too
+    core.delete_permanently(image).unwrap();
+    assert!(core.search("diagram", 10).unwrap().is_empty());
+}
+
+#[test]
+fn test_upgrade_keeps_fts_entries() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+
+    // Database created by the first release, before items_fts had title/note
+    {
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        conn.execute_batch(include_str!("../migrations/0001_init.sql"))
+            .unwrap();
+        conn.execute_batch(
+            "PRAGMA user_version = 1;
+             INSERT INTO items (id, kind, content_ref, created_at) VALUES (1, 'text', 'legacy entry', 1000);
+             INSERT INTO items_fts (item_id, text) VALUES (1, 'legacy entry');",
+        )
+        .unwrap();
+    }
+
+    let core = Core::open(&db_path, &blobs_dir).unwrap();
+    let results = core.search("legacy", 10).unwrap();
+    assert_eq!(results.len(), 1);
+
+    core.annotate(1, Some("Old one"), None).unwrap();
+    assert_eq!(core.search("title:old", 10).unwrap().len(), 1);
+}
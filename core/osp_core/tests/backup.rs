@@ -0,0 +1,376 @@
+//! Tests for online backup and restore.
+
+use osp_core::{BackupOptions, BackupStage, Core, ItemKind};
+use std::ffi::{c_void, CStr, CString};
+use std::path::Path;
+use tempfile::tempdir;
+
+mod common;
+use common::{new_item, open, text_item};
+
+fn ids(core: &Core, query: &str) -> Vec<i64> {
+    let mut ids: Vec<i64> = core.search(query, 1000).unwrap().iter().map(|i| i.id).collect();
+    ids.sort();
+    ids
+}
+
+fn populate(core: &Core, count: i64) {
+    for result in core.add_items((1..=count).map(|i| text_item(&format!("entry {}", i), i))) {
+        result.unwrap();
+    }
+}
+
+fn with_blobs(core: &Core, blobs_dir: &Path) {
+    std::fs::create_dir_all(blobs_dir.join("ab")).unwrap();
+    std::fs::write(blobs_dir.join("image.png"), [1u8, 2, 3]).unwrap();
+    std::fs::write(blobs_dir.join("ab").join("nested.bin"), vec![7u8; 10_000]).unwrap();
+    core.add_item(new_item(ItemKind::Image, "image.png", 10_000))
+    .unwrap();
+}
+
+#[test]
+fn test_backup_and_restore() {
+    let (dir, core) = open();
+    populate(&core, 500);
+    let before = ids(&core, "entry");
+
+    let backup_path = dir.path().join("backup.db");
+    let report = core.backup_to(&backup_path, &BackupOptions::default()).unwrap();
+    assert_eq!(report.path, backup_path);
+    assert_eq!(report.bytes, std::fs::metadata(&backup_path).unwrap().len());
+    assert_eq!(report.blobs, 0);
+    assert!(!dir.path().join("backup.db.partial").exists());
+
+    // Changes after the backup are undone by the restore
+    let later = core.add_item(text_item("entry after backup", 501)).unwrap();
+    core.delete(1).unwrap();
+    assert_ne!(ids(&core, "entry"), before);
+
+    let restored = core.restore_from(&backup_path).unwrap();
+    assert_eq!(restored.items, 500);
+    assert_eq!(restored.blobs, 0);
+    assert_eq!(ids(&core, "entry"), before);
+    assert!(core.get(later).is_err());
+
+    // Still a working WAL database
+    let id = core.add_item(text_item("entry after restore", 502)).unwrap();
+    assert_eq!(core.search("restore", 10).unwrap()[0].id, id);
+    let conn = rusqlite::Connection::open(dir.path().join("test.db")).unwrap();
+    let mode: String = conn.query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap();
+    assert_eq!(mode, "wal");
+}
+
+#[test]
+fn test_backup_into_fresh_core() {
+    let (dir, core) = open();
+    populate(&core, 100);
+
+    let backup_path = dir.path().join("backup.db");
+    core.backup_to(&backup_path, &BackupOptions::default()).unwrap();
+
+    // The backup is a self-contained file
+    let conn = rusqlite::Connection::open(&backup_path).unwrap();
+    let mode: String = conn.query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap();
+    assert_eq!(mode, "delete");
+    drop(conn);
+
+    let other = tempdir().unwrap();
+    let fresh = Core::open(&other.path().join("test.db"), &other.path().join("blobs")).unwrap();
+    fresh.add_item(text_item("replaced", 1)).unwrap();
+    let report = fresh.restore_from(&backup_path).unwrap();
+    assert_eq!(report.items, 100);
+    assert!(fresh.search("replaced", 10).unwrap().is_empty());
+    assert_eq!(ids(&fresh, "entry"), ids(&core, "entry"));
+}
+
+#[test]
+fn test_backup_while_capturing() {
+    let dir = tempdir().unwrap();
+    let core = std::sync::Arc::new(
+        Core::open(&dir.path().join("test.db"), &dir.path().join("blobs")).unwrap(),
+    );
+    populate(&core, 2000);
+
+    let writer = {
+        let core = core.clone();
+        std::thread::spawn(move || {
+            for i in 0..200 {
+                core.add_item(text_item(&format!("concurrent {}", i), 10_000 + i)).unwrap();
+            }
+        })
+    };
+    let backup_path = dir.path().join("backup.db");
+    core.backup_to(&backup_path, &BackupOptions::default()).unwrap();
+    writer.join().unwrap();
+
+    // A consistent snapshot somewhere during the captures
+    let other = tempdir().unwrap();
+    let fresh = Core::open(&other.path().join("test.db"), &other.path().join("blobs")).unwrap();
+    let report = fresh.restore_from(&backup_path).unwrap();
+    assert!((2000..=2200).contains(&report.items));
+    assert_eq!(fresh.stats().unwrap().total, report.items);
+    let report = fresh.maintenance(&Default::default()).unwrap();
+    assert!(report.is_healthy(), "{:?}", report);
+}
+
+#[test]
+fn test_backup_with_blobs() {
+    let (dir, core) = open();
+    let blobs_dir = dir.path().join("blobs");
+    populate(&core, 10);
+    with_blobs(&core, &blobs_dir);
+
+    let backup_path = dir.path().join("backup.db");
+    let mut events = Vec::new();
+    let options = BackupOptions {
+        include_blobs: true,
+    };
+    let report = core
+        .backup_to_with_progress(&backup_path, &options, |p| events.push(p))
+        .unwrap();
+    assert_eq!(report.blobs, 2);
+    assert!(events.iter().any(|p| p.stage == BackupStage::Database));
+    let last = events.last().unwrap();
+    assert_eq!((last.stage, last.done, last.total), (BackupStage::Blobs, 2, 2));
+    let database = events.iter().rfind(|p| p.stage == BackupStage::Database).unwrap();
+    assert_eq!(database.done, database.total);
+
+    std::fs::remove_dir_all(&blobs_dir).unwrap();
+
+    let mut events = Vec::new();
+    let report = core
+        .restore_from_with_progress(&backup_path, |p| events.push(p))
+        .unwrap();
+    assert_eq!(report.blobs, 2);
+    assert_eq!(report.items, 11);
+    assert!(events.iter().any(|p| p.stage == BackupStage::Blobs));
+    assert!(events.iter().any(|p| p.stage == BackupStage::Database));
+    assert_eq!(std::fs::read(blobs_dir.join("image.png")).unwrap(), [1, 2, 3]);
+    assert_eq!(
+        std::fs::read(blobs_dir.join("ab").join("nested.bin")).unwrap(),
+        vec![7u8; 10_000]
+    );
+    assert!(!dir.path().join("blobs.restoring").exists());
+
+    // The blobs don't stay in the database
+    let conn = rusqlite::Connection::open(dir.path().join("test.db")).unwrap();
+    let tables: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_schema WHERE name = 'backup_blobs'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(tables, 0);
+}
+
+#[test]
+fn test_rotating_backups() {
+    let (dir, core) = open();
+    let backups_dir = dir.path().join("backups");
+    assert!(core.list_backups(&backups_dir).unwrap().is_empty());
+    assert!(core
+        .backup_rotating(&backups_dir, 0, &BackupOptions::default())
+        .is_err());
+
+    let mut paths = Vec::new();
+    for i in 1..=5 {
+        core.add_item(text_item(&format!("entry {}", i), i)).unwrap();
+        let report = core
+            .backup_rotating(&backups_dir, 3, &BackupOptions::default())
+            .unwrap();
+        assert_eq!(report.removed.len(), usize::from(i > 3));
+        paths.push(report.path);
+    }
+
+    // Unrelated files are left alone
+    std::fs::write(backups_dir.join("notes.txt"), "keep me").unwrap();
+
+    let listed = core.list_backups(&backups_dir).unwrap();
+    assert_eq!(listed, vec![paths[4].clone(), paths[3].clone(), paths[2].clone()]);
+    assert!(!paths[0].exists());
+    assert!(backups_dir.join("notes.txt").exists());
+
+    // The newest holds every item
+    let report = core.restore_from(&listed[0]).unwrap();
+    assert_eq!(report.items, 5);
+    let report = core.restore_from(&listed[2]).unwrap();
+    assert_eq!(report.items, 3);
+}
+
+#[test]
+fn test_restore_rejects_newer_schema() {
+    let (dir, core) = open();
+    populate(&core, 10);
+
+    let backup_path = dir.path().join("backup.db");
+    core.backup_to(&backup_path, &BackupOptions::default()).unwrap();
+    let conn = rusqlite::Connection::open(&backup_path).unwrap();
+    conn.execute_batch("PRAGMA user_version = 99").unwrap();
+    drop(conn);
+
+    core.add_item(text_item("entry 11", 11)).unwrap();
+    let err = core.restore_from(&backup_path).unwrap_err();
+    assert!(err.to_string().contains("newer version"), "{}", err);
+    assert!(err.to_string().contains("schema 99"), "{}", err);
+    assert_eq!(core.stats().unwrap().total, 11);
+}
+
+#[test]
+fn test_restore_rejects_invalid_files() {
+    let (dir, core) = open();
+    populate(&core, 10);
+
+    let garbage = dir.path().join("garbage.db");
+    std::fs::write(&garbage, vec![0x5au8; 8192]).unwrap();
+    assert!(core.restore_from(&garbage).is_err());
+
+    let other = dir.path().join("other.db");
+    let conn = rusqlite::Connection::open(&other).unwrap();
+    conn.execute_batch("CREATE TABLE notes(text); PRAGMA user_version = 1;").unwrap();
+    drop(conn);
+    let err = core.restore_from(&other).unwrap_err();
+    assert!(err.to_string().contains("not a clipboard database"), "{}", err);
+
+    assert!(core.restore_from(&dir.path().join("missing.db")).is_err());
+    assert!(!dir.path().join("missing.db").exists());
+
+    assert_eq!(ids(&core, "entry").len(), 10);
+}
+
+#[test]
+fn test_failed_restore_leaves_blobs_alone() {
+    let (dir, core) = open();
+    let blobs_dir = dir.path().join("blobs");
+    populate(&core, 10);
+    with_blobs(&core, &blobs_dir);
+
+    let backup_path = dir.path().join("backup.db");
+    let options = BackupOptions {
+        include_blobs: true,
+    };
+    core.backup_to(&backup_path, &options).unwrap();
+    let conn = rusqlite::Connection::open(&backup_path).unwrap();
+    // Sorts after the valid blobs
+    conn.execute("INSERT INTO backup_blobs (name, data) VALUES ('zz/../../escape', x'00')", [])
+        .unwrap();
+    drop(conn);
+
+    std::fs::write(blobs_dir.join("image.png"), [4u8, 5, 6]).unwrap();
+    populate(&core, 2);
+    let before = ids(&core, "entry");
+
+    let err = core.restore_from(&backup_path).unwrap_err();
+    assert!(err.to_string().contains("invalid blob name"), "{}", err);
+    assert_eq!(std::fs::read(blobs_dir.join("image.png")).unwrap(), [4, 5, 6]);
+    assert!(!dir.path().join("blobs.restoring").exists());
+    assert!(!dir.path().join("escape").exists());
+    assert_eq!(ids(&core, "entry"), before);
+}
+
+#[test]
+fn test_restore_migrates_older_backup() {
+    let (dir, core) = open();
+    populate(&core, 10);
+
+    let backup_path = dir.path().join("backup.db");
+    core.backup_to(&backup_path, &BackupOptions::default()).unwrap();
+    let conn = rusqlite::Connection::open(&backup_path).unwrap();
+    let current: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+    // Undo the last migration, as if made by the previous version
+    conn.execute_batch(&format!(
+        "DROP TABLE saved_searches; PRAGMA user_version = {}",
+        current - 1
+    ))
+    .unwrap();
+    drop(conn);
+
+    let report = core.restore_from(&backup_path).unwrap();
+    assert_eq!(report.schema_version, current - 1);
+    let conn = rusqlite::Connection::open(dir.path().join("test.db")).unwrap();
+    let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+    assert_eq!(version, current);
+    drop(conn);
+    assert!(core.list_saved_searches().unwrap().is_empty());
+    assert_eq!(ids(&core, "entry").len(), 10);
+}
+
+extern "C" fn record_progress(stage: i32, done: i64, total: i64, user_data: *mut c_void) {
+    let events = unsafe { &mut *(user_data as *mut Vec<(i32, i64, i64)>) };
+    events.push((stage, done, total));
+}
+
+#[test]
+fn test_ffi_backup_and_restore() {
+    use osp_core::ffi::*;
+
+    let dir = tempdir().unwrap();
+    let db_path = CString::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+    let blobs_dir = CString::new(dir.path().join("blobs").to_str().unwrap()).unwrap();
+    let backup_path = CString::new(dir.path().join("backup.db").to_str().unwrap()).unwrap();
+    let backups_dir = CString::new(dir.path().join("backups").to_str().unwrap()).unwrap();
+
+    unsafe {
+        let handle = core_new(db_path.as_ptr(), blobs_dir.as_ptr());
+        assert!(!handle.is_null());
+        let content = CString::new("hello backup").unwrap();
+        assert!(core_add_item(handle, 0, content.as_ptr(), std::ptr::null(), 1) > 0);
+
+        let mut events: Vec<(i32, i64, i64)> = Vec::new();
+        let options = CString::new(r#"{"include_blobs": true}"#).unwrap();
+        let report = core_backup_to(
+            handle,
+            backup_path.as_ptr(),
+            options.as_ptr(),
+            Some(record_progress),
+            &mut events as *mut _ as *mut c_void,
+        );
+        assert!(!report.is_null());
+        let value: serde_json::Value =
+            serde_json::from_str(CStr::from_ptr(report).to_str().unwrap()).unwrap();
+        assert!(value["bytes"].as_u64().unwrap() > 0);
+        string_free(report);
+        assert!(events.iter().any(|&(stage, _, _)| stage == 0));
+
+        let bad = CString::new("not json").unwrap();
+        let report = core_backup_to(
+            handle,
+            backup_path.as_ptr(),
+            bad.as_ptr(),
+            None,
+            std::ptr::null_mut(),
+        );
+        assert!(report.is_null());
+
+        for _ in 0..3 {
+            let report = core_backup_rotating(
+                handle,
+                backups_dir.as_ptr(),
+                2,
+                std::ptr::null(),
+                None,
+                std::ptr::null_mut(),
+            );
+            assert!(!report.is_null());
+            string_free(report);
+        }
+        let list = core_list_backups_json(handle, backups_dir.as_ptr());
+        let value: serde_json::Value =
+            serde_json::from_str(CStr::from_ptr(list).to_str().unwrap()).unwrap();
+        assert_eq!(value.as_array().unwrap().len(), 2);
+        string_free(list);
+
+        let report = core_restore_from(handle, backup_path.as_ptr(), None, std::ptr::null_mut());
+        assert!(!report.is_null());
+        let value: serde_json::Value =
+            serde_json::from_str(CStr::from_ptr(report).to_str().unwrap()).unwrap();
+        assert_eq!(value["items"], 1);
+        string_free(report);
+
+        let missing = CString::new(dir.path().join("missing.db").to_str().unwrap()).unwrap();
+        let report = core_restore_from(handle, missing.as_ptr(), None, std::ptr::null_mut());
+        assert!(report.is_null());
+
+        core_free(handle);
+    }
+}
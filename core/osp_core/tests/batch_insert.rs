@@ -0,0 +1,76 @@
+//! Tests for the batch insert API.
+
+use std::ffi::{CStr, CString};
+use tempfile::tempdir;
+
+mod common;
+use common::{open, text_item};
+
+#[test]
+fn test_add_items() {
+    let (_dir, core) = open();
+
+    let items = (0..500).map(|i| text_item(&format!("imported entry {}", i), i));
+    let results = core.add_items(items);
+    assert_eq!(results.len(), 500);
+
+    let ids: Vec<i64> = results.into_iter().map(Result::unwrap).collect();
+    assert_eq!(core.get(ids[42]).unwrap().content_ref, "imported entry 42");
+    assert_eq!(core.search("imported", 1000).unwrap().len(), 500);
+
+    // Without dedupe, identical content is stored again
+    let results = core.add_items(vec![text_item("imported entry 0", 1000)]);
+    assert_ne!(*results[0].as_ref().unwrap(), ids[0]);
+}
+
+#[test]
+fn test_dedupe_insert_items() {
+    let (_dir, core) = open();
+    let existing = core.add_item(text_item("hello world", 1000)).unwrap();
+
+    let results = core.dedupe_insert_items(vec![
+        text_item("  hello   world ", 2000),
+        text_item("fresh value", 3000),
+        text_item("fresh   value", 4000),
+    ]);
+    let ids: Vec<i64> = results.into_iter().map(Result::unwrap).collect();
+
+    assert_eq!(ids[0], existing, "Should match the database");
+    assert_eq!(ids[1], ids[2], "Should match earlier items in the batch");
+    assert_eq!(core.get(existing).unwrap().created_at, 2000);
+    assert_eq!(core.get(ids[1]).unwrap().created_at, 4000);
+    assert_eq!(core.search("fresh", 10).unwrap().len(), 1);
+}
+
+#[test]
+fn test_add_items_json_ffi() {
+    let dir = tempdir().unwrap();
+    let db_path = CString::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+    let blobs_dir = CString::new(dir.path().join("blobs").to_str().unwrap()).unwrap();
+
+    unsafe {
+        let handle = osp_core::ffi::core_new(db_path.as_ptr(), blobs_dir.as_ptr());
+        assert!(!handle.is_null());
+
+        let json = CString::new(
+            r#"[
+                {"kind": "text", "content_ref": "first", "created_at": 1},
+                {"kind": "image", "content_ref": "/tmp/a.png", "source_app": "Preview", "created_at": 2, "tags": ["x"]}
+            ]"#,
+        )
+        .unwrap();
+        let out = osp_core::ffi::core_add_items_json(handle, json.as_ptr(), 1);
+        assert!(!out.is_null());
+
+        let ids: Vec<Option<i64>> =
+            serde_json::from_str(CStr::from_ptr(out).to_str().unwrap()).unwrap();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.iter().all(|id| id.is_some()));
+        osp_core::ffi::string_free(out);
+
+        let invalid = CString::new("not json").unwrap();
+        assert!(osp_core::ffi::core_add_items_json(handle, invalid.as_ptr(), 0).is_null());
+
+        osp_core::ffi::core_free(handle);
+    }
+}
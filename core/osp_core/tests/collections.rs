@@ -0,0 +1,164 @@
+//! Tests for collections and their manual ordering.
+
+use osp_core::SearchQuery;
+
+mod common;
+use common::{open, text_item};
+
+fn ids(items: Vec<osp_core::Item>) -> Vec<i64> {
+    items.into_iter().map(|item| item.id).collect()
+}
+
+#[test]
+fn test_create_rename_delete() {
+    let (_dir, core) = open();
+    let replies = core.create_collection("Email replies").unwrap();
+    let deploy = core.create_collection("Deploy commands").unwrap();
+
+    // Names are unique regardless of case, and can't be blank
+    assert!(core.create_collection("deploy COMMANDS").is_err());
+    assert!(core.create_collection("  ").is_err());
+
+    let names: Vec<String> = core.collections().unwrap().into_iter().map(|c| c.name).collect();
+    assert_eq!(names, ["Deploy commands", "Email replies"]);
+
+    core.rename_collection(replies, "Replies").unwrap();
+    assert!(core.rename_collection(replies, "Deploy Commands").is_err());
+    assert!(core.rename_collection(9999, "Missing").is_err());
+
+    // Deleting a collection keeps its items
+    let item = core.add_item(text_item("kubectl rollout status", 1000)).unwrap();
+    core.add_to_collection(deploy, item, None).unwrap();
+    core.delete_collection(deploy).unwrap();
+    assert!(core.get(item).is_ok());
+    assert!(core.item_collections(item).unwrap().is_empty());
+    assert!(core.collection_items(deploy).is_err());
+}
+
+#[test]
+fn test_manual_order() {
+    let (_dir, core) = open();
+    let collection = core.create_collection("Deploy commands").unwrap();
+    let a = core.add_item(text_item("make build", 3000)).unwrap();
+    let b = core.add_item(text_item("make test", 2000)).unwrap();
+    let c = core.add_item(text_item("make release", 1000)).unwrap();
+
+    core.add_to_collection(collection, a, None).unwrap();
+    core.add_to_collection(collection, b, None).unwrap();
+    core.add_to_collection(collection, c, Some(0)).unwrap();
+    assert_eq!(ids(core.collection_items(collection).unwrap()), [c, a, b]);
+
+    core.move_in_collection(collection, c, 2).unwrap();
+    assert_eq!(ids(core.collection_items(collection).unwrap()), [a, b, c]);
+
+    // Past-the-end positions move to the end; adding again moves too
+    core.move_in_collection(collection, a, 99).unwrap();
+    assert_eq!(ids(core.collection_items(collection).unwrap()), [b, c, a]);
+    core.add_to_collection(collection, a, Some(0)).unwrap();
+    assert_eq!(ids(core.collection_items(collection).unwrap()), [a, b, c]);
+
+    core.remove_from_collection(collection, b).unwrap();
+    assert_eq!(ids(core.collection_items(collection).unwrap()), [a, c]);
+    assert!(core.remove_from_collection(collection, b).is_err());
+    assert!(core.move_in_collection(collection, b, 0).is_err());
+    assert!(core.get(b).is_ok());
+}
+
+#[test]
+fn test_item_in_several_collections() {
+    let (_dir, core) = open();
+    let work = core.create_collection("Work").unwrap();
+    let ops = core.create_collection("Ops").unwrap();
+    let shared = core.add_item(text_item("ssh bastion", 1000)).unwrap();
+    let other = core.add_item(text_item("ssh laptop", 2000)).unwrap();
+
+    core.add_to_collection(work, shared, None).unwrap();
+    core.add_to_collection(ops, other, None).unwrap();
+    core.add_to_collection(ops, shared, Some(0)).unwrap();
+
+    assert_eq!(ids(core.collection_items(work).unwrap()), [shared]);
+    assert_eq!(ids(core.collection_items(ops).unwrap()), [shared, other]);
+
+    let names: Vec<String> = core
+        .item_collections(shared)
+        .unwrap()
+        .into_iter()
+        .map(|c| c.name)
+        .collect();
+    assert_eq!(names, ["Ops", "Work"]);
+}
+
+#[test]
+fn test_trashed_and_deleted_items() {
+    let (_dir, core) = open();
+    let collection = core.create_collection("Replies").unwrap();
+    let a = core.add_item(text_item("Thanks!", 1000)).unwrap();
+    let b = core.add_item(text_item("Will do.", 2000)).unwrap();
+    core.add_to_collection(collection, a, None).unwrap();
+    core.add_to_collection(collection, b, None).unwrap();
+
+    // Trashed items are hidden but keep their place
+    core.delete(a).unwrap();
+    assert_eq!(ids(core.collection_items(collection).unwrap()), [b]);
+    assert_eq!(core.collections().unwrap()[0].item_count, 1);
+    assert!(core.add_to_collection(collection, a, None).is_err());
+    core.restore(a).unwrap();
+    assert_eq!(ids(core.collection_items(collection).unwrap()), [a, b]);
+
+    // Permanently deleted items leave the collection
+    core.delete_permanently(a).unwrap();
+    assert_eq!(ids(core.collection_items(collection).unwrap()), [b]);
+}
+
+#[test]
+fn test_positions_skip_trashed_items() {
+    let (_dir, core) = open();
+    let collection = core.create_collection("Snippets").unwrap();
+    let a = core.add_item(text_item("alpha", 1000)).unwrap();
+    let b = core.add_item(text_item("bravo", 2000)).unwrap();
+    let c = core.add_item(text_item("charlie", 3000)).unwrap();
+    let d = core.add_item(text_item("delta", 4000)).unwrap();
+    for item in [a, b, c] {
+        core.add_to_collection(collection, item, None).unwrap();
+    }
+
+    // The UI shows [a, c]; positions index that list
+    core.delete(b).unwrap();
+    assert_eq!(ids(core.collection_items(collection).unwrap()), [a, c]);
+
+    core.move_in_collection(collection, c, 0).unwrap();
+    assert_eq!(ids(core.collection_items(collection).unwrap()), [c, a]);
+    core.move_in_collection(collection, c, 1).unwrap();
+    assert_eq!(ids(core.collection_items(collection).unwrap()), [a, c]);
+
+    core.add_to_collection(collection, d, Some(1)).unwrap();
+    assert_eq!(ids(core.collection_items(collection).unwrap()), [a, d, c]);
+
+    // The trashed item comes back where it was
+    core.restore(b).unwrap();
+    assert_eq!(ids(core.collection_items(collection).unwrap()), [a, b, d, c]);
+}
+
+#[test]
+fn test_search_in_collection() {
+    let (_dir, core) = open();
+    let deploy = core.create_collection("Deploy commands").unwrap();
+    let inside = core.add_item(text_item("kubectl apply -f prod.yaml", 1000)).unwrap();
+    core.add_item(text_item("kubectl get pods", 2000)).unwrap();
+    core.add_to_collection(deploy, inside, None).unwrap();
+
+    assert_eq!(core.search("kubectl", 10).unwrap().len(), 2);
+
+    let query = SearchQuery {
+        collection: Some(deploy),
+        ..SearchQuery::new("kubectl", 10)
+    };
+    assert_eq!(ids(core.search_with(&query).unwrap()), [inside]);
+
+    // Short queries take the LIKE path, which filters the same way
+    let query = SearchQuery {
+        collection: Some(deploy),
+        ..SearchQuery::new("ku", 10)
+    };
+    assert_eq!(ids(core.search_with(&query).unwrap()), [inside]);
+}
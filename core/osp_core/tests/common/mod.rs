@@ -0,0 +1,35 @@
+//! Fixtures shared by the integration tests.
+
+#![allow(dead_code)]
+
+use osp_core::{Core, ItemKind, NewItem};
+use tempfile::{tempdir, TempDir};
+
+/// A `Core` on a new database, `test.db` with its blobs in `blobs`, in a
+/// temporary directory that lives as long as the returned `TempDir`.
+pub fn open() -> (TempDir, Core) {
+    let dir = tempdir().unwrap();
+    let core = Core::open(&dir.path().join("test.db"), &dir.path().join("blobs")).unwrap();
+    (dir, core)
+}
+
+pub fn new_item(kind: ItemKind, content: &str, created_at: i64) -> NewItem {
+    NewItem {
+        kind,
+        content_ref: content.to_string(),
+        source_app: None,
+        created_at,
+        tags: vec![],
+    }
+}
+
+pub fn text_item(content: &str, created_at: i64) -> NewItem {
+    new_item(ItemKind::Text, content, created_at)
+}
+
+pub fn app_item(kind: ItemKind, content: &str, source_app: Option<&str>, created_at: i64) -> NewItem {
+    NewItem {
+        source_app: source_app.map(str::to_string),
+        ..new_item(kind, content, created_at)
+    }
+}
@@ -0,0 +1,99 @@
+//! Concurrency tests: searches and inserts running side by side.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+mod common;
+use common::{open, text_item};
+
+const WRITERS: usize = 4;
+const READERS: usize = 4;
+const INSERTS_PER_WRITER: usize = 250;
+
+/// Generous upper bound so slow CI machines don't flake, while still
+/// catching searches that queue behind the whole insert workload.
+const MAX_SEARCH_LATENCY: Duration = Duration::from_millis(1000);
+
+#[test]
+fn test_concurrent_search_and_insert() {
+    let (_dir, core) = open();
+
+    // Seed some data so searches have work to do
+    for i in 0..1000 {
+        core.add_item(text_item(&format!("seed deploy command {}", i), i))
+            .unwrap();
+    }
+
+    let done = Arc::new(AtomicBool::new(false));
+
+    let writers: Vec<_> = (0..WRITERS)
+        .map(|w| {
+            let core = core.clone();
+            thread::spawn(move || {
+                for i in 0..INSERTS_PER_WRITER {
+                    let created_at = 10_000 + (w * INSERTS_PER_WRITER + i) as i64;
+                    core.add_item(text_item(
+                        &format!("writer {} deploy item {}", w, i),
+                        created_at,
+                    ))
+                    .unwrap();
+                }
+            })
+        })
+        .collect();
+
+    let readers: Vec<_> = (0..READERS)
+        .map(|_| {
+            let core = core.clone();
+            let done = done.clone();
+            thread::spawn(move || {
+                let mut max_latency = Duration::ZERO;
+                let mut searches = 0;
+                while !done.load(Ordering::Relaxed) || searches == 0 {
+                    let start = Instant::now();
+                    let results = core.search("deploy", 50).unwrap();
+                    max_latency = max_latency.max(start.elapsed());
+                    assert!(!results.is_empty());
+                    searches += 1;
+                }
+                (max_latency, searches)
+            })
+        })
+        .collect();
+
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    done.store(true, Ordering::Relaxed);
+
+    for reader in readers {
+        let (max_latency, searches) = reader.join().unwrap();
+        assert!(searches > 0);
+        assert!(
+            max_latency < MAX_SEARCH_LATENCY,
+            "search latency {:?} exceeded bound",
+            max_latency
+        );
+    }
+
+    // Every insert landed
+    let results = core.search("writer", 10_000).unwrap();
+    assert_eq!(results.len(), WRITERS * INSERTS_PER_WRITER);
+}
+
+#[test]
+fn test_reads_see_committed_writes() {
+    let (_dir, core) = open();
+
+    // Each insert must be visible to the very next read, whichever reader
+    // connection serves it.
+    for i in 0..20 {
+        let id = core
+            .add_item(text_item(&format!("visible entry {}", i), i))
+            .unwrap();
+        assert_eq!(core.get(id).unwrap().id, id);
+        assert_eq!(core.search("visible", 100).unwrap().len(), i as usize + 1);
+    }
+}
@@ -0,0 +1,268 @@
+//! Tests for exporting items to JSON Lines, CSV and Markdown.
+
+use osp_core::{Core, ExportFormat, ExportOptions, Item, ItemKind, NewItem, SearchQuery};
+use std::ffi::{CStr, CString};
+use tempfile::tempdir;
+
+mod common;
+use common::{app_item, open};
+
+const DAY: i64 = 24 * 60 * 60 * 1000;
+
+fn text_item(content: &str, created_at: i64) -> NewItem {
+    app_item(ItemKind::Text, content, Some("Terminal"), created_at)
+}
+
+fn export(core: &Core, options: &ExportOptions) -> String {
+    let mut out = Vec::new();
+    core.export(&mut out, options).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+fn options(format: ExportFormat) -> ExportOptions {
+    ExportOptions {
+        format,
+        ..Default::default()
+    }
+}
+
+/// Midday, so the local date is the same in every time zone but the
+/// extremes.
+const NOON: i64 = 1_700_000_000_000 / DAY * DAY + DAY / 2;
+
+#[test]
+fn test_jsonl_round_trips_items() {
+    let (_dir, core) = open();
+    let first = core
+        .add_item(NewItem {
+            tags: vec!["work".to_string(), "ssh".to_string()],
+            ..text_item("ssh deploy@example.com", NOON)
+        })
+        .unwrap();
+    let second = core.add_item(text_item("line one\nline \"two\"", NOON + 1)).unwrap();
+    core.pin(first, true).unwrap();
+    core.annotate(second, Some("Quote"), Some("a note")).unwrap();
+    let trashed = core.add_item(text_item("gone", NOON + 2)).unwrap();
+    core.delete(trashed).unwrap();
+
+    let out = export(&core, &ExportOptions::default());
+    let items: Vec<Item> = out
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(items.len(), 2);
+
+    for item in &items {
+        let stored = core.get(item.id).unwrap();
+        assert_eq!(serde_json::to_value(item).unwrap(), serde_json::to_value(stored).unwrap());
+    }
+    assert_eq!(items[0].id, first);
+    assert!(items[0].pinned);
+    assert_eq!(items[0].tags, ["work", "ssh"]);
+    assert_eq!(items[1].title.as_deref(), Some("Quote"));
+    assert!(!out.contains("\"blob\""));
+}
+
+#[test]
+fn test_export_query() {
+    let (_dir, core) = open();
+    for i in 0..50 {
+        core.add_item(text_item(&format!("kubectl get pods {}", i), NOON + i)).unwrap();
+        core.add_item(app_item(ItemKind::Text, &format!("other {}", i), Some("Safari"), NOON + i))
+            .unwrap();
+    }
+
+    // Paging doesn't apply; items are oldest first
+    let options = ExportOptions {
+        query: Some(SearchQuery::parse("kubectl app:Terminal").unwrap()),
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    let report = core.export(&mut out, &options).unwrap();
+    assert_eq!(report.items, 50);
+    let items: Vec<Item> = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(items.len(), 50);
+    assert!(items.windows(2).all(|w| w[0].created_at < w[1].created_at));
+    assert!(items.iter().all(|item| item.content_ref.starts_with("kubectl")));
+
+    // Regex queries too
+    let options = ExportOptions {
+        query: Some(SearchQuery::regex(r"other [0-4]$")),
+        ..options
+    };
+    assert_eq!(export(&core, &options).lines().count(), 5);
+}
+
+#[test]
+fn test_csv() {
+    let (_dir, core) = open();
+    core.add_item(NewItem {
+        tags: vec!["a".to_string(), "b".to_string()],
+        ..text_item("plain", 1_700_000_000_123)
+    })
+    .unwrap();
+    core.add_item(app_item(ItemKind::Text, "comma, \"quoted\"\nnewline", None, 1_700_000_000_456))
+        .unwrap();
+
+    let out = export(&core, &options(ExportFormat::Csv));
+    let records: Vec<&str> = out.split("\r\n").collect();
+    assert_eq!(
+        records[0],
+        "id,kind,content,source_app,created_at,pinned,tags,title,note,deleted_at,blob"
+    );
+    assert_eq!(
+        records[1],
+        "1,text,plain,Terminal,2023-11-14T22:13:20.123Z,false,\"a, b\",,,,"
+    );
+    assert_eq!(
+        records[2],
+        "2,text,\"comma, \"\"quoted\"\"\nnewline\",,2023-11-14T22:13:20.456Z,false,,,,,"
+    );
+    assert_eq!(records[3], "");
+    assert_eq!(records.len(), 4);
+}
+
+#[test]
+fn test_markdown_grouped_by_day() {
+    let (_dir, core) = open();
+    core.add_item(text_item("day one", NOON)).unwrap();
+    let annotated = core.add_item(text_item("```\ncode\n```", NOON + 60_000)).unwrap();
+    core.annotate(annotated, Some("Snippet"), Some("first line\nsecond line")).unwrap();
+    core.add_item(text_item("day two", NOON + DAY)).unwrap();
+    core.add_item(app_item(ItemKind::Image, "shot.png", None, NOON + DAY + 1)).unwrap();
+
+    let out = export(&core, &options(ExportFormat::Markdown));
+    assert!(out.starts_with("# Clipboard history\n"));
+    let days: Vec<&str> = out.lines().filter(|line| line.starts_with("## ")).collect();
+    assert_eq!(days.len(), 2);
+    assert!(days[0] < days[1]);
+    assert_eq!(out.matches("\n### ").count(), 4);
+    assert!(out.contains(" · Terminal\n"));
+    assert!(out.contains("**Snippet**"));
+    assert!(out.contains("````\n```\ncode\n```\n````\n"));
+    assert!(out.contains("> first line\n> second line\n"));
+    assert!(out.contains("`shot.png`"));
+
+    let day_two = out.find(days[1]).unwrap();
+    assert!(out.find("day one").unwrap() < day_two);
+    assert!(out.find("day two").unwrap() > day_two);
+}
+
+#[test]
+fn test_sidecar_blobs() {
+    let (dir, core) = open();
+    let blobs_dir = dir.path().join("blobs");
+    std::fs::write(blobs_dir.join("shot.png"), [1u8, 2, 3]).unwrap();
+    let external = dir.path().join("report.pdf");
+    std::fs::write(&external, b"%PDF").unwrap();
+
+    let image = core.add_item(app_item(ItemKind::Image, "shot.png", None, NOON)).unwrap();
+    let file = core
+        .add_item(app_item(ItemKind::File, external.to_str().unwrap(), None, NOON + 1))
+        .unwrap();
+    let missing = core.add_item(app_item(ItemKind::Image, "gone.png", None, NOON + 2)).unwrap();
+    core.add_item(text_item("text has no blob", NOON + 3)).unwrap();
+
+    let sidecar_dir = dir.path().join("export-blobs");
+    let options = ExportOptions {
+        sidecar_dir: Some(sidecar_dir.clone()),
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    let report = core.export(&mut out, &options).unwrap();
+    assert_eq!(report.items, 4);
+    assert_eq!(report.blobs, 2);
+    assert_eq!(report.missing_blobs, vec![missing]);
+
+    let image_name = format!("{}-shot.png", image);
+    let file_name = format!("{}-report.pdf", file);
+    assert_eq!(std::fs::read(sidecar_dir.join(&image_name)).unwrap(), [1, 2, 3]);
+    assert_eq!(std::fs::read(sidecar_dir.join(&file_name)).unwrap(), b"%PDF");
+
+    let lines: Vec<serde_json::Value> = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines[0]["blob"], image_name.as_str());
+    assert_eq!(lines[1]["blob"], file_name.as_str());
+    assert!(lines[2].get("blob").is_none());
+    assert!(lines[3].get("blob").is_none());
+
+    let markdown = export(
+        &core,
+        &ExportOptions {
+            format: ExportFormat::Markdown,
+            ..options
+        },
+    );
+    assert!(markdown.contains(&format!(
+        "![{}](<{}>)",
+        image_name,
+        sidecar_dir.join(&image_name).display()
+    )));
+    assert!(markdown.contains(&format!("[{}](<", file_name)));
+}
+
+#[test]
+fn test_export_to_file() {
+    let (dir, core) = open();
+    for result in core.add_items((0..5000).map(|i| text_item(&format!("entry {}", i), i))) {
+        result.unwrap();
+    }
+
+    let path = dir.path().join("history.jsonl");
+    let report = core.export_to(&path, &ExportOptions::default()).unwrap();
+    assert_eq!(report.items, 5000);
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents.lines().count(), 5000);
+
+    assert!(core
+        .export_to(&dir.path().join("missing").join("out.csv"), &ExportOptions::default())
+        .is_err());
+}
+
+#[test]
+fn test_ffi_export() {
+    use osp_core::ffi::*;
+
+    let dir = tempdir().unwrap();
+    let db_path = CString::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+    let blobs_dir = CString::new(dir.path().join("blobs").to_str().unwrap()).unwrap();
+    let out_path = dir.path().join("history.csv");
+    let out = CString::new(out_path.to_str().unwrap()).unwrap();
+
+    unsafe {
+        let handle = core_new(db_path.as_ptr(), blobs_dir.as_ptr());
+        assert!(!handle.is_null());
+        for content in ["alpha", "beta"] {
+            let content = CString::new(content).unwrap();
+            assert!(core_add_item(handle, 0, content.as_ptr(), std::ptr::null(), 1) > 0);
+        }
+
+        let options = CString::new(r#"{"format": "csv", "query": {"text": "beta"}}"#).unwrap();
+        let report = core_export(handle, out.as_ptr(), options.as_ptr());
+        assert!(!report.is_null());
+        let value: serde_json::Value =
+            serde_json::from_str(CStr::from_ptr(report).to_str().unwrap()).unwrap();
+        assert_eq!(value["items"], 1);
+        string_free(report);
+        let csv = std::fs::read_to_string(&out_path).unwrap();
+        assert!(csv.starts_with("id,kind,content"));
+        assert!(csv.contains(",beta,"));
+
+        let report = core_export(handle, out.as_ptr(), std::ptr::null());
+        assert!(!report.is_null());
+        string_free(report);
+        assert_eq!(std::fs::read_to_string(&out_path).unwrap().lines().count(), 2);
+
+        let bad = CString::new(r#"{"format": "pdf"}"#).unwrap();
+        assert!(core_export(handle, out.as_ptr(), bad.as_ptr()).is_null());
+
+        core_free(handle);
+    }
+}
@@ -0,0 +1,165 @@
+//! Tests for faceted result counts.
+
+use chrono::{Local, TimeZone};
+use osp_core::{Core, Count, ItemKind, NewItem, SearchQuery};
+use std::ffi::{CStr, CString};
+use tempfile::tempdir;
+
+mod common;
+use common::{app_item, open};
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+fn new_item(kind: ItemKind, content: &str, source_app: Option<&str>, tags: &[&str], created_at: i64) -> NewItem {
+    NewItem {
+        tags: tags.iter().map(|t| t.to_string()).collect(),
+        ..app_item(kind, content, source_app, created_at)
+    }
+}
+
+fn local_date(ms: i64) -> String {
+    Local.timestamp_millis_opt(ms).unwrap().format("%Y-%m-%d").to_string()
+}
+
+/// Noon on Wednesday 2025-03-05, local time.
+fn wednesday() -> i64 {
+    Local
+        .with_ymd_and_hms(2025, 3, 5, 12, 0, 0)
+        .unwrap()
+        .timestamp_millis()
+}
+
+#[test]
+fn test_facet_counts() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+
+    let wed = wednesday();
+    let core = Core::open(&db_path, &blobs_dir).unwrap();
+    let image = core
+        .add_item(new_item(ItemKind::Image, "/blobs/a.png", Some("Safari"), &["work"], wed))
+        .unwrap();
+    core.annotate(image, Some("Deploy diagram"), None).unwrap();
+    let pinned = core
+        .add_item(new_item(ItemKind::Text, "deploy staging", Some("Terminal"), &["work", "prod"], wed))
+        .unwrap();
+    core.pin(pinned, true).unwrap();
+    core.add_item(new_item(ItemKind::Text, "deploy prod", Some("Terminal"), &[], wed - DAY_MS))
+        .unwrap();
+    core.add_item(new_item(ItemKind::Text, "deploy canary", None, &[], wed - 7 * DAY_MS))
+        .unwrap();
+    core.add_item(new_item(ItemKind::Text, "unrelated", Some("Notes"), &["work"], wed))
+        .unwrap();
+    let trashed = core
+        .add_item(new_item(ItemKind::Text, "deploy old", Some("Terminal"), &[], wed))
+        .unwrap();
+    core.delete(trashed).unwrap();
+
+    let facets = core.facets(&SearchQuery::new("deploy", 10)).unwrap();
+    assert_eq!(facets.total, 4);
+    assert_eq!(facets.pinned, 1);
+    assert_eq!(
+        facets.kinds,
+        [
+            Count { value: ItemKind::Text, count: 3 },
+            Count { value: ItemKind::Image, count: 1 },
+        ]
+    );
+    assert_eq!(
+        facets.apps,
+        [
+            Count { value: Some("Terminal".to_string()), count: 2 },
+            Count { value: None, count: 1 },
+            Count { value: Some("Safari".to_string()), count: 1 },
+        ]
+    );
+    assert_eq!(
+        facets.tags,
+        [
+            Count { value: "work".to_string(), count: 2 },
+            Count { value: "prod".to_string(), count: 1 },
+        ]
+    );
+    assert_eq!(
+        facets.days,
+        [
+            Count { value: local_date(wed), count: 2 },
+            Count { value: local_date(wed - DAY_MS), count: 1 },
+            Count { value: local_date(wed - 7 * DAY_MS), count: 1 },
+        ]
+    );
+    assert_eq!(
+        facets.weeks,
+        [
+            Count { value: "2025-03-03".to_string(), count: 3 },
+            Count { value: "2025-02-24".to_string(), count: 1 },
+        ]
+    );
+}
+
+#[test]
+fn test_facets_follow_filters_and_modes() {
+    let (_dir, core) = open();
+    core.add_item(new_item(ItemKind::Text, "export KUBECONFIG=~/.kube/kubeconfig", Some("Terminal"), &[], 1000))
+        .unwrap();
+    core.add_item(new_item(ItemKind::Text, "kubectl get pods", Some("Terminal"), &[], 2000))
+        .unwrap();
+    core.add_item(new_item(ItemKind::File, "/tmp/kubectl.tar.gz", Some("Finder"), &[], 3000))
+        .unwrap();
+
+    let facets = core.facets(&SearchQuery::parse("kubectl kind:text").unwrap()).unwrap();
+    assert_eq!(facets.total, 1);
+
+    // Falls back to substrings like the search does
+    let facets = core.facets(&SearchQuery::new("config", 10)).unwrap();
+    assert_eq!(facets.total, 1);
+
+    let facets = core.facets(&SearchQuery::regex(r"kube(ctl|config)")).unwrap();
+    assert_eq!(facets.total, 3);
+    assert_eq!(facets.apps[0], Count { value: Some("Terminal".to_string()), count: 2 });
+
+    let facets = core
+        .facets(&SearchQuery {
+            mode: osp_core::SearchMode::Fuzzy,
+            ..SearchQuery::new("kubctl", 1)
+        })
+        .unwrap();
+    assert_eq!(facets.total, 2);
+
+    let facets = core.facets(&SearchQuery::new("nothing matches this", 10)).unwrap();
+    assert_eq!(facets.total, 0);
+    assert!(facets.kinds.is_empty() && facets.days.is_empty());
+}
+
+#[test]
+fn test_ffi_facets() {
+    let dir = tempdir().unwrap();
+    let db_path = CString::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+    let blobs_dir = CString::new(dir.path().join("blobs").to_str().unwrap()).unwrap();
+
+    unsafe {
+        let handle = osp_core::ffi::core_new(db_path.as_ptr(), blobs_dir.as_ptr());
+        assert!(!handle.is_null());
+
+        let content = CString::new("ssh prod-db").unwrap();
+        let app = CString::new("Terminal").unwrap();
+        osp_core::ffi::core_add_item(handle, 0, content.as_ptr(), app.as_ptr(), 1000);
+
+        let query = CString::new(r#"{"text": "ssh"}"#).unwrap();
+        let json = osp_core::ffi::core_facets_json(handle, query.as_ptr());
+        assert!(!json.is_null());
+        let value: serde_json::Value =
+            serde_json::from_str(CStr::from_ptr(json).to_str().unwrap()).unwrap();
+        osp_core::ffi::string_free(json);
+
+        assert_eq!(value["total"], 1);
+        assert_eq!(value["kinds"][0]["value"], "text");
+        assert_eq!(value["apps"][0], serde_json::json!({"value": "Terminal", "count": 1}));
+
+        let bad = CString::new("not json").unwrap();
+        assert!(osp_core::ffi::core_facets_json(handle, bad.as_ptr()).is_null());
+
+        osp_core::ffi::core_free(handle);
+    }
+}
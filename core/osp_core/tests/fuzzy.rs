@@ -0,0 +1,147 @@
+//! Tests for fuzzy, typo-tolerant search.
+
+use osp_core::{Core, ItemKind, SearchMode, SearchQuery};
+use std::ffi::CString;
+use tempfile::tempdir;
+
+mod common;
+use common::{new_item, open};
+
+fn fuzzy(core: &Core, text: &str) -> Vec<i64> {
+    let query = SearchQuery {
+        mode: SearchMode::Fuzzy,
+        ..SearchQuery::new(text, 10)
+    };
+    core.search_with(&query)
+        .unwrap()
+        .into_iter()
+        .map(|item| item.id)
+        .collect()
+}
+
+#[test]
+fn test_fuzzy_subsequence() {
+    let (_dir, core) = open();
+    let hello = core.add_item(new_item(ItemKind::Text, "hello world", 1000)).unwrap();
+    core.add_item(new_item(ItemKind::Text, "goodbye", 2000)).unwrap();
+
+    assert_eq!(fuzzy(&core, "hlo"), [hello]);
+    assert_eq!(fuzzy(&core, "HLW"), [hello]);
+    assert!(fuzzy(&core, "xyz").is_empty());
+    assert!(fuzzy(&core, "  ").is_empty());
+}
+
+#[test]
+fn test_fuzzy_substring_and_typos() {
+    let (_dir, core) = open();
+    let kubeconfig = core
+        .add_item(new_item(ItemKind::Text, "export KUBECONFIG=~/.kube/kubeconfig", 1000))
+        .unwrap();
+    let kubectl = core
+        .add_item(new_item(ItemKind::Text, "kubectl get pods -n prod", 2000))
+        .unwrap();
+
+    // Standard search doesn't correct typos
+    assert!(core.search("kubctl", 10).unwrap().is_empty());
+
+    assert_eq!(fuzzy(&core, "config"), [kubeconfig]);
+    assert_eq!(fuzzy(&core, "kubctl")[0], kubectl);
+    assert_eq!(fuzzy(&core, "kubctl get pdos")[0], kubectl);
+}
+
+#[test]
+fn test_fuzzy_ranks_closer_matches_first() {
+    let (_dir, core) = open();
+    let exact = core.add_item(new_item(ItemKind::Text, "git push origin", 1000)).unwrap();
+    let scattered = core
+        .add_item(new_item(ItemKind::Text, "go install tool", 2000))
+        .unwrap();
+
+    assert_eq!(fuzzy(&core, "git"), [exact, scattered]);
+}
+
+#[test]
+fn test_fuzzy_matches_paths_and_titles() {
+    let (_dir, core) = open();
+    let file = core
+        .add_item(new_item(ItemKind::File, "/Users/me/Documents/quarterly-report.pdf", 1000))
+        .unwrap();
+    let image = core
+        .add_item(new_item(ItemKind::Image, "/blobs/5f3a.png", 2000))
+        .unwrap();
+    core.annotate(image, Some("Architecture diagram"), None).unwrap();
+
+    assert_eq!(fuzzy(&core, "qtrly"), [file]);
+    assert_eq!(fuzzy(&core, "archdiag"), [image]);
+    assert_eq!(fuzzy(&core, "diagarm"), [image]);
+}
+
+#[test]
+fn test_fuzzy_respects_filters() {
+    let (_dir, core) = open();
+    let trashed = core.add_item(new_item(ItemKind::Text, "deploy staging", 1000)).unwrap();
+    let kept = core.add_item(new_item(ItemKind::Text, "deploy production", 2000)).unwrap();
+    let other = core.add_item(new_item(ItemKind::Text, "deploy preview", 3000)).unwrap();
+    core.delete(trashed).unwrap();
+
+    assert_eq!(fuzzy(&core, "dploy"), [other, kept]);
+
+    let collection = core.create_collection("Releases").unwrap();
+    core.add_to_collection(collection, kept, None).unwrap();
+
+    let query = SearchQuery {
+        mode: SearchMode::Fuzzy,
+        collection: Some(collection),
+        ..SearchQuery::new("dploy", 10)
+    };
+    let results = core.search_with(&query).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id, kept);
+}
+
+#[test]
+fn test_fuzzy_searches_whole_history() {
+    let (_dir, core) = open();
+    let old = core
+        .add_item(new_item(ItemKind::Text, "ssh-keygen -t ed25519", 1))
+        .unwrap();
+    core.add_items((0..1000).map(|i| new_item(ItemKind::Text, &format!("note {}", i), 1000 + i)))
+        .into_iter()
+        .for_each(|result| {
+            result.unwrap();
+        });
+
+    assert_eq!(fuzzy(&core, "sshkey"), [old]);
+    assert_eq!(fuzzy(&core, "ssh-keygne")[0], old);
+}
+
+#[test]
+fn test_ffi_fuzzy_search() {
+    let dir = tempdir().unwrap();
+    let db_path = CString::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+    let blobs_dir = CString::new(dir.path().join("blobs").to_str().unwrap()).unwrap();
+
+    unsafe {
+        let handle = osp_core::ffi::core_new(db_path.as_ptr(), blobs_dir.as_ptr());
+        assert!(!handle.is_null());
+
+        let content = CString::new("kubectl get pods").unwrap();
+        let id = osp_core::ffi::core_add_item(handle, 0, content.as_ptr(), std::ptr::null(), 1000);
+        assert!(id > 0);
+
+        let query = CString::new("kubctl").unwrap();
+        let results = osp_core::ffi::core_search_mode(handle, query.as_ptr(), 1, 10);
+        assert!(!results.is_null());
+        assert_eq!((*results).count, 1);
+        assert_eq!((*(*results).items).id, id);
+        osp_core::ffi::item_array_free(results);
+
+        let query = CString::new(r#"{"text": "kgp", "mode": "fuzzy"}"#).unwrap();
+        let results = osp_core::ffi::core_search_query(handle, query.as_ptr());
+        assert!(!results.is_null());
+        assert_eq!((*results).count, 1);
+        osp_core::ffi::item_array_free(results);
+
+        osp_core::ffi::core_free(handle);
+    }
+}
@@ -0,0 +1,372 @@
+//! Tests for importing history from other clipboard managers, using the
+//! fixture files in `tests/fixtures/import`.
+
+use osp_core::{Core, ImportFormat, ImportOptions, Item, ItemKind, SearchQuery, SortOrder};
+use std::ffi::{CStr, CString};
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+mod common;
+use common::open;
+
+/// Seconds from the Unix epoch to Maccy's reference date, 2001-01-01.
+const MACCY_REFERENCE_DATE: i64 = 978_307_200;
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/import")
+        .join(name)
+}
+
+/// Every item, newest first.
+fn all_items(core: &Core) -> Vec<Item> {
+    core.search_with(&SearchQuery {
+        sort: SortOrder::Recent,
+        ..SearchQuery::new("", 1000)
+    })
+    .unwrap()
+}
+
+fn find<'a>(items: &'a [Item], content: &str) -> &'a Item {
+    items
+        .iter()
+        .find(|item| item.content_ref == content)
+        .unwrap_or_else(|| panic!("{:?} not imported", content))
+}
+
+#[test]
+fn test_import_maccy() {
+    let (dir, core) = open();
+    let report = core
+        .import(&fixture("maccy.sqlite"), &ImportOptions::new(ImportFormat::Maccy))
+        .unwrap();
+    assert_eq!(report.format, ImportFormat::Maccy);
+    assert_eq!(report.imported, 4, "{:?}", report);
+    assert_eq!(report.duplicates, 1);
+    assert_eq!(report.skipped, 1);
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+
+    let items = all_items(&core);
+    assert_eq!(items.len(), 4);
+
+    // The last copy time, with the source app's bundle ID
+    let text = find(&items, "git status");
+    assert_eq!(text.kind, ItemKind::Text);
+    assert_eq!(text.created_at, (700_000_300 + MACCY_REFERENCE_DATE) * 1000 + 250);
+    assert_eq!(text.source_app.as_deref(), Some("com.apple.Terminal"));
+
+    let file = find(&items, "/Users/me/Documents/Quarterly Report.pdf");
+    assert_eq!(file.kind, ItemKind::File);
+    assert_eq!(file.source_app.as_deref(), Some("com.apple.finder"));
+
+    let ssh = find(&items, "ssh deploy@prod");
+    assert_eq!(ssh.created_at, (699_999_000 + MACCY_REFERENCE_DATE) * 1000);
+    assert_eq!(ssh.source_app, None);
+
+    let image = items.iter().find(|item| item.kind == ItemKind::Image).unwrap();
+    assert!(image.content_ref.starts_with("imported/"));
+    assert_eq!(
+        std::fs::read(dir.path().join("blobs").join(&image.content_ref)).unwrap(),
+        std::fs::read(fixture("gpaste-image.png")).unwrap()
+    );
+
+    // The fixture is opened read-only
+    let conn = rusqlite::Connection::open(fixture("maccy.sqlite")).unwrap();
+    let count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM ZHISTORYITEM", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(count, 6);
+}
+
+#[test]
+fn test_import_copyq_tab() {
+    let (_dir, core) = open();
+    let report = core
+        .import(&fixture("copyq_tab_clipboard.dat"), &ImportOptions::new(ImportFormat::CopyQ))
+        .unwrap();
+    assert_eq!(report.imported, 5, "{:?}", report);
+    assert_eq!(report.skipped, 1);
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+
+    // Newest first in the tab
+    let items = all_items(&core);
+    let contents: Vec<&str> = items.iter().map(|item| item.content_ref.as_str()).collect();
+    assert_eq!(contents[0], "kubectl get pods");
+    assert_eq!(contents[2], "/home/me/photo.jpg");
+    assert_eq!(contents[4], "legacy item");
+
+    assert_eq!(items[0].tags, ["work", "k8s"]);
+    assert!(items[1].content_ref.starts_with("compressed compressed"));
+    assert_eq!(items[1].content_ref.len(), 439);
+    assert_eq!(items[2].kind, ItemKind::File);
+    assert_eq!(items[3].kind, ItemKind::Image);
+}
+
+#[test]
+fn test_import_copyq_export() {
+    let (_dir, core) = open();
+    core.import(&fixture("copyq_tab_clipboard.dat"), &ImportOptions::new(ImportFormat::CopyQ))
+        .unwrap();
+
+    let report = core
+        .import(&fixture("copyq_export.cpq"), &ImportOptions::new(ImportFormat::CopyQ))
+        .unwrap();
+    assert_eq!(report.imported, 1, "{:?}", report);
+    assert_eq!(report.duplicates, 1);
+    assert_eq!(core.search("meeting", 10).unwrap().len(), 1);
+
+    // Not CopyQ data at all
+    assert!(core
+        .import(&fixture("list.txt"), &ImportOptions::new(ImportFormat::CopyQ))
+        .is_err());
+}
+
+#[test]
+fn test_import_gpaste() {
+    let (dir, core) = open();
+
+    // The history refers to images by absolute path
+    let history = std::fs::read_to_string(fixture("gpaste-history.xml"))
+        .unwrap()
+        .replace("@FIXTURES@", fixture("").to_str().unwrap().trim_end_matches('/'));
+    let path = dir.path().join("history.xml");
+    std::fs::write(&path, history).unwrap();
+
+    let options = ImportOptions {
+        source_app: Some("GPaste".to_string()),
+        ..ImportOptions::new(ImportFormat::GPaste)
+    };
+    let report = core.import(&path, &options).unwrap();
+    assert_eq!(report.imported, 5, "{:?}", report);
+    assert_eq!(report.duplicates, 1);
+    assert_eq!(report.skipped, 1, "the password is not imported");
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert!(core.search("hunter2", 10).unwrap().is_empty());
+
+    let items = all_items(&core);
+    assert!(items.iter().all(|item| item.source_app.as_deref() == Some("GPaste")));
+    assert_eq!(find(&items, "/home/me/notes.txt").kind, ItemKind::File);
+    assert_eq!(find(&items, "/home/me/todo.md").kind, ItemKind::File);
+    find(&items, "multi-line\ntext &amp; <markup>");
+
+    let image = items.iter().find(|item| item.kind == ItemKind::Image).unwrap();
+    assert_eq!(image.created_at, 1_700_000_000_000);
+
+    // The first text is the newest and keeps its time over its duplicate
+    let echo = find(&items, "echo \"hello\" > /tmp/out");
+    assert!(items
+        .iter()
+        .filter(|item| item.kind != ItemKind::Image)
+        .all(|item| item.created_at <= echo.created_at));
+}
+
+#[test]
+fn test_import_gpaste_missing_image() {
+    let (_dir, core) = open();
+    let report = core
+        .import(&fixture("gpaste-history.xml"), &ImportOptions::new(ImportFormat::GPaste))
+        .unwrap();
+    assert_eq!(report.imported, 4);
+    assert_eq!(report.errors.len(), 1);
+    assert!(report.errors[0].contains("@FIXTURES@"), "{:?}", report.errors);
+
+    assert!(core
+        .import(&fixture("list.json"), &ImportOptions::new(ImportFormat::GPaste))
+        .is_err());
+}
+
+#[test]
+fn test_import_gpaste_date_out_of_range() {
+    let (dir, core) = open();
+    let path = dir.path().join("history.xml");
+    std::fs::write(
+        &path,
+        r#"<history version="2.0">
+  <item kind="Text" date="9223372036854775807"><value>far future</value></item>
+  <item kind="Text"><value>undated</value></item>
+</history>"#,
+    )
+    .unwrap();
+
+    let report = core.import(&path, &ImportOptions::new(ImportFormat::GPaste)).unwrap();
+    assert_eq!(report.imported, 1);
+    assert_eq!(report.errors.len(), 1);
+    assert!(report.errors[0].starts_with("Item 1:"), "{:?}", report.errors);
+}
+
+#[test]
+fn test_import_json() {
+    let (_dir, core) = open();
+    let options = ImportOptions {
+        tags: vec!["imported".to_string()],
+        ..ImportOptions::new(ImportFormat::Json)
+    };
+    let report = core.import(&fixture("list.json"), &options).unwrap();
+    assert_eq!(report.imported, 3, "{:?}", report);
+    assert_eq!(report.duplicates, 1);
+    assert_eq!(report.skipped, 1);
+    assert_eq!(report.errors.len(), 2, "{:?}", report.errors);
+    assert!(report.errors[0].starts_with("Entry 4:"));
+
+    let items = all_items(&core);
+    let object = find(&items, "object with seconds");
+    assert_eq!(object.created_at, 1_700_000_000_000);
+    assert_eq!(object.source_app.as_deref(), Some("Notes"));
+    assert_eq!(object.tags, ["notes", "imported"]);
+    let file = find(&items, "/Users/me/report.pdf");
+    assert_eq!(file.kind, ItemKind::File);
+    assert_eq!(file.created_at, 1_700_000_000_000);
+    assert_eq!(find(&items, "first plain string").tags, ["imported"]);
+}
+
+#[test]
+fn test_import_jsonl_export() {
+    let (dir, core) = open();
+    let report = core
+        .import(&fixture("history.jsonl"), &ImportOptions::new(ImportFormat::Json))
+        .unwrap();
+    assert_eq!(report.imported, 3, "{:?}", report);
+    assert_eq!(report.errors.len(), 1);
+    assert!(report.errors[0].contains("Line 4"), "{:?}", report.errors);
+
+    let items = all_items(&core);
+    let tests = find(&items, "cargo test --workspace");
+    assert_eq!(tests.created_at, 1_700_000_000_123);
+    assert_eq!(tests.source_app.as_deref(), Some("Terminal"));
+    assert_eq!(tests.tags, ["rust"]);
+    assert_eq!(find(&items, "screenshot.png").kind, ItemKind::Image);
+
+    // An export of this app imports back without changes
+    let mut out = Vec::new();
+    core.export(&mut out, &Default::default()).unwrap();
+    let path = dir.path().join("export.jsonl");
+    std::fs::write(&path, out).unwrap();
+    let report = core.import(&path, &ImportOptions::new(ImportFormat::Json)).unwrap();
+    assert_eq!((report.imported, report.duplicates), (0, 3));
+    assert_eq!(all_items(&core).len(), 3);
+
+    // and into a new history with its title, note and pin
+    core.annotate(find(&items, "screenshot.png").id, None, Some("login page"))
+        .unwrap();
+    let mut out = Vec::new();
+    core.export(&mut out, &Default::default()).unwrap();
+    std::fs::write(&path, out).unwrap();
+
+    let (_dir, fresh) = open();
+    let report = fresh.import(&path, &ImportOptions::new(ImportFormat::Json)).unwrap();
+    assert_eq!(report.imported, 3, "{:?}", report);
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+
+    let imported = all_items(&fresh);
+    let tests = find(&imported, "cargo test --workspace");
+    assert!(tests.pinned);
+    assert_eq!(tests.title.as_deref(), Some("Run tests"));
+    assert_eq!(find(&imported, "screenshot.png").note.as_deref(), Some("login page"));
+    assert!(!find(&imported, "https://example.com/docs").pinned);
+    assert_eq!(fresh.search("title:tests", 10).unwrap().len(), 1);
+}
+
+#[test]
+fn test_import_plain_text() {
+    let (_dir, core) = open();
+    let report = core
+        .import(&fixture("list.txt"), &ImportOptions::new(ImportFormat::PlainText))
+        .unwrap();
+    assert_eq!(report.imported, 3);
+
+    let contents: Vec<String> = all_items(&core).into_iter().map(|item| item.content_ref).collect();
+    assert_eq!(contents, ["alpha", "beta", "gamma"]);
+
+    let options = ImportOptions {
+        separator: Some("\n---\n".to_string()),
+        ..ImportOptions::new(ImportFormat::PlainText)
+    };
+    let report = core.import(&fixture("records.txt"), &options).unwrap();
+    assert_eq!(report.imported, 2);
+    let records = core.search("record", 10).unwrap();
+    assert_eq!(records.len(), 2);
+    find(&records, "first record\nwith two lines");
+    find(&records, "second record");
+}
+
+#[test]
+fn test_import_dedupes_across_sources() {
+    let (_dir, core) = open();
+    core.add_item(osp_core::NewItem {
+        kind: ItemKind::Text,
+        content_ref: "git status".to_string(),
+        source_app: Some("Terminal".to_string()),
+        created_at: i64::MAX / 2,
+        tags: vec![],
+    })
+    .unwrap();
+
+    let report = core
+        .import(&fixture("maccy.sqlite"), &ImportOptions::new(ImportFormat::Maccy))
+        .unwrap();
+    assert_eq!((report.imported, report.duplicates), (3, 2));
+
+    // Imported duplicates don't make existing items older
+    let existing = core.search("git status", 10).unwrap();
+    assert_eq!(existing.len(), 1);
+    assert_eq!(existing[0].created_at, i64::MAX / 2);
+
+    // The same image, stored under the same name
+    let report = core
+        .import(&fixture("copyq_tab_clipboard.dat"), &ImportOptions::new(ImportFormat::CopyQ))
+        .unwrap();
+    assert_eq!((report.imported, report.duplicates), (4, 1));
+
+    // Importing twice adds nothing
+    let report = core
+        .import(&fixture("maccy.sqlite"), &ImportOptions::new(ImportFormat::Maccy))
+        .unwrap();
+    assert_eq!((report.imported, report.duplicates), (0, 5));
+}
+
+#[test]
+fn test_import_missing_file() {
+    let (dir, core) = open();
+    for format in [
+        ImportFormat::Maccy,
+        ImportFormat::CopyQ,
+        ImportFormat::GPaste,
+        ImportFormat::Json,
+        ImportFormat::PlainText,
+    ] {
+        let missing = dir.path().join("missing");
+        assert!(core.import(&missing, &ImportOptions::new(format)).is_err(), "{:?}", format);
+    }
+}
+
+#[test]
+fn test_ffi_import() {
+    use osp_core::ffi::*;
+
+    let dir = tempdir().unwrap();
+    let db_path = CString::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+    let blobs_dir = CString::new(dir.path().join("blobs").to_str().unwrap()).unwrap();
+    let path = CString::new(fixture("maccy.sqlite").to_str().unwrap()).unwrap();
+
+    unsafe {
+        let handle = core_new(db_path.as_ptr(), blobs_dir.as_ptr());
+        assert!(!handle.is_null());
+
+        let options = CString::new(r#"{"format": "maccy", "tags": ["maccy"]}"#).unwrap();
+        let report = core_import(handle, path.as_ptr(), options.as_ptr());
+        assert!(!report.is_null());
+        let value: serde_json::Value =
+            serde_json::from_str(CStr::from_ptr(report).to_str().unwrap()).unwrap();
+        assert_eq!(value["format"], "maccy");
+        assert_eq!(value["imported"], 4);
+        assert_eq!(value["duplicates"], 1);
+        string_free(report);
+
+        // The format is required
+        let options = CString::new(r#"{"tags": ["maccy"]}"#).unwrap();
+        assert!(core_import(handle, path.as_ptr(), options.as_ptr()).is_null());
+        assert!(core_import(handle, path.as_ptr(), std::ptr::null()).is_null());
+
+        core_free(handle);
+    }
+}
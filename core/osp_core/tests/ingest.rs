@@ -0,0 +1,139 @@
+//! Tests for the background ingestion worker.
+
+use osp_core::{CaptureRules, Config, Core, IngestConfig, NewItem};
+use tempfile::tempdir;
+
+mod common;
+use common::{open, text_item};
+
+fn ingest_config(flush_interval_ms: u64, max_batch_items: usize) -> Config {
+    Config {
+        ingest: Some(IngestConfig {
+            flush_interval_ms,
+            max_batch_items,
+        }),
+        ..Config::default()
+    }
+}
+
+#[test]
+fn test_enqueue_and_flush() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+
+    // Long interval so only the batch size and flush() trigger writes
+    let core = Core::open_with_config(&db_path, &blobs_dir, ingest_config(60_000, 100)).unwrap();
+
+    for i in 0..250 {
+        core.enqueue(text_item(&format!("queued item {}", i), i)).unwrap();
+    }
+    core.flush().unwrap();
+
+    let results = core.search("queued", 1000).unwrap();
+    assert_eq!(results.len(), 250);
+}
+
+#[test]
+fn test_enqueue_dedupes_within_batch() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+
+    let core = Core::open_with_config(&db_path, &blobs_dir, ingest_config(60_000, 100)).unwrap();
+
+    core.add_item(text_item("already stored", 1000)).unwrap();
+
+    core.enqueue(text_item("burst value", 2000)).unwrap();
+    core.enqueue(text_item("  burst   value ", 3000)).unwrap();
+    core.enqueue(text_item("already stored", 4000)).unwrap();
+    core.flush().unwrap();
+
+    let results = core.search("burst", 10).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].created_at, 3000, "Duplicate should bump timestamp");
+
+    let results = core.search("already", 10).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].created_at, 4000);
+}
+
+#[test]
+fn test_interval_flush() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+
+    let core = Core::open_with_config(&db_path, &blobs_dir, ingest_config(10, 1000)).unwrap();
+    core.enqueue(text_item("timed write", 1000)).unwrap();
+
+    // Written by the timer without an explicit flush
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while core.search("timed", 10).unwrap().is_empty() {
+        assert!(std::time::Instant::now() < deadline, "Batch was never written");
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn test_shutdown_writes_pending_items() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+
+    let core = Core::open_with_config(&db_path, &blobs_dir, ingest_config(60_000, 1000)).unwrap();
+    for i in 0..10 {
+        core.enqueue(text_item(&format!("pending item {}", i), i)).unwrap();
+    }
+    core.shutdown();
+    assert!(core.enqueue(text_item("too late", 99)).is_err());
+    drop(core);
+
+    let core = Core::open(&db_path, &blobs_dir).unwrap();
+    assert_eq!(core.search("pending", 100).unwrap().len(), 10);
+}
+
+#[test]
+fn test_enqueue_without_worker() {
+    let (_dir, core) = open();
+    core.enqueue(text_item("direct write", 1000)).unwrap();
+
+    // Written synchronously; flush is a no-op
+    assert_eq!(core.search("direct", 10).unwrap().len(), 1);
+    core.flush().unwrap();
+}
+
+#[test]
+fn test_capture_rules() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+
+    let config = Config {
+        capture: CaptureRules {
+            ignored_apps: vec!["com.1password.1password".to_string()],
+            max_text_bytes: Some(64),
+            ..CaptureRules::default()
+        },
+        ..ingest_config(60_000, 100)
+    };
+    let core = Core::open_with_config(&db_path, &blobs_dir, config).unwrap();
+
+    core.enqueue(NewItem {
+        source_app: Some("com.1Password.1password".to_string()),
+        ..text_item("secret captured", 1000)
+    })
+    .unwrap();
+    core.enqueue(text_item(" \n\t ", 2000)).unwrap();
+    core.enqueue(text_item(&format!("captured {}", "x".repeat(64)), 3000)).unwrap();
+    core.enqueue(NewItem {
+        source_app: Some("com.apple.Terminal".to_string()),
+        ..text_item("kept captured", 4000)
+    })
+    .unwrap();
+    core.flush().unwrap();
+
+    let results = core.search("captured", 10).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].content_ref, "kept captured");
+}
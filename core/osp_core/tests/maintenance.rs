@@ -0,0 +1,192 @@
+//! Tests for database maintenance.
+
+use osp_core::{Core, MaintenanceOptions, MaintenanceStep};
+use rusqlite::Connection;
+use std::ffi::{c_void, CStr, CString};
+use std::path::Path;
+use tempfile::tempdir;
+
+mod common;
+use common::{open, text_item};
+
+fn ids(items: &[osp_core::Item]) -> Vec<i64> {
+    items.iter().map(|item| item.id).collect()
+}
+
+/// Fill the database, then delete most of it for good.
+fn churn(core: &Core) -> i64 {
+    let keep = core
+        .add_item(text_item("keep this deployment note", 1))
+        .unwrap();
+    let filler = "lorem ipsum dolor sit amet ".repeat(40);
+    for result in core.add_items((0..2000).map(|i| text_item(&format!("{} {}", filler, i), 2 + i))) {
+        result.unwrap();
+    }
+    core.pin(keep, true).unwrap();
+    core.clear_history().unwrap();
+    core.empty_trash().unwrap();
+    keep
+}
+
+fn auto_vacuum(db_path: &Path) -> i64 {
+    Connection::open(db_path)
+        .unwrap()
+        .query_row("PRAGMA auto_vacuum", [], |row| row.get(0))
+        .unwrap()
+}
+
+#[test]
+fn test_maintenance_compacts() {
+    let (dir, core) = open();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+    let keep = churn(&core);
+
+    let report = core.maintenance(&MaintenanceOptions::default()).unwrap();
+    assert!(report.is_healthy(), "{:?}", report);
+    assert_eq!(
+        report.steps,
+        vec![
+            MaintenanceStep::IntegrityCheck,
+            MaintenanceStep::FtsIntegrityCheck,
+            MaintenanceStep::FtsOptimize,
+            MaintenanceStep::Vacuum,
+            MaintenanceStep::Analyze,
+            MaintenanceStep::Checkpoint,
+        ]
+    );
+    assert!(report.bytes_freed > 100_000, "{}", report.bytes_freed);
+
+    let checkpoint = report.checkpoint.unwrap();
+    assert!(!checkpoint.busy);
+    assert_eq!(checkpoint.wal_frames, checkpoint.checkpointed_frames);
+    assert_eq!(std::fs::metadata(dir.path().join("test.db-wal")).unwrap().len(), 0);
+
+    assert_eq!(ids(&core.search("deployment", 10).unwrap()), vec![keep]);
+    assert_eq!(ids(&core.search("*ployme", 10).unwrap()), vec![keep]);
+
+    // The first vacuum switched to incremental auto-vacuum
+    drop(core);
+    assert_eq!(auto_vacuum(&db_path), 2);
+
+    // Later runs release free pages incrementally
+    let core = Core::open(&db_path, &blobs_dir).unwrap();
+    let kept_again = churn(&core);
+    let report = core.maintenance(&MaintenanceOptions::default()).unwrap();
+    assert!(report.bytes_freed > 100_000, "{}", report.bytes_freed);
+    let mut found = ids(&core.search("deployment", 10).unwrap());
+    found.sort();
+    assert_eq!(found, vec![keep, kept_again]);
+}
+
+#[test]
+fn test_maintenance_progress() {
+    let (_dir, core) = open();
+    let id = core.add_item(text_item("hello world", 1)).unwrap();
+
+    let options = MaintenanceOptions {
+        integrity_check: false,
+        fts_rebuild: true,
+        vacuum: false,
+        checkpoint: false,
+        ..Default::default()
+    };
+    let mut seen = Vec::new();
+    let report = core
+        .maintenance_with_progress(&options, |progress| {
+            seen.push((progress.step, progress.index, progress.total))
+        })
+        .unwrap();
+
+    assert_eq!(
+        seen,
+        vec![
+            (MaintenanceStep::FtsIntegrityCheck, 0, 4),
+            (MaintenanceStep::FtsRebuild, 1, 4),
+            (MaintenanceStep::FtsOptimize, 2, 4),
+            (MaintenanceStep::Analyze, 3, 4),
+        ]
+    );
+    assert_eq!(report.steps.len(), 4);
+    assert!(report.checkpoint.is_none());
+    assert_eq!(report.bytes_freed, 0);
+    assert_eq!(ids(&core.search("hello", 10).unwrap()), vec![id]);
+}
+
+#[test]
+fn test_maintenance_repairs_fts() {
+    let (dir, core) = open();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+    let id = core.add_item(text_item("hello world", 1)).unwrap();
+    drop(core);
+
+    // Lose the index while keeping the indexed text
+    Connection::open(&db_path)
+        .unwrap()
+        .execute("DELETE FROM items_fts_data WHERE id > 10", [])
+        .unwrap();
+
+    let core = Core::open(&db_path, &blobs_dir).unwrap();
+    let report = core.maintenance(&MaintenanceOptions::default()).unwrap();
+    assert!(report.integrity_errors.is_empty(), "{:?}", report);
+    assert_eq!(report.fts_errors.len(), 1);
+    assert!(report.fts_errors[0].starts_with("items_fts: "));
+    assert!(!report.is_healthy());
+
+    let options = MaintenanceOptions {
+        fts_rebuild: true,
+        ..Default::default()
+    };
+    core.maintenance(&options).unwrap();
+    let report = core.maintenance(&MaintenanceOptions::default()).unwrap();
+    assert!(report.is_healthy(), "{:?}", report);
+    assert_eq!(ids(&core.search("hello", 10).unwrap()), vec![id]);
+}
+
+extern "C" fn record_progress(step: i32, index: i32, total: i32, user_data: *mut c_void) {
+    let seen = unsafe { &mut *(user_data as *mut Vec<(i32, i32, i32)>) };
+    seen.push((step, index, total));
+}
+
+#[test]
+fn test_ffi_maintenance_json() {
+    use osp_core::ffi::*;
+
+    let dir = tempdir().unwrap();
+    let db_path = CString::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+    let blobs_dir = CString::new(dir.path().join("blobs").to_str().unwrap()).unwrap();
+    let options = CString::new(r#"{"integrity_check": false, "vacuum": false}"#).unwrap();
+    let malformed = CString::new(r#"{"vacuum": "yes"}"#).unwrap();
+
+    unsafe {
+        let handle = core_new(db_path.as_ptr(), blobs_dir.as_ptr());
+        assert!(!handle.is_null());
+
+        let mut seen: Vec<(i32, i32, i32)> = Vec::new();
+        let json = core_maintenance_json(
+            handle,
+            options.as_ptr(),
+            Some(record_progress),
+            &mut seen as *mut _ as *mut c_void,
+        );
+        assert!(!json.is_null());
+        let value: serde_json::Value =
+            serde_json::from_str(CStr::from_ptr(json).to_str().unwrap()).unwrap();
+        assert_eq!(
+            value["steps"],
+            serde_json::json!(["fts_integrity_check", "fts_optimize", "analyze", "checkpoint"])
+        );
+        assert_eq!(value["checkpoint"]["busy"], false);
+        string_free(json);
+        assert_eq!(seen, vec![(1, 0, 4), (3, 1, 4), (5, 2, 4), (6, 3, 4)]);
+
+        let json = core_maintenance_json(handle, std::ptr::null(), None, std::ptr::null_mut());
+        assert!(!json.is_null());
+        string_free(json);
+
+        assert!(core_maintenance_json(handle, malformed.as_ptr(), None, std::ptr::null_mut()).is_null());
+        assert!(core_maintenance_json(std::ptr::null_mut(), std::ptr::null(), None, std::ptr::null_mut()).is_null());
+        core_free(handle);
+    }
+}
@@ -0,0 +1,132 @@
+//! Tests for the paste queue.
+
+use osp_core::{Config, Core, IngestConfig, PasteQueue, QueueMode};
+use tempfile::tempdir;
+
+mod common;
+use common::{open, text_item};
+
+fn popped(core: &Core) -> Vec<String> {
+    std::iter::from_fn(|| core.queue_pop().unwrap())
+        .map(|item| item.content_ref)
+        .collect()
+}
+
+#[test]
+fn test_fifo_and_lifo() {
+    let (_dir, core) = open();
+    let ids: Vec<i64> = ["Jane", "Doe", "jane@example.com"]
+        .iter()
+        .enumerate()
+        .map(|(i, s)| core.add_item(text_item(s, i as i64)).unwrap())
+        .collect();
+
+    assert_eq!(core.paste_queue().unwrap().mode, QueueMode::Fifo);
+    for &id in &ids {
+        core.queue_push(id).unwrap();
+    }
+    assert_eq!(core.queue_peek().unwrap().unwrap().content_ref, "Jane");
+    assert_eq!(core.queue_items().unwrap().len(), 3);
+    assert_eq!(popped(&core), ["Jane", "Doe", "jane@example.com"]);
+    assert!(core.queue_pop().unwrap().is_none());
+
+    core.set_paste_queue(&PasteQueue {
+        mode: QueueMode::Lifo,
+        ..PasteQueue::default()
+    })
+    .unwrap();
+    for &id in &ids {
+        core.queue_push(id).unwrap();
+    }
+    assert_eq!(popped(&core), ["jane@example.com", "Doe", "Jane"]);
+
+    assert!(core.queue_push(9999).is_err());
+}
+
+#[test]
+fn test_queue_survives_reopen() {
+    let (dir, core) = open();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+    let a = core.add_item(text_item("first", 1000)).unwrap();
+    let b = core.add_item(text_item("second", 2000)).unwrap();
+    core.set_paste_queue(&PasteQueue {
+        mode: QueueMode::Lifo,
+        active: true,
+        auto_enqueue: false,
+    })
+    .unwrap();
+    core.queue_push(a).unwrap();
+    core.queue_push(b).unwrap();
+    drop(core);
+
+    let core = Core::open(&db_path, &blobs_dir).unwrap();
+    assert_eq!(core.paste_queue().unwrap().mode, QueueMode::Lifo);
+    assert!(core.paste_queue().unwrap().active);
+    assert_eq!(popped(&core), ["second", "first"]);
+}
+
+#[test]
+fn test_auto_enqueue() {
+    let (_dir, core) = open();
+
+    // Inactive: captures aren't queued
+    core.dedupe_insert(text_item("before", 1000)).unwrap();
+    assert!(core.queue_items().unwrap().is_empty());
+
+    core.set_paste_queue(&PasteQueue {
+        active: true,
+        auto_enqueue: true,
+        ..PasteQueue::default()
+    })
+    .unwrap();
+    core.dedupe_insert(text_item("street", 2000)).unwrap();
+    core.dedupe_insert(text_item("city", 3000)).unwrap();
+    // Copying an existing item again queues it too
+    core.dedupe_insert(text_item("before", 4000)).unwrap();
+
+    // Imports are not captures
+    core.add_items(vec![text_item("imported", 5000)]);
+
+    assert_eq!(popped(&core), ["street", "city", "before"]);
+}
+
+#[test]
+fn test_auto_enqueue_with_ingest_worker() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+
+    let config = Config {
+        ingest: Some(IngestConfig::default()),
+        ..Config::default()
+    };
+    let core = Core::open_with_config(&db_path, &blobs_dir, config).unwrap();
+    core.set_paste_queue(&PasteQueue {
+        active: true,
+        auto_enqueue: true,
+        ..PasteQueue::default()
+    })
+    .unwrap();
+
+    core.enqueue(text_item("one", 1000)).unwrap();
+    core.enqueue(text_item("two", 2000)).unwrap();
+    core.flush().unwrap();
+
+    assert_eq!(popped(&core), ["one", "two"]);
+}
+
+#[test]
+fn test_trashed_items_skipped() {
+    let (_dir, core) = open();
+    let a = core.add_item(text_item("kept", 1000)).unwrap();
+    let b = core.add_item(text_item("trashed", 2000)).unwrap();
+    core.queue_push(b).unwrap();
+    core.queue_push(a).unwrap();
+
+    core.delete(b).unwrap();
+    assert_eq!(core.queue_peek().unwrap().unwrap().id, a);
+
+    assert_eq!(core.queue_clear().unwrap(), 2);
+    assert!(core.queue_peek().unwrap().is_none());
+}
@@ -0,0 +1,207 @@
+//! Tests for inline search operators.
+
+use osp_core::{Core, ItemKind, NewItem, SearchMode, SearchQuery};
+use std::ffi::{CStr, CString};
+use tempfile::tempdir;
+
+mod common;
+use common::{app_item, open};
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+fn ids(items: Vec<osp_core::Item>) -> Vec<i64> {
+    items.into_iter().map(|item| item.id).collect()
+}
+
+#[test]
+fn test_kind_app_and_pinned_operators() {
+    let (_dir, core) = open();
+    let screenshot = core
+        .add_item(app_item(ItemKind::Image, "/blobs/a.png", Some("com.apple.Safari"), 1000))
+        .unwrap();
+    let link = core
+        .add_item(app_item(ItemKind::Text, "https://example.com/release", Some("Safari"), 2000))
+        .unwrap();
+    let notes = core
+        .add_item(app_item(ItemKind::Text, "release notes draft", Some("Notes"), 3000))
+        .unwrap();
+    core.pin(notes, true).unwrap();
+
+    assert_eq!(ids(core.search("kind:image", 10).unwrap()), [screenshot]);
+    assert_eq!(ids(core.search("app:safari", 10).unwrap()), [link, screenshot]);
+    assert_eq!(ids(core.search("app:Safari kind:text", 10).unwrap()), [link]);
+    assert_eq!(ids(core.search("kind:image kind:text app:Notes", 10).unwrap()), [notes]);
+    assert_eq!(ids(core.search("release pinned:yes", 10).unwrap()), [notes]);
+    assert_eq!(ids(core.search("release pinned:no", 10).unwrap()), [link]);
+}
+
+#[test]
+fn test_tag_operator() {
+    let (_dir, core) = open();
+    let tagged = core
+        .add_item(NewItem {
+            tags: vec!["work".to_string(), "urgent".to_string()],
+            ..app_item(ItemKind::Text, "quarterly numbers", None, 1000)
+        })
+        .unwrap();
+    core.add_item(NewItem {
+        tags: vec!["homework".to_string()],
+        ..app_item(ItemKind::Text, "quarterly essay", None, 2000)
+    })
+    .unwrap();
+
+    assert_eq!(ids(core.search("tag:WORK", 10).unwrap()), [tagged]);
+    assert_eq!(ids(core.search("quarterly tag:work tag:urgent", 10).unwrap()), [tagged]);
+    assert!(core.search("tag:work tag:homework", 10).unwrap().is_empty());
+}
+
+#[test]
+fn test_date_operators() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+
+    let now = now_ms();
+    let core = Core::open(&db_path, &blobs_dir).unwrap();
+    let old = core
+        .add_item(app_item(ItemKind::Text, "backup log", None, now - 10 * DAY_MS))
+        .unwrap();
+    let recent = core
+        .add_item(app_item(ItemKind::Text, "backup log", None, now - DAY_MS / 2))
+        .unwrap();
+    let ancient = core
+        .add_item(app_item(ItemKind::Text, "backup log", None, 1_700_000_000_000))
+        .unwrap();
+
+    assert_eq!(ids(core.search("backup after:3d", 10).unwrap()), [recent]);
+    assert_eq!(ids(core.search("backup before:3d after:2024-01-01", 10).unwrap()), [old]);
+    assert_eq!(ids(core.search("before:2024-01-01", 10).unwrap()), [ancient]);
+    assert_eq!(ids(core.search("backup before:2w", 10).unwrap()), [ancient]);
+}
+
+#[test]
+fn test_exclusions_and_phrases() {
+    let (_dir, core) = open();
+    let final_notes = core
+        .add_item(app_item(ItemKind::Text, "release notes final", None, 1000))
+        .unwrap();
+    let draft = core
+        .add_item(app_item(ItemKind::Text, "release notes draft", None, 2000))
+        .unwrap();
+    let reordered = core
+        .add_item(app_item(ItemKind::Text, "notes on the release", None, 3000))
+        .unwrap();
+    core.annotate(final_notes, Some("Draft"), None).unwrap();
+
+    assert_eq!(ids(core.search("release -draft", 10).unwrap()), [reordered]);
+    assert_eq!(ids(core.search("\"release notes\"", 10).unwrap()), [draft, final_notes]);
+    assert_eq!(
+        ids(core.search("\"release notes\" -\"notes draft\"", 10).unwrap()),
+        [final_notes]
+    );
+}
+
+#[test]
+fn test_malformed_operators_fail() {
+    let (_dir, core) = open();
+    core.add_item(app_item(ItemKind::Text, "anything", None, 1000)).unwrap();
+
+    let err = core.search("kind:video", 10).unwrap_err();
+    let err = err.downcast_ref::<osp_core::ParseError>().unwrap();
+    assert_eq!((err.start, err.end), (5, 10));
+
+    assert!(core.search("after:soon", 10).is_err());
+    assert!(core.search("\"open quote", 10).is_err());
+}
+
+#[test]
+fn test_ffi_parse_query() {
+    unsafe {
+        let input = CString::new("deploy kind:image after:2025-01-01 -draft").unwrap();
+        let json = osp_core::ffi::core_parse_query(input.as_ptr());
+        assert!(!json.is_null());
+        let value: serde_json::Value =
+            serde_json::from_str(CStr::from_ptr(json).to_str().unwrap()).unwrap();
+        osp_core::ffi::string_free(json);
+
+        assert_eq!(value["query"]["text"], "deploy");
+        assert_eq!(value["query"]["kinds"], serde_json::json!(["image"]));
+        assert_eq!(value["query"]["exclude"], serde_json::json!(["draft"]));
+        assert!(value["query"]["after"].is_i64());
+
+        let input = CString::new("deploy pinned:maybe").unwrap();
+        let json = osp_core::ffi::core_parse_query(input.as_ptr());
+        let value: serde_json::Value =
+            serde_json::from_str(CStr::from_ptr(json).to_str().unwrap()).unwrap();
+        osp_core::ffi::string_free(json);
+
+        assert_eq!(value["error"]["start"], 14);
+        assert_eq!(value["error"]["end"], 19);
+        assert!(value["error"]["message"].as_str().unwrap().contains("yes or no"));
+    }
+}
+
+#[test]
+fn test_operators_in_every_search_mode() {
+    let (dir, core) = open();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+    let terminal = core
+        .add_item(app_item(ItemKind::Text, "kubectl get pods", Some("Terminal"), 1000))
+        .unwrap();
+    let notes = core
+        .add_item(app_item(ItemKind::Text, "kubectl get pods draft", Some("Notes"), 2000))
+        .unwrap();
+    let collection = core.create_collection("Ops").unwrap();
+    core.add_to_collection(collection, terminal, None).unwrap();
+    core.add_to_collection(collection, notes, None).unwrap();
+
+    let input = r#""get pods" app:terminal -draft"#;
+    assert_eq!(ids(core.search(input, 10).unwrap()), [terminal]);
+    for mode in [SearchMode::Standard, SearchMode::Fuzzy] {
+        let query = SearchQuery {
+            mode,
+            collection: Some(collection),
+            ..SearchQuery::new(input, 10)
+        };
+        assert_eq!(ids(core.search_input(query).unwrap()), [terminal], "{:?}", mode);
+    }
+
+    // A regex is matched as given
+    let query = SearchQuery {
+        mode: SearchMode::Regex,
+        ..SearchQuery::new("pods -?draft", 10)
+    };
+    assert_eq!(ids(core.search_input(query).unwrap()), [notes]);
+
+    // Through the FFI, whatever the entry point
+    let handle = unsafe {
+        let db_path = CString::new(db_path.to_str().unwrap()).unwrap();
+        let blobs_dir = CString::new(blobs_dir.to_str().unwrap()).unwrap();
+        osp_core::ffi::core_new(db_path.as_ptr(), blobs_dir.as_ptr())
+    };
+    let input = CString::new(input).unwrap();
+    let found = |array: *mut osp_core::ffi::CItemArray| unsafe {
+        assert!(!array.is_null());
+        let items = std::slice::from_raw_parts((*array).items, (*array).count);
+        let found: Vec<i64> = items.iter().map(|item| item.id).collect();
+        osp_core::ffi::item_array_free(array);
+        found
+    };
+    unsafe {
+        assert_eq!(found(osp_core::ffi::core_search(handle, input.as_ptr(), 10)), [terminal]);
+        assert_eq!(found(osp_core::ffi::core_search_mode(handle, input.as_ptr(), 1, 10)), [terminal]);
+        assert_eq!(
+            found(osp_core::ffi::core_search_in_collection(handle, input.as_ptr(), collection, 10)),
+            [terminal]
+        );
+        osp_core::ffi::core_free(handle);
+    }
+}
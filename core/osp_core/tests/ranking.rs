@@ -0,0 +1,127 @@
+//! Tests for learned ranking from search selections.
+
+use osp_core::NewItem;
+
+mod common;
+use common::{open, text_item};
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+fn ids(items: Vec<osp_core::Item>) -> Vec<i64> {
+    items.into_iter().map(|item| item.id).collect()
+}
+
+#[test]
+fn test_selection_boosts_item() {
+    let (_dir, core) = open();
+    let picked = core.add_item(text_item("deploy staging", 1000)).unwrap();
+    let other = core.add_item(text_item("deploy preview", 2000)).unwrap();
+    let newest = core.add_item(text_item("deploy canary", 3000)).unwrap();
+
+    assert_eq!(ids(core.search("deploy", 10).unwrap()), [newest, other, picked]);
+
+    core.record_selection("deploy", picked).unwrap();
+    assert_eq!(ids(core.search("deploy", 10).unwrap())[0], picked);
+
+    // Queries are normalized before matching
+    assert_eq!(ids(core.search("  DEPLOY ", 10).unwrap())[0], picked);
+
+    // Other queries are unaffected
+    assert_eq!(ids(core.search("canary", 10).unwrap()), [newest]);
+
+    assert!(core.record_selection("deploy", 9999).is_err());
+    assert!(core.record_selection("   ", picked).is_err());
+}
+
+#[test]
+fn test_selection_with_operators() {
+    let (_dir, core) = open();
+    let picked = core
+        .add_item(NewItem {
+            source_app: Some("Terminal".to_string()),
+            ..text_item("deploy staging", 1000)
+        })
+        .unwrap();
+    let newest = core
+        .add_item(NewItem {
+            source_app: Some("Terminal".to_string()),
+            ..text_item("deploy canary", 2000)
+        })
+        .unwrap();
+
+    let query = "deploy app:Terminal -draft";
+    assert_eq!(ids(core.search(query, 10).unwrap()), [newest, picked]);
+    core.record_selection(query, picked).unwrap();
+    assert_eq!(ids(core.search(query, 10).unwrap()), [picked, newest]);
+    assert_eq!(ids(core.search("DEPLOY kind:text", 10).unwrap()), [picked, newest]);
+
+    // Nothing to learn from operators alone, or from a malformed query
+    assert!(core.record_selection("app:Terminal", picked).is_err());
+    assert!(core.record_selection("deploy after:soon", picked).is_err());
+}
+
+#[test]
+fn test_short_queries_use_learned_boost() {
+    let (_dir, core) = open();
+    let picked = core.add_item(text_item("k9s", 1000)).unwrap();
+    let newer = core.add_item(text_item("k3s", 2000)).unwrap();
+
+    assert_eq!(ids(core.search("k", 10).unwrap()), [newer, picked]);
+    core.record_selection("k", picked).unwrap();
+    assert_eq!(ids(core.search("k", 10).unwrap()), [picked, newer]);
+}
+
+#[test]
+fn test_old_selections_decay() {
+    let (dir, core) = open();
+    let db_path = dir.path().join("test.db");
+    let old_favorite = core.add_item(text_item("release notes v1", 1000)).unwrap();
+    let new_favorite = core.add_item(text_item("release notes v2", 1000)).unwrap();
+
+    // Picked three times, a year ago
+    {
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+        conn.execute(
+            "INSERT INTO search_selections (query, item_id, weight, updated_at)
+             VALUES ('release', ?1, 3.0, ?2)",
+            (old_favorite, now - 365 * DAY_MS),
+        )
+        .unwrap();
+    }
+    assert_eq!(ids(core.search("release", 10).unwrap())[0], old_favorite);
+
+    // One pick today outweighs them
+    core.record_selection("release", new_favorite).unwrap();
+    assert_eq!(ids(core.search("release", 10).unwrap())[0], new_favorite);
+}
+
+#[test]
+fn test_reset_learned_ranking() {
+    let (_dir, core) = open();
+    let older = core.add_item(text_item("build script", 1000)).unwrap();
+    let newer = core.add_item(text_item("build cache", 2000)).unwrap();
+
+    core.record_selection("build", older).unwrap();
+    core.record_selection("script", older).unwrap();
+    assert_eq!(ids(core.search("build", 10).unwrap())[0], older);
+
+    assert_eq!(core.reset_learned_ranking().unwrap(), 2);
+    assert_eq!(ids(core.search("build", 10).unwrap()), [newer, older]);
+}
+
+#[test]
+fn test_title_match_outranks_single_selection() {
+    let (_dir, core) = open();
+    let titled = core.add_item(text_item("make release", 1000)).unwrap();
+    core.annotate(titled, Some("Deploy"), None).unwrap();
+    let content = core.add_item(text_item("deploy after tests", 2000)).unwrap();
+
+    // The learned boost reorders similar matches; one pick doesn't
+    // outweigh a title match
+    core.record_selection("deploy", content).unwrap();
+    assert_eq!(ids(core.search("deploy", 10).unwrap()), [titled, content]);
+}
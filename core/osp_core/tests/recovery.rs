@@ -0,0 +1,208 @@
+//! Tests for recovering corrupted databases, using deliberately damaged
+//! database files.
+
+use osp_core::{Config, Core, ItemKind, MaintenanceOptions, NewItem, RecoveryReport};
+use std::ffi::{CStr, CString};
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+mod common;
+use common::app_item;
+
+const ITEMS: i64 = 3000;
+
+fn new_item(content: &str, source_app: &str, created_at: i64) -> NewItem {
+    NewItem {
+        tags: vec!["kept".to_string()],
+        ..app_item(ItemKind::Text, content, Some(source_app), created_at)
+    }
+}
+
+fn open_with_recovery(db_path: &Path, blobs_dir: &Path) -> (Core, Option<RecoveryReport>) {
+    Core::open_with_recovery(db_path, blobs_dir, Config::default()).unwrap()
+}
+
+/// Create a database with `ITEMS` items, each with a unique source app, and
+/// checkpoint it so everything is in the main file.
+fn create_database(db_path: &Path, blobs_dir: &Path) {
+    let core = Core::open(db_path, blobs_dir).unwrap();
+    for result in core.add_items((1..=ITEMS).map(|i| {
+        new_item(&format!("clipboard entry number {}", i), &format!("app-{:05}", i), i)
+    })) {
+        result.unwrap();
+    }
+    let collection = core.create_collection("Favorites").unwrap();
+    core.add_to_collection(collection, 1, None).unwrap();
+    core.add_to_collection(collection, ITEMS, None).unwrap();
+    core.pin(2, true).unwrap();
+
+    let checkpoint_only = MaintenanceOptions {
+        integrity_check: false,
+        fts_integrity_check: false,
+        fts_optimize: false,
+        vacuum: false,
+        analyze: false,
+        ..Default::default()
+    };
+    let report = core.maintenance(&checkpoint_only).unwrap();
+    assert!(!report.checkpoint.unwrap().busy);
+}
+
+/// Overwrite the page holding `needle` with garbage.
+fn damage_page_containing(db_path: &Path, needle: &str) {
+    let mut bytes = std::fs::read(db_path).unwrap();
+    let page_size = u16::from_be_bytes([bytes[16], bytes[17]]) as usize;
+    let offset = bytes
+        .windows(needle.len())
+        .position(|window| window == needle.as_bytes())
+        .unwrap();
+    let page = offset / page_size;
+    assert!(page > 0, "must not damage the schema page");
+    bytes[page * page_size..(page + 1) * page_size].fill(0xff);
+    std::fs::write(db_path, bytes).unwrap();
+}
+
+fn corrupt_files(dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let name = path.to_str().unwrap();
+            name.contains(".corrupt-") && !name.ends_with("-wal") && !name.ends_with("-shm")
+        })
+        .collect()
+}
+
+#[test]
+fn test_healthy_database_is_untouched() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+
+    create_database(&db_path, &blobs_dir);
+    let (core, report) = open_with_recovery(&db_path, &blobs_dir);
+
+    assert!(report.is_none());
+    assert!(corrupt_files(dir.path()).is_empty());
+    assert_eq!(core.stats().unwrap().total, ITEMS);
+}
+
+#[test]
+fn test_recover_damaged_items_page() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+
+    create_database(&db_path, &blobs_dir);
+    let original = std::fs::read(&db_path).unwrap();
+    damage_page_containing(&db_path, "app-01500");
+    let damaged = std::fs::read(&db_path).unwrap();
+    assert_ne!(original, damaged);
+
+    // Opening the usual way doesn't notice
+    drop(Core::open(&db_path, &blobs_dir).unwrap());
+
+    let (core, report) = open_with_recovery(&db_path, &blobs_dir);
+    let report = report.unwrap();
+    assert!(!report.reason.is_empty());
+
+    // The damaged file is kept, unchanged
+    assert_eq!(corrupt_files(dir.path()), vec![report.moved_to.clone()]);
+    assert_eq!(std::fs::read(&report.moved_to).unwrap(), damaged);
+
+    // Only the rows around the damaged page are lost
+    let items = report.tables.iter().find(|t| t.table == "items").unwrap();
+    assert_eq!(items.recovered, report.items_recovered);
+    assert!(report.items_recovered < ITEMS);
+    assert!(report.items_recovered > ITEMS - 200, "{:?}", items);
+    assert_eq!(items.errors.len(), 1, "{:?}", items);
+    assert!(core.get(1500).is_err());
+
+    let collection_items = report
+        .tables
+        .iter()
+        .find(|t| t.table == "collection_items")
+        .unwrap();
+    assert_eq!(collection_items.recovered, 2);
+
+    // Recovered items are searchable, with their metadata
+    let stats = core.stats().unwrap();
+    assert_eq!(stats.total, report.items_recovered);
+    let found = core.search("entry number 2999", 1).unwrap();
+    assert_eq!(found[0].id, 2999);
+    assert_eq!(found[0].source_app.as_deref(), Some("app-02999"));
+    assert_eq!(found[0].tags, vec!["kept".to_string()]);
+    assert!(core.get(2).unwrap().pinned);
+    assert_eq!(core.search("*ntry numb", 5).unwrap().len(), 5);
+    assert_eq!(core.collections().unwrap()[0].name, "Favorites");
+
+    // The new database is healthy and keeps working
+    let id = core.add_item(new_item("after recovery", "app", ITEMS + 1)).unwrap();
+    assert_eq!(core.search("recovery", 10).unwrap()[0].id, id);
+    drop(core);
+    let (_core, report) = open_with_recovery(&db_path, &blobs_dir);
+    assert!(report.is_none());
+}
+
+#[test]
+fn test_recover_garbage_file() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+
+    let garbage = vec![0x5au8; 64 * 1024];
+    std::fs::write(&db_path, &garbage).unwrap();
+    assert!(Core::open(&db_path, &blobs_dir).is_err());
+
+    let (core, report) = open_with_recovery(&db_path, &blobs_dir);
+    let report = report.unwrap();
+    assert_eq!(report.items_recovered, 0);
+    assert!(report.tables.iter().all(|t| t.recovered == 0 && !t.errors.is_empty()));
+    assert_eq!(std::fs::read(&report.moved_to).unwrap(), garbage);
+
+    let id = core.add_item(new_item("fresh start", "app", 1)).unwrap();
+    assert_eq!(core.search("fresh", 10).unwrap()[0].id, id);
+}
+
+#[test]
+fn test_ffi_new_with_recovery() {
+    use osp_core::ffi::*;
+
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    std::fs::write(&db_path, vec![0x5au8; 8192]).unwrap();
+    let db_path = CString::new(db_path.to_str().unwrap()).unwrap();
+    let blobs_dir = CString::new(dir.path().join("blobs").to_str().unwrap()).unwrap();
+
+    unsafe {
+        assert!(core_new(db_path.as_ptr(), blobs_dir.as_ptr()).is_null());
+
+        let mut report = std::ptr::null_mut();
+        let handle = core_new_with_recovery(
+            db_path.as_ptr(),
+            blobs_dir.as_ptr(),
+            std::ptr::null(),
+            &mut report,
+        );
+        assert!(!handle.is_null());
+        assert!(!report.is_null());
+        let value: serde_json::Value =
+            serde_json::from_str(CStr::from_ptr(report).to_str().unwrap()).unwrap();
+        assert_eq!(value["items_recovered"], 0);
+        assert!(value["moved_to"].as_str().unwrap().contains("test.db.corrupt-"));
+        string_free(report);
+        core_free(handle);
+
+        // Healthy now: no report
+        let mut report = std::ptr::null_mut();
+        let handle = core_new_with_recovery(
+            db_path.as_ptr(),
+            blobs_dir.as_ptr(),
+            std::ptr::null(),
+            &mut report,
+        );
+        assert!(!handle.is_null());
+        assert!(report.is_null());
+        core_free(handle);
+    }
+}
@@ -0,0 +1,123 @@
+//! Tests for regex search.
+
+use osp_core::{SearchQuery, SortOrder};
+use std::ffi::CString;
+use tempfile::tempdir;
+
+mod common;
+use common::{open, text_item};
+
+fn ids(items: Vec<osp_core::Item>) -> Vec<i64> {
+    items.into_iter().map(|item| item.id).collect()
+}
+
+const UUID: &str = r"(?i)\b[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}\b";
+
+#[test]
+fn test_regex_search() {
+    let (_dir, core) = open();
+    let lower = core
+        .add_item(text_item("request 3f2b8c1e-9d4a-4f6b-8e2c-1a5d7b9c0e3f failed", 1000))
+        .unwrap();
+    let upper = core
+        .add_item(text_item("3F2B8C1E-9D4A-4F6B-8E2C-1A5D7B9C0E3F", 2000))
+        .unwrap();
+    core.add_item(text_item("not-a-uuid-1234", 3000)).unwrap();
+    let private_ip = core.add_item(text_item("ssh admin@10.20.30.40", 4000)).unwrap();
+    core.add_item(text_item("ping 110.20.30.40", 5000)).unwrap();
+
+    assert_eq!(ids(core.search_with(&SearchQuery::regex(UUID)).unwrap()), [upper, lower]);
+    assert_eq!(
+        ids(core
+            .search_with(&SearchQuery::regex(r"\b10(\.\d{1,3}){3}\b"))
+            .unwrap()),
+        [private_ip]
+    );
+
+    // Titles and notes are matched too
+    let titled = core.add_item(text_item("see title", 500)).unwrap();
+    core.annotate(titled, Some("ticket OPS-1234"), None).unwrap();
+    assert_eq!(
+        ids(core.search_with(&SearchQuery::regex(r"^ticket [A-Z]+-\d+$")).unwrap()),
+        [titled]
+    );
+}
+
+#[test]
+fn test_regex_search_filters_and_sort() {
+    let (_dir, core) = open();
+    let first = core.add_item(text_item("port 8080", 1000)).unwrap();
+    let second = core.add_item(text_item("port 5432", 2000)).unwrap();
+    let trashed = core.add_item(text_item("port 22", 3000)).unwrap();
+    core.delete(trashed).unwrap();
+    core.record_use(first, None).unwrap();
+
+    let query = SearchQuery::regex(r"port \d+");
+    assert_eq!(ids(core.search_with(&query).unwrap()), [second, first]);
+
+    let query = SearchQuery {
+        sort: SortOrder::Frecency,
+        limit: 1,
+        ..SearchQuery::regex(r"port \d+")
+    };
+    assert_eq!(ids(core.search_with(&query).unwrap()), [first]);
+}
+
+#[test]
+fn test_regex_search_rejects_bad_patterns() {
+    let (_dir, core) = open();
+    core.add_item(text_item("anything", 1000)).unwrap();
+
+    let err = core.search_with(&SearchQuery::regex("(unclosed")).unwrap_err();
+    assert!(err.to_string().contains("Invalid regex"));
+
+    assert!(core
+        .search_with(&SearchQuery::regex(r"\w{1000}\w{1000}\w{1000}"))
+        .is_err());
+    assert!(core
+        .search_with(&SearchQuery::regex("a".repeat(5000)))
+        .is_err());
+
+    // The connection is still usable
+    assert_eq!(core.search_with(&SearchQuery::regex("any")).unwrap().len(), 1);
+}
+
+#[test]
+fn test_ffi_search_mode() {
+    let dir = tempdir().unwrap();
+    let db_path = CString::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+    let blobs_dir = CString::new(dir.path().join("blobs").to_str().unwrap()).unwrap();
+
+    unsafe {
+        let handle = osp_core::ffi::core_new(db_path.as_ptr(), blobs_dir.as_ptr());
+        assert!(!handle.is_null());
+
+        let content = CString::new("deploy build 1234").unwrap();
+        let id = osp_core::ffi::core_add_item(handle, 0, content.as_ptr(), std::ptr::null(), 1000);
+
+        let pattern = CString::new(r"build \d{4}$").unwrap();
+        let results = osp_core::ffi::core_search_mode(handle, pattern.as_ptr(), 2, 10);
+        assert!(!results.is_null());
+        assert_eq!((*results).count, 1);
+        assert_eq!((*(*results).items).id, id);
+        osp_core::ffi::item_array_free(results);
+
+        let text = CString::new("deploy").unwrap();
+        let results = osp_core::ffi::core_search_mode(handle, text.as_ptr(), 0, 10);
+        assert!(!results.is_null());
+        assert_eq!((*results).count, 1);
+        osp_core::ffi::item_array_free(results);
+
+        let query = CString::new(r#"{"text": "\\d+", "mode": "regex"}"#).unwrap();
+        let results = osp_core::ffi::core_search_query(handle, query.as_ptr());
+        assert!(!results.is_null());
+        assert_eq!((*results).count, 1);
+        osp_core::ffi::item_array_free(results);
+
+        let bad = CString::new("(").unwrap();
+        assert!(osp_core::ffi::core_search_mode(handle, bad.as_ptr(), 2, 10).is_null());
+        assert!(osp_core::ffi::core_search_mode(handle, pattern.as_ptr(), 7, 10).is_null());
+
+        osp_core::ffi::core_free(handle);
+    }
+}
@@ -0,0 +1,163 @@
+//! Tests for saved searches.
+
+use osp_core::{Core, ItemKind, SearchQuery, SortOrder};
+use std::ffi::{CStr, CString};
+use tempfile::tempdir;
+
+mod common;
+use common::{app_item, open};
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+fn ids(items: Vec<osp_core::Item>) -> Vec<i64> {
+    items.into_iter().map(|item| item.id).collect()
+}
+
+#[test]
+fn test_saved_search_is_live() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+
+    let now = now_ms();
+    let core = Core::open(&db_path, &blobs_dir).unwrap();
+    core.add_item(app_item(ItemKind::Image, "/blobs/old.png", None, now - 30 * DAY_MS))
+        .unwrap();
+    let recent = core
+        .add_item(app_item(ItemKind::Image, "/blobs/recent.png", None, now - DAY_MS))
+        .unwrap();
+    core.add_item(app_item(ItemKind::Text, "not an image", None, now))
+        .unwrap();
+
+    let id = core
+        .save_search("Recent screenshots", &SearchQuery::new("kind:image after:7d", 10))
+        .unwrap();
+    assert_eq!(ids(core.run_saved_search(id, 0).unwrap()), [recent]);
+
+    // New matches show up without touching the saved search
+    let newer = core
+        .add_item(app_item(ItemKind::Image, "/blobs/new.png", None, now_ms()))
+        .unwrap();
+    assert_eq!(ids(core.run_saved_search(id, 0).unwrap()), [newer, recent]);
+
+    // Operators are stored unparsed, so relative dates stay relative
+    let saved = &core.list_saved_searches().unwrap()[0];
+    assert_eq!(saved.query.text, "kind:image after:7d");
+    assert_eq!(saved.query.after, None);
+}
+
+#[test]
+fn test_saved_search_paging_and_options() {
+    let (_dir, core) = open();
+    let ids_by_age: Vec<i64> = (0..5)
+        .map(|i| {
+            core.add_item(app_item(ItemKind::Text, &format!("kubectl apply {}", i), Some("Terminal"), 1000 + i))
+                .unwrap()
+        })
+        .collect();
+    core.add_item(app_item(ItemKind::Text, "kubectl in notes", Some("Notes"), 2000))
+        .unwrap();
+
+    let query = SearchQuery {
+        sort: SortOrder::Recent,
+        ..SearchQuery::new("kubectl app:Terminal", 2)
+    };
+    let id = core.save_search("Terminal kubectl", &query).unwrap();
+
+    assert_eq!(ids(core.run_saved_search(id, 0).unwrap()), [ids_by_age[4], ids_by_age[3]]);
+    assert_eq!(ids(core.run_saved_search(id, 1).unwrap()), [ids_by_age[2], ids_by_age[1]]);
+    assert_eq!(ids(core.run_saved_search(id, 2).unwrap()), [ids_by_age[0]]);
+    assert!(core.run_saved_search(id, 3).unwrap().is_empty());
+}
+
+#[test]
+fn test_manage_saved_searches() {
+    let (_dir, core) = open();
+    let prod = core
+        .save_search("prod", &SearchQuery::new("app:Terminal tag:prod", 50))
+        .unwrap();
+    let images = core
+        .save_search("Images", &SearchQuery::new("kind:image", 50))
+        .unwrap();
+
+    let names: Vec<String> = core
+        .list_saved_searches()
+        .unwrap()
+        .into_iter()
+        .map(|s| s.name)
+        .collect();
+    assert_eq!(names, ["Images", "prod"]);
+
+    // Names are unique ignoring case, and queries must parse
+    assert!(core.save_search("PROD", &SearchQuery::new("x", 10)).is_err());
+    assert!(core.save_search("  ", &SearchQuery::new("x", 10)).is_err());
+    assert!(core.save_search("Bad", &SearchQuery::new("kind:video", 10)).is_err());
+    assert!(core.save_search("Bad", &SearchQuery::regex("(")).is_err());
+
+    core.update_saved_search(images, "Screenshots", &SearchQuery::new("kind:image app:Safari", 50))
+        .unwrap();
+    assert!(core
+        .update_saved_search(images, "prod", &SearchQuery::new("x", 10))
+        .is_err());
+
+    core.delete_saved_search(prod).unwrap();
+    let saved = core.list_saved_searches().unwrap();
+    assert_eq!(saved.len(), 1);
+    assert_eq!(saved[0].name, "Screenshots");
+    assert_eq!(saved[0].query.text, "kind:image app:Safari");
+
+    assert!(core.run_saved_search(prod, 0).is_err());
+    assert!(core.delete_saved_search(prod).is_err());
+}
+
+#[test]
+fn test_ffi_saved_searches() {
+    let dir = tempdir().unwrap();
+    let db_path = CString::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+    let blobs_dir = CString::new(dir.path().join("blobs").to_str().unwrap()).unwrap();
+
+    unsafe {
+        let handle = osp_core::ffi::core_new(db_path.as_ptr(), blobs_dir.as_ptr());
+        assert!(!handle.is_null());
+
+        let content = CString::new("ssh prod-db").unwrap();
+        let item = osp_core::ffi::core_add_item(handle, 0, content.as_ptr(), std::ptr::null(), 1000);
+
+        let name = CString::new("SSH").unwrap();
+        let query = CString::new(r#"{"text": "ssh kind:text", "limit": 20}"#).unwrap();
+        let id = osp_core::ffi::core_save_search(handle, name.as_ptr(), query.as_ptr());
+        assert!(id > 0);
+
+        let results = osp_core::ffi::core_run_saved_search(handle, id, 0);
+        assert!(!results.is_null());
+        assert_eq!((*results).count, 1);
+        assert_eq!((*(*results).items).id, item);
+        osp_core::ffi::item_array_free(results);
+
+        let json = osp_core::ffi::core_saved_searches_json(handle);
+        let value: serde_json::Value =
+            serde_json::from_str(CStr::from_ptr(json).to_str().unwrap()).unwrap();
+        osp_core::ffi::string_free(json);
+        assert_eq!(value[0]["name"], "SSH");
+        assert_eq!(value[0]["query"]["limit"], 20);
+
+        let bad = CString::new(r#"{"text": "pinned:maybe"}"#).unwrap();
+        let other = CString::new("Other").unwrap();
+        assert_eq!(osp_core::ffi::core_save_search(handle, other.as_ptr(), bad.as_ptr()), -1);
+        assert_eq!(
+            osp_core::ffi::core_update_saved_search(handle, id, other.as_ptr(), query.as_ptr()),
+            0
+        );
+        assert_eq!(osp_core::ffi::core_delete_saved_search(handle, id), 0);
+        assert!(osp_core::ffi::core_run_saved_search(handle, id, 0).is_null());
+
+        osp_core::ffi::core_free(handle);
+    }
+}
@@ -0,0 +1,71 @@
+//! Tests for numbered quick-paste slots.
+
+mod common;
+use common::{open, text_item};
+
+#[test]
+fn test_assign_and_list() {
+    let (_dir, core) = open();
+    let email = core.add_item(text_item("me@example.com", 1000)).unwrap();
+    let phone = core.add_item(text_item("+1 555 0100", 2000)).unwrap();
+
+    assert!(core.slot(1).unwrap().is_none());
+    core.assign_slot(1, email).unwrap();
+    core.assign_slot(3, phone).unwrap();
+    assert_eq!(core.slot(1).unwrap().unwrap().id, email);
+
+    let slots = core.slots().unwrap();
+    let pairs: Vec<(u8, i64)> = slots.iter().map(|s| (s.slot, s.item.id)).collect();
+    assert_eq!(pairs, [(1, email), (3, phone)]);
+
+    // Reassigning a slot replaces its item; an item moves between slots
+    core.assign_slot(1, phone).unwrap();
+    let pairs: Vec<(u8, i64)> = core.slots().unwrap().iter().map(|s| (s.slot, s.item.id)).collect();
+    assert_eq!(pairs, [(1, phone)]);
+
+    assert!(core.clear_slot(1).unwrap());
+    assert!(!core.clear_slot(1).unwrap());
+    assert!(core.slots().unwrap().is_empty());
+}
+
+#[test]
+fn test_slot_validation() {
+    let (_dir, core) = open();
+    let id = core.add_item(text_item("slotted", 1000)).unwrap();
+
+    assert!(core.assign_slot(0, id).is_err());
+    assert!(core.assign_slot(10, id).is_err());
+    assert!(core.slot(10).is_err());
+    assert!(core.assign_slot(1, 9999).is_err());
+}
+
+#[test]
+fn test_slots_survive_clear_history() {
+    let (_dir, core) = open();
+    let slotted = core.add_item(text_item("slotted entry", 1000)).unwrap();
+    core.add_item(text_item("regular entry", 1001)).unwrap();
+    core.assign_slot(5, slotted).unwrap();
+
+    assert_eq!(core.clear_history().unwrap(), 1);
+    assert_eq!(core.slot(5).unwrap().unwrap().id, slotted);
+}
+
+#[test]
+fn test_delete_clears_slot() {
+    let (_dir, core) = open();
+    let trashed = core.add_item(text_item("trashed", 1000)).unwrap();
+    let deleted = core.add_item(text_item("deleted", 2000)).unwrap();
+    core.assign_slot(1, trashed).unwrap();
+    core.assign_slot(2, deleted).unwrap();
+
+    core.delete(trashed).unwrap();
+    assert!(core.slot(1).unwrap().is_none());
+
+    // Restoring doesn't bring the slot back
+    core.restore(trashed).unwrap();
+    assert!(core.slot(1).unwrap().is_none());
+
+    core.delete_permanently(deleted).unwrap();
+    assert!(core.slot(2).unwrap().is_none());
+    assert!(core.slots().unwrap().is_empty());
+}
@@ -0,0 +1,111 @@
+//! Tests for snippets and template rendering.
+
+use osp_core::ItemKind;
+
+mod common;
+use common::{new_item, open};
+
+#[test]
+fn test_create_and_expand() {
+    let (_dir, core) = open();
+    let id = core
+        .create_snippet("Address", ";addr", "221B Baker Street\nLondon")
+        .unwrap();
+
+    let snippet = core.snippet(";addr").unwrap().unwrap();
+    assert_eq!(snippet.id, id);
+    assert_eq!(snippet.name.as_deref(), Some("Address"));
+
+    // Snippets are regular items: named and searchable
+    assert_eq!(core.get(id).unwrap().title.as_deref(), Some("Address"));
+    assert_eq!(core.search("baker", 10).unwrap().len(), 1);
+
+    let rendered = core.expand_snippet(";addr").unwrap().unwrap();
+    assert_eq!(rendered.text, "221B Baker Street\nLondon");
+    assert_eq!(rendered.cursor, None);
+
+    assert!(core.expand_snippet(";missing").unwrap().is_none());
+}
+
+#[test]
+fn test_render_clipboard_and_cursor() {
+    let (_dir, core) = open();
+    core.add_item(new_item(ItemKind::Text, "older copy", 1000))
+        .unwrap();
+    core.add_item(new_item(ItemKind::Text, "https://example.com", 2000))
+        .unwrap();
+    core.create_snippet("Link", ";ln", "[{{cursor}}]({{clipboard}})")
+        .unwrap();
+
+    // The snippet itself isn't treated as the clipboard
+    let rendered = core.expand_snippet(";ln").unwrap().unwrap();
+    assert_eq!(rendered.text, "[](https://example.com)");
+    assert_eq!(rendered.cursor, Some(1));
+}
+
+#[test]
+fn test_render_date() {
+    let (_dir, core) = open();
+    let id = core
+        .add_item(new_item(ItemKind::Text, "Report {{date:%Y}}", 1000))
+        .unwrap();
+
+    let rendered = core.render_template(id).unwrap();
+    let year = chrono::Local::now().format("%Y").to_string();
+    assert_eq!(rendered.text, format!("Report {}", year));
+
+    // Only text items are templates
+    let image = core
+        .add_item(new_item(ItemKind::Image, "/blobs/a.png", 1000))
+        .unwrap();
+    assert!(core.render_template(image).is_err());
+}
+
+#[test]
+fn test_abbreviation_rules() {
+    let (_dir, core) = open();
+    core.create_snippet("Signature", ";sig", "Regards").unwrap();
+
+    assert!(core.create_snippet("Other", ";sig", "Cheers").is_err());
+    assert!(core.create_snippet("Spaces", "; sig", "Cheers").is_err());
+    assert!(core.create_snippet("Empty", "", "Cheers").is_err());
+
+    // The failed attempts left no items behind
+    assert!(core.search("cheers", 10).unwrap().is_empty());
+
+    // An existing item can become a snippet, and be renamed
+    let id = core.add_item(new_item(ItemKind::Text, "Best,", 1000)).unwrap();
+    core.set_snippet_abbreviation(id, ";best").unwrap();
+    core.set_snippet_abbreviation(id, ";b").unwrap();
+    assert!(core.snippet(";best").unwrap().is_none());
+
+    let abbreviations: Vec<String> = core
+        .snippets()
+        .unwrap()
+        .into_iter()
+        .map(|s| s.abbreviation)
+        .collect();
+    assert_eq!(abbreviations, [";b", ";sig"]);
+
+    core.remove_snippet(id).unwrap();
+    assert!(core.snippet(";b").unwrap().is_none());
+    assert!(core.get(id).is_ok());
+}
+
+#[test]
+fn test_trashed_snippets_hidden() {
+    let (_dir, core) = open();
+    let id = core.create_snippet("Temp", ";tmp", "temporary").unwrap();
+
+    core.delete(id).unwrap();
+    assert!(core.expand_snippet(";tmp").unwrap().is_none());
+    assert!(core.snippets().unwrap().is_empty());
+
+    // Restoring brings the abbreviation back
+    core.restore(id).unwrap();
+    assert!(core.expand_snippet(";tmp").unwrap().is_some());
+
+    // Permanent deletion frees it
+    core.delete_permanently(id).unwrap();
+    core.create_snippet("Temp", ";tmp", "again").unwrap();
+}
@@ -0,0 +1,173 @@
+//! Tests for the usage statistics and storage report.
+
+use chrono::Local;
+use osp_core::{Core, Count, ItemKind};
+use std::ffi::CStr;
+use tempfile::tempdir;
+
+mod common;
+use common::{app_item, open};
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+fn now() -> i64 {
+    Local::now().timestamp_millis()
+}
+
+#[test]
+fn test_stats_empty() {
+    let (_dir, core) = open();
+    let stats = core.stats().unwrap();
+
+    assert_eq!(stats.total, 0);
+    assert_eq!(stats.trashed, 0);
+    assert!(stats.kinds.is_empty());
+    assert_eq!(stats.text_bytes, 0);
+    assert_eq!(stats.blob_bytes, 0);
+    assert!(stats.database_bytes > 0);
+    assert!(stats.oldest.is_none());
+    assert!(stats.newest.is_none());
+    assert!(stats.apps.is_empty());
+    assert_eq!(stats.days.len(), 30);
+    assert!(stats.days.iter().all(|day| day.count == 0));
+    assert_eq!(stats.dedupe.attempts, 0);
+    assert_eq!(stats.dedupe.hit_rate, 0.0);
+}
+
+#[test]
+fn test_stats_counts() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+
+    let now = now();
+    let core = Core::open(&db_path, &blobs_dir).unwrap();
+    let oldest = core
+        .add_item(app_item(ItemKind::Text, "héllo", Some("Terminal"), now - 3 * DAY_MS))
+        .unwrap();
+    core.add_item(app_item(ItemKind::Text, "world", Some("Terminal"), now - DAY_MS))
+        .unwrap();
+    core.add_item(app_item(ItemKind::Image, "/blobs/a.png", Some("Safari"), now - DAY_MS))
+        .unwrap();
+    let newest = core
+        .add_item(app_item(ItemKind::Text, "newest", None, now))
+        .unwrap();
+    let trashed = core
+        .add_item(app_item(ItemKind::Text, "ancient", Some("Notes"), now - 90 * DAY_MS))
+        .unwrap();
+    core.delete(trashed).unwrap();
+
+    let stats = core.stats().unwrap();
+    assert_eq!(stats.total, 4);
+    assert_eq!(stats.trashed, 1);
+    assert_eq!(
+        stats.kinds,
+        vec![
+            Count { value: ItemKind::Text, count: 3 },
+            Count { value: ItemKind::Image, count: 1 },
+        ]
+    );
+    // "héllo" is six bytes in UTF-8; the trashed item still takes space
+    assert_eq!(stats.text_bytes, 6 + 5 + 6 + 7);
+    assert_eq!(stats.oldest.unwrap().id, oldest);
+    assert_eq!(stats.newest.unwrap().id, newest);
+    assert_eq!(
+        stats.apps,
+        vec![
+            Count { value: Some("Terminal".to_string()), count: 2 },
+            Count { value: None, count: 1 },
+            Count { value: Some("Safari".to_string()), count: 1 },
+        ]
+    );
+
+    assert_eq!(stats.days.len(), 30);
+    let counts: Vec<i64> = stats.days.iter().map(|day| day.count).collect();
+    assert_eq!(counts[29], 1);
+    assert_eq!(counts[28], 2);
+    assert_eq!(counts[26], 1);
+    assert_eq!(counts.iter().sum::<i64>(), 4);
+    assert_eq!(stats.days[29].value, Local::now().format("%Y-%m-%d").to_string());
+    assert!(stats.days.windows(2).all(|pair| pair[0].value < pair[1].value));
+}
+
+#[test]
+fn test_stats_file_sizes() {
+    let (dir, core) = open();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+    std::fs::write(blobs_dir.join("a.png"), vec![0u8; 1000]).unwrap();
+    std::fs::create_dir_all(blobs_dir.join("ab")).unwrap();
+    std::fs::write(blobs_dir.join("ab").join("b.png"), vec![0u8; 234]).unwrap();
+    core.add_item(app_item(ItemKind::Text, "hello", None, now())).unwrap();
+
+    let stats = core.stats().unwrap();
+    assert_eq!(stats.blob_bytes, 1234);
+    assert_eq!(
+        stats.database_bytes,
+        std::fs::metadata(&db_path).unwrap().len()
+    );
+    assert_eq!(
+        stats.wal_bytes,
+        std::fs::metadata(dir.path().join("test.db-wal")).unwrap().len()
+    );
+    assert!(stats.wal_bytes > 0);
+}
+
+#[test]
+fn test_stats_dedupe_hit_rate() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+
+    let now = now();
+    let core = Core::open(&db_path, &blobs_dir).unwrap();
+    core.dedupe_insert(app_item(ItemKind::Text, "hello", None, now)).unwrap();
+    core.dedupe_insert(app_item(ItemKind::Text, "hello", None, now + 1)).unwrap();
+    core.dedupe_insert_items(vec![
+        app_item(ItemKind::Text, "world", None, now + 2),
+        app_item(ItemKind::Text, "hello", None, now + 3),
+    ]);
+    // Plain inserts don't check for duplicates
+    core.add_item(app_item(ItemKind::Text, "hello", None, now + 4)).unwrap();
+
+    let stats = core.stats().unwrap();
+    assert_eq!(stats.dedupe.attempts, 4);
+    assert_eq!(stats.dedupe.hits, 2);
+    assert_eq!(stats.dedupe.hit_rate, 0.5);
+
+    // Counters persist across restarts
+    drop(core);
+    let core = Core::open(&db_path, &blobs_dir).unwrap();
+    assert_eq!(core.stats().unwrap().dedupe.hits, 2);
+}
+
+#[test]
+fn test_ffi_stats_json() {
+    use osp_core::ffi::*;
+    use std::ffi::CString;
+
+    let dir = tempdir().unwrap();
+    let db_path = CString::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+    let blobs_dir = CString::new(dir.path().join("blobs").to_str().unwrap()).unwrap();
+    let content = CString::new("hello").unwrap();
+
+    unsafe {
+        let handle = core_new(db_path.as_ptr(), blobs_dir.as_ptr());
+        assert!(!handle.is_null());
+        assert!(core_add_item(handle, 0, content.as_ptr(), std::ptr::null(), now()) > 0);
+
+        let json = core_stats_json(handle);
+        assert!(!json.is_null());
+        let value: serde_json::Value =
+            serde_json::from_str(CStr::from_ptr(json).to_str().unwrap()).unwrap();
+        assert_eq!(value["total"], 1);
+        assert_eq!(value["kinds"][0]["value"], "text");
+        assert_eq!(value["newest"]["content_ref"], "hello");
+        assert_eq!(value["days"].as_array().unwrap().len(), 30);
+        assert_eq!(value["dedupe"]["hit_rate"], 0.0);
+        string_free(json);
+
+        assert!(core_stats_json(std::ptr::null_mut()).is_null());
+        core_free(handle);
+    }
+}
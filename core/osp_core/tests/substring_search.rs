@@ -0,0 +1,161 @@
+//! Tests for CJK and substring search through the trigram index.
+
+use osp_core::{Core, ItemKind, SearchQuery};
+use tempfile::tempdir;
+
+mod common;
+use common::{new_item, open};
+
+fn ids(items: Vec<osp_core::Item>) -> Vec<i64> {
+    items.into_iter().map(|item| item.id).collect()
+}
+
+#[test]
+fn test_cjk_search() {
+    let (_dir, core) = open();
+    let chinese = core
+        .add_item(new_item(ItemKind::Text, "我们明天去东京塔看夜景", 1000))
+        .unwrap();
+    let japanese = core
+        .add_item(new_item(ItemKind::Text, "会議は午後三時からです", 2000))
+        .unwrap();
+    let korean = core
+        .add_item(new_item(ItemKind::Text, "서울특별시 강남구 테헤란로", 3000))
+        .unwrap();
+
+    assert_eq!(ids(core.search("东京塔", 10).unwrap()), [chinese]);
+    assert_eq!(ids(core.search("午後三時", 10).unwrap()), [japanese]);
+    assert_eq!(ids(core.search("강남구", 10).unwrap()), [korean]);
+    assert_eq!(ids(core.search("특별시", 10).unwrap()), [korean]);
+
+    // Shorter than a trigram
+    assert_eq!(ids(core.search("会議", 10).unwrap()), [japanese]);
+    assert!(core.search("大阪城", 10).unwrap().is_empty());
+}
+
+#[test]
+fn test_substring_search() {
+    let (_dir, core) = open();
+    let kubeconfig = core
+        .add_item(new_item(ItemKind::Text, "export KUBECONFIG=~/.kube/kubeconfig", 1000))
+        .unwrap();
+
+    // No word matches, so the query is retried as a substring
+    assert_eq!(ids(core.search("config", 10).unwrap()), [kubeconfig]);
+
+    let config = core
+        .add_item(new_item(ItemKind::Text, "config.yaml", 2000))
+        .unwrap();
+
+    // Word matches are preferred...
+    assert_eq!(ids(core.search("config", 10).unwrap()), [config]);
+
+    // ...unless a substring is asked for
+    let results = ids(core.search("*config", 10).unwrap());
+    assert_eq!(results.len(), 2);
+    assert!(results.contains(&kubeconfig));
+    assert!(results.contains(&config));
+
+    // FTS5 syntax is taken literally in substring searches
+    assert!(core.search("*con\"fig", 10).unwrap().is_empty());
+}
+
+#[test]
+fn test_substring_fallback_ignores_paging() {
+    let (_dir, core) = open();
+    let word = core.add_item(new_item(ItemKind::Text, "config file", 1000)).unwrap();
+    for i in 0..15 {
+        core.add_item(new_item(ItemKind::Text, &format!("kubeconfig{}", i), 2000 + i))
+            .unwrap();
+    }
+
+    // The word matches, so every page comes from the word search
+    let page = |offset| {
+        core.search_with(&SearchQuery {
+            offset,
+            ..SearchQuery::new("config", 10)
+        })
+        .unwrap()
+    };
+    assert_eq!(ids(page(0)), [word]);
+    assert!(page(10).is_empty());
+
+    let saved = core.save_search("Config", &SearchQuery::new("config", 10)).unwrap();
+    assert_eq!(ids(core.run_saved_search(saved, 0).unwrap()), [word]);
+    assert!(core.run_saved_search(saved, 1).unwrap().is_empty());
+    assert_eq!(core.facets(&SearchQuery::new("config", 10)).unwrap().total, 1);
+
+    // Without a word match, pages of the substring search
+    let query = |offset| SearchQuery {
+        offset,
+        ..SearchQuery::new("kubeconf", 10)
+    };
+    assert_eq!(core.search_with(&query(0)).unwrap().len(), 10);
+    assert_eq!(core.search_with(&query(10)).unwrap().len(), 5);
+}
+
+#[test]
+fn test_trigram_index_stays_in_sync() {
+    let (dir, core) = open();
+    let db_path = dir.path().join("test.db");
+    let id = core
+        .add_item(new_item(ItemKind::Text, "东京塔的夜景", 1000))
+        .unwrap();
+    assert_eq!(ids(core.search("东京塔", 10).unwrap()), [id]);
+
+    // Update
+    core.update_content(id, "大阪城公园").unwrap();
+    assert!(core.search("东京塔", 10).unwrap().is_empty());
+    assert_eq!(ids(core.search("大阪城", 10).unwrap()), [id]);
+
+    // Titles are indexed too
+    core.annotate(id, Some("旅行计划清单"), None).unwrap();
+    assert_eq!(ids(core.search("计划清", 10).unwrap()), [id]);
+
+    // Trash hides the item, permanent deletion removes its row
+    core.delete(id).unwrap();
+    assert!(core.search("大阪城", 10).unwrap().is_empty());
+    core.restore(id).unwrap();
+    assert_eq!(ids(core.search("大阪城", 10).unwrap()), [id]);
+    core.delete_permanently(id).unwrap();
+
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    let count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM items_trigram", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn test_trigram_index_backfilled_on_upgrade() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+
+    // Database from before the trigram index
+    {
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        for sql in [
+            include_str!("../migrations/0001_init.sql"),
+            include_str!("../migrations/0002_soft_delete.sql"),
+            include_str!("../migrations/0003_item_versions.sql"),
+            include_str!("../migrations/0004_item_annotations.sql"),
+            include_str!("../migrations/0005_snippets.sql"),
+            include_str!("../migrations/0006_collections.sql"),
+            include_str!("../migrations/0007_slots.sql"),
+            include_str!("../migrations/0008_paste_queue.sql"),
+            include_str!("../migrations/0009_uses.sql"),
+            include_str!("../migrations/0010_search_selections.sql"),
+        ] {
+            conn.execute_batch(sql).unwrap();
+        }
+        conn.execute_batch(
+            "PRAGMA user_version = 10;
+             INSERT INTO items (id, kind, content_ref, created_at) VALUES (1, 'text', '서울특별시 강남구', 1000);",
+        )
+        .unwrap();
+    }
+
+    let core = Core::open(&db_path, &blobs_dir).unwrap();
+    assert_eq!(core.search("강남구", 10).unwrap().len(), 1);
+}
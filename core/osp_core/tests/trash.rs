@@ -0,0 +1,143 @@
+//! Tests for soft delete, restore and trash purging.
+
+use osp_core::{Config, Core};
+use tempfile::tempdir;
+
+mod common;
+use common::{open, text_item};
+
+#[test]
+fn test_delete_moves_to_trash() {
+    let (_dir, core) = open();
+    let id = core.add_item(text_item("recoverable snippet", 1000)).unwrap();
+
+    core.delete(id).unwrap();
+
+    // Hidden from get and search
+    assert!(core.get(id).is_err());
+    assert!(core.search("recoverable", 10).unwrap().is_empty());
+    assert!(core.search("re", 10).unwrap().is_empty());
+
+    // Listed in the trash
+    let trash = core.trash_list(10).unwrap();
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0].id, id);
+    assert!(trash[0].deleted_at.is_some());
+
+    // Restore brings it back, searchable again
+    core.restore(id).unwrap();
+    assert!(core.get(id).unwrap().deleted_at.is_none());
+    assert_eq!(core.search("recoverable", 10).unwrap().len(), 1);
+    assert!(core.trash_list(10).unwrap().is_empty());
+
+    // Restoring an item that isn't trashed fails
+    assert!(core.restore(id).is_err());
+}
+
+#[test]
+fn test_trashed_items_excluded_from_dedupe() {
+    let (_dir, core) = open();
+    let id1 = core.dedupe_insert(text_item("copied twice", 1000)).unwrap().unwrap();
+    core.delete(id1).unwrap();
+
+    // Copying the same content again creates a fresh item
+    let id2 = core.dedupe_insert(text_item("copied twice", 2000)).unwrap().unwrap();
+    assert_ne!(id1, id2);
+    assert_eq!(core.trash_list(10).unwrap()[0].created_at, 1000);
+}
+
+#[test]
+fn test_empty_trash() {
+    let (_dir, core) = open();
+    let kept = core.add_item(text_item("kept item", 1000)).unwrap();
+    for i in 0..3 {
+        let id = core.add_item(text_item(&format!("trashed item {}", i), i)).unwrap();
+        core.delete(id).unwrap();
+    }
+
+    assert_eq!(core.empty_trash().unwrap(), 3);
+    assert!(core.trash_list(10).unwrap().is_empty());
+    assert!(core.get(kept).is_ok());
+}
+
+#[test]
+fn test_delete_permanently() {
+    let (_dir, core) = open();
+    let id = core.add_item(text_item("gone for good", 1000)).unwrap();
+
+    core.delete_permanently(id).unwrap();
+    assert!(core.get(id).is_err());
+    assert!(core.trash_list(10).unwrap().is_empty());
+    assert!(core.restore(id).is_err());
+}
+
+#[test]
+fn test_clear_history_keeps_pinned() {
+    let (_dir, core) = open();
+    let pinned = core.add_item(text_item("pinned entry", 1000)).unwrap();
+    core.pin(pinned, true).unwrap();
+    core.add_item(text_item("regular entry one", 1001)).unwrap();
+    core.add_item(text_item("regular entry two", 1002)).unwrap();
+
+    assert_eq!(core.clear_history().unwrap(), 2);
+    assert_eq!(core.search("entry", 10).unwrap().len(), 1);
+    assert_eq!(core.trash_list(10).unwrap().len(), 2);
+}
+
+#[test]
+fn test_grace_period_purge() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+
+    // With a zero grace period, trashed items are purged right away
+    let config = Config {
+        trash_grace_period_ms: 0,
+        ..Config::default()
+    };
+    let core = Core::open_with_config(&db_path, &blobs_dir, config).unwrap();
+    let id = core.add_item(text_item("short lived", 1000)).unwrap();
+
+    core.delete(id).unwrap();
+    assert!(core.trash_list(10).unwrap().is_empty());
+    assert!(core.restore(id).is_err());
+}
+
+#[test]
+fn test_trash_survives_reopen() {
+    let (dir, core) = open();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+    let id = core.add_item(text_item("still here", 1000)).unwrap();
+    core.delete(id).unwrap();
+    drop(core);
+
+    // Default grace period keeps it through the purge on open
+    let core = Core::open(&db_path, &blobs_dir).unwrap();
+    assert_eq!(core.trash_list(10).unwrap().len(), 1);
+    core.restore(id).unwrap();
+    assert_eq!(core.get(id).unwrap().content_ref, "still here");
+}
+
+#[test]
+fn test_capture_purges_expired_trash() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let blobs_dir = dir.path().join("blobs");
+
+    let config = Config {
+        trash_grace_period_ms: 200,
+        ..Config::default()
+    };
+    let core = Core::open_with_config(&db_path, &blobs_dir, config).unwrap();
+    assert_eq!(core.trash_grace_period_ms(), 200);
+
+    let id = core.add_item(text_item("expires while running", 1000)).unwrap();
+    core.delete(id).unwrap();
+    assert_eq!(core.trash_list(10).unwrap().len(), 1);
+
+    // Nothing but new captures happens until the period is over
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    core.dedupe_insert(text_item("next copy", 2000)).unwrap();
+    assert!(core.trash_list(10).unwrap().is_empty());
+}
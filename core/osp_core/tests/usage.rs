@@ -0,0 +1,191 @@
+//! Tests for paste tracking and frecency ranking.
+
+use osp_core::{SearchQuery, SortOrder};
+use std::ffi::CString;
+use tempfile::tempdir;
+
+mod common;
+use common::{open, text_item};
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+fn frecency_query(text: &str) -> SearchQuery {
+    SearchQuery {
+        sort: SortOrder::Frecency,
+        ..SearchQuery::new(text, 10)
+    }
+}
+
+fn ids(items: Vec<osp_core::Item>) -> Vec<i64> {
+    items.into_iter().map(|item| item.id).collect()
+}
+
+#[test]
+fn test_frecency_sort() {
+    let (_dir, core) = open();
+    let reused = core.add_item(text_item("ssh deploy@prod", 1000)).unwrap();
+    let once = core.add_item(text_item("ssh deploy@staging", 2000)).unwrap();
+    let never = core.add_item(text_item("ssh deploy@dev", 3000)).unwrap();
+
+    core.record_use(reused, Some("com.apple.Terminal")).unwrap();
+    core.record_use(reused, Some("com.apple.Terminal")).unwrap();
+    core.record_use(once, None).unwrap();
+
+    // Default order is unaffected by uses
+    assert_eq!(ids(core.search("deploy", 10).unwrap())[0], never);
+
+    let results = ids(core.search_with(&frecency_query("deploy")).unwrap());
+    assert_eq!(results, [reused, once, never]);
+
+    // Short queries and empty queries sort the same way
+    let results = ids(core.search_with(&frecency_query("")).unwrap());
+    assert_eq!(results, [reused, once, never]);
+
+    assert!(core.record_use(9999, None).is_err());
+}
+
+#[test]
+fn test_pinned_before_frecency() {
+    let (_dir, core) = open();
+    let used = core.add_item(text_item("token used", 1000)).unwrap();
+    let pinned_old = core.add_item(text_item("token pinned old", 2000)).unwrap();
+    let pinned_used = core.add_item(text_item("token pinned used", 500)).unwrap();
+    core.pin(pinned_old, true).unwrap();
+    core.pin(pinned_used, true).unwrap();
+    core.record_use(used, None).unwrap();
+    core.record_use(used, None).unwrap();
+    core.record_use(pinned_used, None).unwrap();
+
+    // Pinned first, each group by frecency
+    let results = ids(core.search_with(&frecency_query("token")).unwrap());
+    assert_eq!(results, [pinned_used, pinned_old, used]);
+}
+
+#[test]
+fn test_old_uses_weigh_less() {
+    let (dir, core) = open();
+    let db_path = dir.path().join("test.db");
+    let stale = core.add_item(text_item("query stale", 1000)).unwrap();
+    let fresh = core.add_item(text_item("query fresh", 1000)).unwrap();
+
+    // Three uses 100 days ago (3 x 10) lose to one use today (100)
+    {
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+        for _ in 0..3 {
+            conn.execute(
+                "INSERT INTO uses (item_id, used_at) VALUES (?1, ?2)",
+                (stale, now - 100 * DAY_MS),
+            )
+            .unwrap();
+        }
+    }
+    core.record_use(fresh, None).unwrap();
+
+    let results = ids(core.search_with(&frecency_query("query")).unwrap());
+    assert_eq!(results, [fresh, stale]);
+}
+
+#[test]
+fn test_suggest_by_target_app() {
+    let (_dir, core) = open();
+    let key_path = core.add_item(text_item("~/.ssh/id_ed25519.pub", 1000)).unwrap();
+    let host = core.add_item(text_item("deploy@prod", 2000)).unwrap();
+    let signature = core.add_item(text_item("Best regards, Sam", 3000)).unwrap();
+
+    for _ in 0..3 {
+        core.record_use(key_path, Some("com.apple.Terminal")).unwrap();
+    }
+    core.record_use(host, Some("com.apple.Terminal")).unwrap();
+    for _ in 0..5 {
+        core.record_use(signature, Some("com.apple.mail")).unwrap();
+    }
+
+    assert_eq!(
+        ids(core.suggest("com.apple.Terminal", 10).unwrap()),
+        [key_path, host]
+    );
+    assert_eq!(ids(core.suggest("com.apple.mail", 10).unwrap()), [signature]);
+    assert_eq!(ids(core.suggest("com.apple.Terminal", 1).unwrap()), [key_path]);
+    assert!(core.suggest("com.example.Unknown", 10).unwrap().is_empty());
+
+    // Trashed items aren't suggested
+    core.delete(key_path).unwrap();
+    assert_eq!(ids(core.suggest("com.apple.Terminal", 10).unwrap()), [host]);
+}
+
+#[test]
+fn test_suggest_prefers_recent_uses() {
+    let (dir, core) = open();
+    let db_path = dir.path().join("test.db");
+    let old_habit = core.add_item(text_item("old habit", 1000)).unwrap();
+    let new_habit = core.add_item(text_item("new habit", 1000)).unwrap();
+
+    // Five uses 60 days ago (5 x 30) lose to two uses today (2 x 100)
+    {
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+        for _ in 0..5 {
+            conn.execute(
+                "INSERT INTO uses (item_id, target_app, used_at) VALUES (?1, 'Terminal', ?2)",
+                (old_habit, now - 60 * DAY_MS),
+            )
+            .unwrap();
+        }
+    }
+    core.record_use(new_habit, Some("Terminal")).unwrap();
+    core.record_use(new_habit, Some("Terminal")).unwrap();
+
+    assert_eq!(ids(core.suggest("Terminal", 10).unwrap()), [new_habit, old_habit]);
+}
+
+#[test]
+fn test_uses_removed_with_item() {
+    let (dir, core) = open();
+    let db_path = dir.path().join("test.db");
+    let id = core.add_item(text_item("short lived", 1000)).unwrap();
+    core.record_use(id, None).unwrap();
+    core.delete_permanently(id).unwrap();
+
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    let count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM uses", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn test_ffi_search_query() {
+    let dir = tempdir().unwrap();
+    let db_path = CString::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+    let blobs_dir = CString::new(dir.path().join("blobs").to_str().unwrap()).unwrap();
+
+    unsafe {
+        let handle = osp_core::ffi::core_new(db_path.as_ptr(), blobs_dir.as_ptr());
+        assert!(!handle.is_null());
+
+        let content = CString::new("frecent entry").unwrap();
+        let first = osp_core::ffi::core_add_item(handle, 0, content.as_ptr(), std::ptr::null(), 1000);
+        let content = CString::new("newer entry").unwrap();
+        osp_core::ffi::core_add_item(handle, 0, content.as_ptr(), std::ptr::null(), 2000);
+        assert_eq!(osp_core::ffi::core_record_use(handle, first, std::ptr::null()), 0);
+
+        let query = CString::new(r#"{"text": "entry", "sort": "frecency"}"#).unwrap();
+        let results = osp_core::ffi::core_search_query(handle, query.as_ptr());
+        assert!(!results.is_null());
+        assert_eq!((*results).count, 2);
+        assert_eq!((*(*results).items).id, first);
+        osp_core::ffi::item_array_free(results);
+
+        let bad = CString::new(r#"{"sort": "sideways"}"#).unwrap();
+        assert!(osp_core::ffi::core_search_query(handle, bad.as_ptr()).is_null());
+
+        osp_core::ffi::core_free(handle);
+    }
+}
@@ -0,0 +1,89 @@
+//! Tests for editing item content and version history.
+
+use osp_core::ItemKind;
+
+mod common;
+use common::{new_item, open};
+
+#[test]
+fn test_update_content_reindexes() {
+    let (_dir, core) = open();
+    let id = core
+        .add_item(new_item(ItemKind::Text, "kubectl aply -f deploy.yaml", 1000))
+        .unwrap();
+
+    core.update_content(id, "kubectl apply -f deploy.yaml").unwrap();
+
+    assert_eq!(core.get(id).unwrap().content_ref, "kubectl apply -f deploy.yaml");
+    assert_eq!(core.search("apply", 10).unwrap().len(), 1);
+    assert!(core.search("aply", 10).unwrap().is_empty());
+
+    let versions = core.versions(id).unwrap();
+    assert_eq!(versions.len(), 1);
+    assert_eq!(versions[0].version, 1);
+    assert_eq!(versions[0].content_ref, "kubectl aply -f deploy.yaml");
+}
+
+#[test]
+fn test_update_content_recomputes_dedupe_key() {
+    let (_dir, core) = open();
+    let id = core.dedupe_insert(new_item(ItemKind::Text, "old text", 1000)).unwrap().unwrap();
+    core.update_content(id, "new   text").unwrap();
+
+    // The new content now dedupes against the edited item...
+    let dup = core.dedupe_insert(new_item(ItemKind::Text, "new text", 2000)).unwrap();
+    assert_eq!(dup, Some(id));
+
+    // ...and the old content no longer does
+    let fresh = core.dedupe_insert(new_item(ItemKind::Text, "old text", 3000)).unwrap();
+    assert_ne!(fresh, Some(id));
+}
+
+#[test]
+fn test_revert() {
+    let (_dir, core) = open();
+    let id = core.add_item(new_item(ItemKind::Text, "first draft", 1000)).unwrap();
+    core.update_content(id, "second draft").unwrap();
+    core.update_content(id, "third draft").unwrap();
+
+    core.revert(id, 1).unwrap();
+    assert_eq!(core.get(id).unwrap().content_ref, "first draft");
+    assert_eq!(core.search("first", 10).unwrap().len(), 1);
+
+    // The reverted-from content was kept as a new version
+    let versions = core.versions(id).unwrap();
+    let contents: Vec<&str> = versions.iter().map(|v| v.content_ref.as_str()).collect();
+    assert_eq!(contents, ["first draft", "second draft", "third draft"]);
+    assert_eq!(versions.last().unwrap().version, 3);
+
+    assert!(core.revert(id, 99).is_err());
+}
+
+#[test]
+fn test_update_content_edge_cases() {
+    let (_dir, core) = open();
+
+    // Unchanged content records nothing
+    let id = core.add_item(new_item(ItemKind::Text, "same", 1000)).unwrap();
+    core.update_content(id, "same").unwrap();
+    assert!(core.versions(id).unwrap().is_empty());
+
+    // Images and files can't be edited
+    let image = core.add_item(new_item(ItemKind::Image, "/tmp/a.png", 1000)).unwrap();
+    assert!(core.update_content(image, "/tmp/b.png").is_err());
+
+    // Missing and trashed items can't be edited
+    assert!(core.update_content(9999, "text").is_err());
+    core.delete(id).unwrap();
+    assert!(core.update_content(id, "changed").is_err());
+}
+
+#[test]
+fn test_versions_removed_with_item() {
+    let (_dir, core) = open();
+    let id = core.add_item(new_item(ItemKind::Text, "v1", 1000)).unwrap();
+    core.update_content(id, "v2").unwrap();
+
+    core.delete_permanently(id).unwrap();
+    assert!(core.versions(id).unwrap().is_empty());
+}
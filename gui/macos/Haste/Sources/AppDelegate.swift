@@ -84,6 +84,27 @@ class AppDelegate: NSObject, NSApplicationDelegate {
         }
         
         self.core = core
+        
+        if let report = core.recoveryReport {
+            showRecoveryReport(report)
+        }
+    }
+    
+    private func showRecoveryReport(_ report: CoreBridge.RecoveryReport) {
+        let lost = report.tables.filter { $0.dropped > 0 || !$0.errors.isEmpty }
+        var details = "\(report.itemsRecovered) items were recovered."
+        if !lost.isEmpty {
+            details += " Some data could not be recovered from: "
+                + lost.map { $0.table }.joined(separator: ", ") + "."
+        }
+        details += "\n\nThe damaged database was kept at \(report.movedTo)"
+        
+        let alert = NSAlert()
+        alert.messageText = "Clipboard history was damaged"
+        alert.informativeText = "Haste repaired its database (\(report.reason)). \(details)"
+        alert.alertStyle = .warning
+        alert.addButton(withTitle: "OK")
+        alert.runModal()
     }
     
     private func setupMenuBar() {
@@ -42,12 +42,44 @@ public class CoreBridge {
         }
     }
     
-    /// Initialize with database path
+    /// What was salvaged when a corrupted database was recovered
+    public struct RecoveryReport: Decodable {
+        public let reason: String
+        public let movedTo: String
+        public let itemsRecovered: Int64
+        public let tables: [TableRecovery]
+        
+        public struct TableRecovery: Decodable {
+            public let table: String
+            public let recovered: Int64
+            public let dropped: Int64
+            public let errors: [String]
+        }
+        
+        enum CodingKeys: String, CodingKey {
+            case reason
+            case movedTo = "moved_to"
+            case itemsRecovered = "items_recovered"
+            case tables
+        }
+    }
+    
+    /// Set if the database was corrupted and had to be recovered on open
+    public private(set) var recoveryReport: RecoveryReport?
+    
+    /// Initialize with database path, recovering the database if it is corrupted
     public init?(dbPath: String, blobsDir: String) {
-        guard let handle = core_new(dbPath, blobsDir) else {
+        var reportPtr: UnsafeMutablePointer<CChar>?
+        guard let handle = core_new_with_recovery(dbPath, blobsDir, nil, &reportPtr) else {
             return nil
         }
         self.handle = handle
+        
+        if let reportPtr = reportPtr {
+            defer { string_free(reportPtr) }
+            let data = Data(String(cString: reportPtr).utf8)
+            recoveryReport = try? JSONDecoder().decode(RecoveryReport.self, from: data)
+        }
     }
     
     deinit {
@@ -138,6 +170,13 @@ public class CoreBridge {
         return core_delete_item(handle, id) == 0
     }
     
+    /// How long trashed items are kept before they are deleted for good
+    public var trashGracePeriod: TimeInterval? {
+        guard let handle = handle else { return nil }
+        let ms = core_trash_grace_period_ms(handle)
+        return ms >= 0 ? TimeInterval(ms) / 1000 : nil
+    }
+    
     /// Pin or unpin an item
     public func pinItem(id: Int64, pinned: Bool) -> Bool {
         guard let handle = handle else { return false }
@@ -325,7 +325,15 @@ class SearchWindow: NSObject, NSWindowDelegate, NSCollectionViewDataSource, NSCo
     @objc private func clearHistory() {
         let alert = NSAlert()
         alert.messageText = "Clear Clipboard History?"
-        alert.informativeText = "This will permanently delete all clipboard items. This action cannot be undone."
+        alert.informativeText = "This will move all clipboard items to the trash."
+        if let period = core.trashGracePeriod {
+            let formatter = DateComponentsFormatter()
+            formatter.unitsStyle = .full
+            formatter.maximumUnitCount = 1
+            if let text = formatter.string(from: period) {
+                alert.informativeText += " They are deleted for good after \(text)."
+            }
+        }
         alert.alertStyle = .warning
         alert.addButton(withTitle: "Clear")
         alert.addButton(withTitle: "Cancel")